@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+use std::fs::{create_dir_all, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use database::model::library_entry::{
+    CreateModel as LibraryEntryCreateModel, Model as LibraryEntry, RepeatMode, Variant,
+};
+use database::model::spotify_config::Model as SpotifyConfig;
+use database::model::system_config::Model as SystemConfig;
+use database::model::track_source::CreateModel as TrackSourceCreateModel;
+use database::MediaStore;
+
+// Increase on incompatible changes of the manifest. Backups of newer versions are refused.
+const BACKUP_VERSION: u32 = 1;
+const MANIFEST_NAME: &str = "backup.json";
+
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    created_at: String,
+    library: Vec<BackupEntry>,
+    system_config: Option<serde_json::Value>,
+    spotify_config: Option<serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BackupEntry {
+    variant: Variant,
+    name: String,
+    // Path of the image inside the archive
+    image: Option<String>,
+    sort_key: i32,
+    #[serde(default)]
+    shuffle: bool,
+    #[serde(default)]
+    repeat_mode: RepeatMode,
+    #[serde(default)]
+    children: Vec<BackupEntry>,
+    track_source: Option<BackupTrackSource>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BackupTrackSource {
+    title: String,
+    url: Option<String>,
+    // Path of the audio file inside the archive
+    file: Option<String>,
+    spotify_id: Option<String>,
+    spotify_type: Option<String>,
+    // Episodes of podcasts are not part of the backup, the feed is fetched again after restoring it
+    #[serde(default)]
+    episode_limit: Option<i32>,
+}
+
+/// Content of a backup archive, ready to be written to the database
+pub struct Restore {
+    pub entries: Vec<LibraryEntryCreateModel>,
+    pub system_config: Option<serde_json::Value>,
+    pub spotify_config: Option<serde_json::Value>,
+}
+
+/// Writes the library with all its images and audio files and the configs into a zip archive. Runs blocking.
+pub fn export(
+    library: Vec<LibraryEntry>,
+    system_config: Option<SystemConfig>,
+    spotify_config: Option<SpotifyConfig>,
+) -> Result<PathBuf, String> {
+    let folder = PathBuf::from(std::env::var("CACHE").unwrap_or("/tmp/tgb".to_string()));
+    create_dir_all(&folder).map_err(|error| format!("could not create cache folder: {}", error))?;
+    let path = folder.join(format!("backup-{}.zip", chrono::Utc::now().timestamp_millis()));
+    let file = File::create(&path).map_err(|error| format!("could not create backup file: {}", error))?;
+
+    let mut writer = BackupWriter { zip: ZipWriter::new(file), image_count: 0, written_files: HashMap::new() };
+    let library = library.into_iter().map(|entry| writer.add_entry(entry)).collect::<Result<Vec<_>, _>>()?;
+    let manifest = Manifest {
+        version: BACKUP_VERSION,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        library,
+        system_config: system_config.map(serde_json::to_value).transpose().map_err(|error| error.to_string())?,
+        spotify_config: spotify_config.map(serde_json::to_value).transpose().map_err(|error| error.to_string())?,
+    };
+
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    writer.zip.start_file(MANIFEST_NAME, options).map_err(|error| error.to_string())?;
+    serde_json::to_writer_pretty(&mut writer.zip, &manifest).map_err(|error| error.to_string())?;
+    writer.zip.finish().map_err(|error| format!("could not write backup file: {}", error))?;
+
+    info!("Exported {} audio files and {} images", writer.written_files.len(), writer.image_count);
+    Ok(path)
+}
+
+/// Reads the backup archive and moves its audio files into the media store. Runs blocking.
+pub fn read(path: &Path) -> Result<Restore, String> {
+    let file = File::open(path).map_err(|error| format!("could not open backup: {}", error))?;
+    let mut zip = ZipArchive::new(file).map_err(|error| format!("could not read backup: {}", error))?;
+
+    let manifest: Manifest = {
+        let manifest = zip.by_name(MANIFEST_NAME).map_err(|_| "backup contains no manifest".to_string())?;
+        serde_json::from_reader(manifest).map_err(|error| format!("could not parse manifest: {}", error))?
+    };
+    if manifest.version > BACKUP_VERSION {
+        return Err(format!("backup version {} is not supported, update the box first", manifest.version));
+    }
+    info!("Restoring backup version {} created at {}", manifest.version, manifest.created_at);
+
+    let mut reader = BackupReader { zip, stored_files: HashMap::new() };
+    let entries = manifest.library.into_iter().map(|entry| reader.read_entry(entry)).collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Restore { entries, system_config: manifest.system_config, spotify_config: manifest.spotify_config })
+}
+
+struct BackupWriter {
+    zip: ZipWriter<File>,
+    image_count: usize,
+    // Files of the media store are only added once, even if used by multiple entries
+    written_files: HashMap<String, String>,
+}
+
+impl BackupWriter {
+    fn add_entry(&mut self, entry: LibraryEntry) -> Result<BackupEntry, String> {
+        let image = match entry.image {
+            Some(image) => {
+                self.image_count += 1;
+                let name = format!("images/{}", self.image_count);
+                self.zip.start_file(name.as_str(), SimpleFileOptions::default()).map_err(|error| error.to_string())?;
+                self.zip.write_all(&image).map_err(|error| format!("could not write image: {}", error))?;
+                Some(name)
+            }
+            None => None,
+        };
+        let track_source = match entry.track_source {
+            Some(track_source) => Some(BackupTrackSource {
+                title: track_source.title,
+                url: track_source.url,
+                file: track_source.file_hash.map(|hash| self.add_file(hash)).transpose()?,
+                spotify_id: track_source.spotify_id,
+                spotify_type: track_source.spotify_type,
+                episode_limit: track_source.episode_limit,
+            }),
+            None => None,
+        };
+        let children = entry.children.unwrap_or_default().into_iter().map(|child| self.add_entry(child));
+
+        Ok(BackupEntry {
+            variant: entry.variant,
+            name: entry.name,
+            image,
+            sort_key: entry.sort_key,
+            shuffle: entry.shuffle,
+            repeat_mode: entry.repeat_mode,
+            children: children.collect::<Result<Vec<_>, _>>()?,
+            track_source,
+        })
+    }
+
+    fn add_file(&mut self, hash: String) -> Result<String, String> {
+        if let Some(name) = self.written_files.get(&hash) {
+            return Ok(name.clone());
+        }
+
+        let name = format!("media/{}", hash);
+        let mut file =
+            File::open(MediaStore::get_path(&hash)).map_err(|error| format!("could not open {}: {}", hash, error))?;
+        // Audio files are compressed already
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored).large_file(true);
+        self.zip.start_file(name.as_str(), options).map_err(|error| error.to_string())?;
+        std::io::copy(&mut file, &mut self.zip).map_err(|error| format!("could not write {}: {}", hash, error))?;
+
+        self.written_files.insert(hash, name.clone());
+        Ok(name)
+    }
+}
+
+struct BackupReader {
+    zip: ZipArchive<File>,
+    // Maps the paths inside the archive to the hashes in the media store
+    stored_files: HashMap<String, String>,
+}
+
+impl BackupReader {
+    fn read_entry(&mut self, entry: BackupEntry) -> Result<LibraryEntryCreateModel, String> {
+        let image = match entry.image {
+            Some(name) => {
+                let mut image = vec![];
+                let mut file =
+                    self.zip.by_name(&name).map_err(|error| format!("could not read {}: {}", name, error))?;
+                file.read_to_end(&mut image).map_err(|error| format!("could not read {}: {}", name, error))?;
+                Some(image)
+            }
+            None => None,
+        };
+        let track_source = match entry.track_source {
+            Some(track_source) => Some(TrackSourceCreateModel {
+                title: track_source.title,
+                url: track_source.url,
+                file: None,
+                file_hash: track_source.file.map(|name| self.store_file(name)).transpose()?,
+                spotify_id: track_source.spotify_id,
+                spotify_type: track_source.spotify_type,
+                published_at: None,
+                episode_limit: track_source.episode_limit,
+            }),
+            None => None,
+        };
+        let children = entry.children.into_iter().map(|child| self.read_entry(child)).collect::<Result<Vec<_>, _>>()?;
+
+        Ok(LibraryEntryCreateModel {
+            parent_id: None,
+            variant: entry.variant,
+            name: entry.name,
+            image,
+            sort_key: entry.sort_key,
+            shuffle: entry.shuffle,
+            repeat_mode: entry.repeat_mode,
+            children: Some(children),
+            track_source,
+        })
+    }
+
+    fn store_file(&mut self, name: String) -> Result<String, String> {
+        if let Some(hash) = self.stored_files.get(&name) {
+            return Ok(hash.clone());
+        }
+
+        let mut file = self.zip.by_name(&name).map_err(|error| format!("could not read {}: {}", name, error))?;
+        let hash = MediaStore::add_from_reader(&mut file).map_err(|error| error.to_string())?;
+
+        self.stored_files.insert(name, hash.clone());
+        Ok(hash)
+    }
+}
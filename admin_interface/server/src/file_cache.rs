@@ -14,7 +14,7 @@ pub struct InnerCache {
 
 impl InnerCache {
     pub fn get(&mut self, name: String) -> Result<Vec<u8>, String> {
-        if self.cached_files.get(&name).is_none() {
+        if !self.cached_files.contains_key(&name) {
             return Err(format!("no uploaded file found for {}", name));
         }
 
@@ -50,7 +50,7 @@ impl FileCache {
         info!("Checking cache file lifetimessss");
         let path = Path::new(&cache_folder);
         if !path.exists() {
-            create_dir_all(&path).expect("could not create cache dir");
+            create_dir_all(path).expect("could not create cache dir");
         }
 
         let cache = FileCache {
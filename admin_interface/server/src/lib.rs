@@ -0,0 +1,11 @@
+pub mod backup;
+pub mod commands;
+pub mod error;
+pub mod file_cache;
+pub mod library_import;
+pub mod loudness;
+pub mod metadata;
+pub mod offline;
+pub mod player_client;
+pub mod podcast;
+pub mod routes;
@@ -0,0 +1,325 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{read_dir, File};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use actix_multipart::form::tempfile::TempFile;
+use serde::Serialize;
+use tracing::{error, info};
+use zip::ZipArchive;
+
+use database::model::library_entry::{CreateModel as LibraryEntryCreateModel, RepeatMode, Variant};
+use database::model::track_source::CreateModel as TrackSourceCreateModel;
+use database::{DatabaseConnection, LibraryEntryRepository, MediaStore};
+
+use crate::loudness::LoudnessAnalyzer;
+use crate::metadata::AudioMetadata;
+
+const AUDIO_EXTENSIONS: [&str; 8] = ["mp3", "m4a", "mp4", "aac", "flac", "ogg", "opus", "wav"];
+// The web ui polls the progress until the import ended, so finished imports are only kept this long afterward
+const FINISHED_JOB_TTL: Duration = Duration::from_secs(60 * 60);
+
+pub enum ImportSource {
+    Archive(TempFile),
+    Directory(PathBuf),
+}
+
+#[derive(Serialize, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportState {
+    StoringFiles,
+    CreatingEntries,
+    Done,
+    Failed,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ImportProgress {
+    pub state: ImportState,
+    pub total_files: usize,
+    pub stored_files: usize,
+    pub total_entries: usize,
+    pub created_entries: usize,
+    pub error: Option<String>,
+    #[serde(skip)]
+    finished_at: Option<Instant>,
+}
+
+struct ImportedFile {
+    folders: Vec<String>,
+    file_name: String,
+    hash: String,
+    title: Option<String>,
+    track_number: Option<u32>,
+}
+
+/// Keeps track of running imports, so the web ui can poll their progress
+#[derive(Clone, Default)]
+pub struct ImportJobs {
+    jobs: Arc<Mutex<HashMap<u32, ImportProgress>>>,
+    next_id: Arc<AtomicU32>,
+}
+
+impl ImportJobs {
+    pub fn get(&self, id: u32) -> Option<ImportProgress> {
+        self.jobs.lock().expect("couldn't lock").get(&id).cloned()
+    }
+
+    /// Imports all audio files of the source into the parent in background and returns the id to poll progress with.
+    /// Imports which finished a while ago are dropped meanwhile.
+    pub fn start(
+        &self,
+        conn: DatabaseConnection,
+        loudness_analyzer: LoudnessAnalyzer,
+        parent_id: Option<i32>,
+        source: ImportSource,
+    ) -> u32 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut jobs = self.jobs.lock().expect("couldn't lock");
+        jobs.retain(|_, job| job.finished_at.is_none_or(|finished_at| finished_at.elapsed() < FINISHED_JOB_TTL));
+        jobs.insert(
+            id,
+            ImportProgress {
+                state: ImportState::StoringFiles,
+                total_files: 0,
+                stored_files: 0,
+                total_entries: 0,
+                created_entries: 0,
+                error: None,
+                finished_at: None,
+            },
+        );
+        drop(jobs);
+
+        let jobs = self.clone();
+        tokio::spawn(async move {
+            match jobs.run(id, conn, parent_id, source).await {
+                Ok(_) => {
+                    jobs.update(id, |progress| {
+                        progress.state = ImportState::Done;
+                        progress.finished_at = Some(Instant::now());
+                    });
+                    loudness_analyzer.trigger();
+                }
+                Err(error) => {
+                    error!("Import {} failed: {}", id, error);
+                    jobs.update(id, |progress| {
+                        progress.state = ImportState::Failed;
+                        progress.error = Some(error);
+                        progress.finished_at = Some(Instant::now());
+                    });
+                }
+            }
+        });
+
+        id
+    }
+
+    fn update(&self, id: u32, update: impl FnOnce(&mut ImportProgress)) {
+        if let Some(progress) = self.jobs.lock().expect("couldn't lock").get_mut(&id) {
+            update(progress);
+        }
+    }
+
+    async fn run(
+        &self,
+        id: u32,
+        conn: DatabaseConnection,
+        parent_id: Option<i32>,
+        source: ImportSource,
+    ) -> Result<(), String> {
+        // The stored files are not used by any track source until all entries are created. Files of a failed import
+        // are removed by the next cleanup after the hold is released.
+        let _hold = MediaStore::hold_for_import();
+        let jobs = self.clone();
+        let files = tokio::task::spawn_blocking(move || jobs.store_files(id, source))
+            .await
+            .map_err(|error| format!("storing files was aborted: {}", error))??;
+        if files.is_empty() {
+            return Err("no audio files found".to_string());
+        }
+
+        let entries = FolderNode::from_files(files).into_entries();
+        let total_entries = count_entries(&entries);
+        info!("Import {} creates {} entries", id, total_entries);
+        self.update(id, |progress| {
+            progress.state = ImportState::CreatingEntries;
+            progress.total_entries = total_entries;
+        });
+
+        let jobs = self.clone();
+        let on_progress = move |created: usize| jobs.update(id, |progress| progress.created_entries = created);
+        LibraryEntryRepository::create_with_progress(&conn, parent_id, entries, &on_progress)
+            .await
+            .map_err(|error| format!("could not create entries: {}", error))?;
+
+        Ok(())
+    }
+
+    /// Copies the audio files into the media store. Runs blocking, as neither zip nor file system access is async.
+    fn store_files(&self, id: u32, source: ImportSource) -> Result<Vec<ImportedFile>, String> {
+        let mut files = vec![];
+        match source {
+            ImportSource::Archive(archive) => {
+                let archive = File::open(archive.file.path()).map_err(|error| format!("could not open archive: {}", error))?;
+                let mut archive = ZipArchive::new(archive).map_err(|error| format!("could not read archive: {}", error))?;
+
+                let paths = (0..archive.len())
+                    .filter_map(|index| {
+                        let entry = archive.by_index(index).ok()?;
+                        let path = entry.enclosed_name().filter(|path| !entry.is_dir() && is_audio_file(path))?;
+                        Some((index, path))
+                    })
+                    .collect::<Vec<(usize, PathBuf)>>();
+                self.update(id, |progress| progress.total_files = paths.len());
+
+                for (index, path) in paths {
+                    let mut entry =
+                        archive.by_index(index).map_err(|error| format!("could not read {:?}: {}", path, error))?;
+                    let hash = MediaStore::add_from_reader(&mut entry).map_err(|error| error.to_string())?;
+                    files.push(ImportedFile::new(&path, hash));
+                    self.update(id, |progress| progress.stored_files = files.len());
+                }
+            }
+            ImportSource::Directory(root) => {
+                let mut paths = vec![];
+                find_audio_files(&root, Path::new(""), &mut paths)?;
+                self.update(id, |progress| progress.total_files = paths.len());
+
+                for path in paths {
+                    let mut file =
+                        File::open(root.join(&path)).map_err(|error| format!("could not open {:?}: {}", path, error))?;
+                    let hash = MediaStore::add_from_reader(&mut file).map_err(|error| error.to_string())?;
+                    files.push(ImportedFile::new(&path, hash));
+                    self.update(id, |progress| progress.stored_files = files.len());
+                }
+            }
+        }
+        Ok(files)
+    }
+}
+
+impl ImportedFile {
+    fn new(path: &Path, hash: String) -> Self {
+        let file_name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        let folders = path
+            .parent()
+            .map(|parent| parent.iter().map(|folder| folder.to_string_lossy().to_string()).collect())
+            .unwrap_or_default();
+        let metadata = AudioMetadata::from_file(&MediaStore::get_path(&hash), &file_name).unwrap_or_default();
+
+        Self {
+            folders,
+            file_name,
+            hash,
+            title: metadata.title,
+            track_number: metadata.track_number,
+        }
+    }
+
+    fn get_name(&self) -> String {
+        self.title.clone().unwrap_or_else(|| {
+            Path::new(&self.file_name).file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default()
+        })
+    }
+
+    /// Tracks are ordered by their track number. Without tags, file names like "01 - Intro.mp3" are numbered as well.
+    fn get_sort_number(&self) -> u32 {
+        self.track_number
+            .or_else(|| {
+                let digits = self.file_name.chars().take_while(|char| char.is_ascii_digit()).collect::<String>();
+                digits.parse().ok()
+            })
+            .unwrap_or(u32::MAX)
+    }
+}
+
+#[derive(Default)]
+struct FolderNode {
+    folders: BTreeMap<String, FolderNode>,
+    files: Vec<ImportedFile>,
+}
+
+impl FolderNode {
+    fn from_files(files: Vec<ImportedFile>) -> Self {
+        let mut root = FolderNode::default();
+        for file in files {
+            let mut node = &mut root;
+            for folder in file.folders.iter() {
+                node = node.folders.entry(folder.clone()).or_default();
+            }
+            node.files.push(file);
+        }
+        root
+    }
+
+    /// Folders come first ordered by name, followed by the tracks
+    fn into_entries(mut self) -> Vec<LibraryEntryCreateModel> {
+        self.files.sort_by(|a, b| (a.get_sort_number(), &a.file_name).cmp(&(b.get_sort_number(), &b.file_name)));
+
+        let folders = self.folders.into_iter().map(|(name, node)| (name, Variant::Folder, Some(node.into_entries()), None));
+        let files = self.files.into_iter().map(|file| {
+            let track_source = TrackSourceCreateModel {
+                title: file.file_name.clone(),
+                url: None,
+                file: None,
+                file_hash: Some(file.hash.clone()),
+                spotify_id: None,
+                spotify_type: None,
+                published_at: None,
+                episode_limit: None,
+            };
+            (file.get_name(), Variant::File, None, Some(track_source))
+        });
+
+        folders
+            .chain(files)
+            .enumerate()
+            .map(|(sort_key, (name, variant, children, track_source))| LibraryEntryCreateModel {
+                parent_id: None,
+                variant,
+                name,
+                image: None,
+                sort_key: sort_key as i32,
+                shuffle: false,
+                repeat_mode: RepeatMode::Off,
+                children,
+                track_source,
+            })
+            .collect()
+    }
+}
+
+fn count_entries(entries: &[LibraryEntryCreateModel]) -> usize {
+    entries.iter().map(|entry| 1 + entry.children.as_ref().map(|children| count_entries(children)).unwrap_or(0)).sum()
+}
+
+// Skips hidden files and the resource forks macOS adds to archives
+fn is_audio_file(path: &Path) -> bool {
+    let hidden = path.iter().any(|part| {
+        let part = part.to_string_lossy();
+        part.starts_with('.') || part == "__MACOSX"
+    });
+    let extension = path.extension().map(|extension| extension.to_string_lossy().to_lowercase()).unwrap_or_default();
+
+    !hidden && AUDIO_EXTENSIONS.contains(&extension.as_str())
+}
+
+fn find_audio_files(root: &Path, relative: &Path, paths: &mut Vec<PathBuf>) -> Result<(), String> {
+    let folder = root.join(relative);
+    let entries = read_dir(&folder).map_err(|error| format!("could not read {:?}: {}", folder, error))?;
+    for entry in entries.flatten() {
+        let path = relative.join(entry.file_name());
+        // Links to folders are not followed, as they may point to a parent and recurse forever
+        let is_dir =
+            entry.file_type().map_err(|error| format!("could not read {:?}: {}", entry.path(), error))?.is_dir();
+        if is_dir {
+            find_audio_files(root, &path, paths)?;
+        } else if is_audio_file(&path) {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,370 @@
+use std::collections::HashSet;
+use std::f64::consts::PI;
+use std::fs::File;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::DecoderOptions;
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+use tokio::sync::Notify;
+use tracing::{error, info, warn};
+
+use database::{DatabaseConnection, DbErr, LibraryEntryRepository, MediaStore, TrackSourceRepository};
+
+// Loudness the gains lead to, the one of ReplayGain 2.0
+const REFERENCE_LOUDNESS: f64 = -18.0;
+// Loudness is measured in blocks of 400ms, which overlap by 75%
+const BLOCK_STEPS: usize = 4;
+const STEPS_PER_SECOND: u32 = 10;
+// Blocks below these thresholds are silence or quiet passages, which are ignored like in EBU R128
+const ABSOLUTE_GATE: f64 = -70.0;
+const RELATIVE_GATE: f64 = -10.0;
+
+/// Analyzes the loudness of uploaded files in background and stores the gains which normalize it
+#[derive(Clone)]
+pub struct LoudnessAnalyzer {
+    pending: Arc<Notify>,
+}
+
+impl LoudnessAnalyzer {
+    /// Analyzes all files which are not analyzed yet, e.g. after an update, and waits for new ones afterwards
+    pub fn start(conn: DatabaseConnection) -> Self {
+        let analyzer = Self { pending: Arc::new(Notify::new()) };
+
+        let pending = analyzer.pending.clone();
+        tokio::spawn(async move {
+            // Files which can not be decoded are not tried again until the next start
+            let failed = Arc::new(Mutex::new(HashSet::new()));
+            loop {
+                if let Err(error) = analyze_pending(&conn, &failed).await {
+                    error!("Failed to analyze loudness: {:?}", error);
+                }
+                pending.notified().await;
+            }
+        });
+
+        analyzer
+    }
+
+    /// Lets the analysis check for new files
+    pub fn trigger(&self) {
+        self.pending.notify_one();
+    }
+}
+
+async fn analyze_pending(conn: &DatabaseConnection, failed: &Arc<Mutex<HashSet<i32>>>) -> Result<(), DbErr> {
+    let track_sources = TrackSourceRepository::get_unanalyzed(conn)
+        .await?
+        .into_iter()
+        .filter(|track_source| !failed.lock().unwrap().contains(&track_source.id))
+        .collect::<Vec<_>>();
+    if track_sources.is_empty() {
+        return Ok(());
+    }
+    info!("Analyzing loudness of {} files", track_sources.len());
+
+    let mut folder_ids = HashSet::new();
+    for track_source in track_sources {
+        let path = match track_source.file_hash.as_ref() {
+            Some(hash) => MediaStore::get_path(hash),
+            None => continue,
+        };
+
+        let loudness = match tokio::task::spawn_blocking(move || get_loudness(&path)).await {
+            Ok(Ok(loudness)) => loudness,
+            Ok(Err(error)) => {
+                warn!("Could not analyze loudness of track source {}: {}", track_source.id, error);
+                failed.lock().unwrap().insert(track_source.id);
+                continue;
+            }
+            Err(error) => {
+                error!("Loudness analysis of track source {} failed: {}", track_source.id, error);
+                failed.lock().unwrap().insert(track_source.id);
+                continue;
+            }
+        };
+        TrackSourceRepository::set_gain(conn, track_source.id, REFERENCE_LOUDNESS - loudness).await?;
+
+        if let Some(parent_id) = LibraryEntryRepository::get_flat(conn, track_source.library_entry_id)
+            .await?
+            .and_then(|entry| entry.parent_id)
+        {
+            folder_ids.insert(parent_id);
+        }
+    }
+
+    for folder_id in folder_ids {
+        let gains = LibraryEntryRepository::get_tracks_in_parent(conn, folder_id)
+            .await?
+            .iter()
+            .filter_map(|entry| entry.track_source.as_ref()?.gain)
+            .collect::<Vec<f64>>();
+        LibraryEntryRepository::set_album_gain(conn, folder_id, get_album_gain(&gains)).await?;
+    }
+    info!("Analyzed loudness");
+
+    Ok(())
+}
+
+/// Combines the gains of the tracks to one for the whole folder. The loudness of the tracks is averaged by its energy,
+/// which is close to measuring all tracks at once.
+fn get_album_gain(gains: &[f64]) -> Option<f64> {
+    if gains.is_empty() {
+        return None;
+    }
+    let energy = gains.iter().map(|gain| 10f64.powf((REFERENCE_LOUDNESS - gain) / 10.0)).sum::<f64>();
+    let loudness = 10.0 * (energy / gains.len() as f64).log10();
+    Some(REFERENCE_LOUDNESS - loudness)
+}
+
+/// Decodes the file and returns its integrated loudness in LUFS as defined by ITU-R BS.1770
+fn get_loudness(path: &Path) -> Result<f64, String> {
+    let file = File::open(path).map_err(|error| format!("could not open file: {}", error))?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut format = symphonia::default::get_probe()
+        .format(&Hint::new(), stream, &FormatOptions::default(), &MetadataOptions::default())
+        .map_err(|error| format!("unsupported audio file: {}", error))?
+        .format;
+
+    let track = format.default_track().ok_or("file contains no audio track".to_string())?;
+    let track_id = track.id;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|error| format!("unsupported codec: {}", error))?;
+
+    let mut meter: Option<LoudnessMeter> = None;
+    let mut buffer: Option<SampleBuffer<f32>> = None;
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(error)) if error.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(error) => return Err(format!("could not read file: {}", error)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // Single broken frames are skipped like a player would do
+            Err(SymphoniaError::DecodeError(_)) => continue,
+            Err(error) => return Err(format!("could not decode file: {}", error)),
+        };
+        let spec = *decoded.spec();
+        if buffer.as_ref().is_none_or(|buffer| buffer.capacity() < decoded.capacity() * spec.channels.count()) {
+            buffer = Some(SampleBuffer::new(decoded.capacity() as u64, spec));
+        }
+        let buffer = buffer.as_mut().expect("buffer is created above");
+        buffer.copy_interleaved_ref(decoded);
+
+        meter.get_or_insert_with(|| LoudnessMeter::new(spec.rate, spec.channels.count())).add(buffer.samples());
+    }
+
+    meter.and_then(|meter| meter.get_loudness()).ok_or("file contains no audible audio".to_string())
+}
+
+/// Second order IIR filter
+#[derive(Clone, Default)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 3],
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+impl Biquad {
+    fn process(&mut self, sample: f64) -> f64 {
+        let output = self.b[0] * sample + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[1] * self.y[0]
+            - self.a[2] * self.y[1];
+        self.x = [sample, self.x[0]];
+        self.y = [output, self.y[0]];
+        output
+    }
+}
+
+/// Applies the K-weighting of BS.1770, a high shelf modelling the head followed by a high pass, for any sample rate
+fn get_k_weighting(rate: u32) -> [Biquad; 2] {
+    let rate = rate as f64;
+
+    let (frequency, gain, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+    let k = (PI * frequency / rate).tan();
+    let vh = 10f64.powf(gain / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b: [(vh + vb * k / q + k * k) / a0, 2.0 * (k * k - vh) / a0, (vh - vb * k / q + k * k) / a0],
+        a: [1.0, 2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        ..Default::default()
+    };
+
+    let (frequency, q) = (38.13547087602444, 0.5003270373238773);
+    let k = (PI * frequency / rate).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+        b: [1.0, -2.0, 1.0],
+        a: [1.0, 2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        ..Default::default()
+    };
+
+    [shelf, high_pass]
+}
+
+/// Measures the integrated loudness of interleaved samples. All channels are weighted equally, which is right for
+/// mono and stereo files, the ones expected on the box.
+struct LoudnessMeter {
+    filters: Vec<[Biquad; 2]>,
+    step_length: usize,
+    // Energy of the running step, summed over all channels
+    step_energy: f64,
+    step_frames: usize,
+    // Mean energy of every completed step
+    steps: Vec<f64>,
+}
+
+impl LoudnessMeter {
+    fn new(rate: u32, channels: usize) -> Self {
+        Self {
+            filters: vec![get_k_weighting(rate); channels],
+            step_length: (rate / STEPS_PER_SECOND) as usize,
+            step_energy: 0.0,
+            step_frames: 0,
+            steps: vec![],
+        }
+    }
+
+    fn add(&mut self, samples: &[f32]) {
+        let channels = self.filters.len();
+        for frame in samples.chunks_exact(channels) {
+            for (sample, filters) in frame.iter().zip(self.filters.iter_mut()) {
+                let weighted = filters.iter_mut().fold(*sample as f64, |sample, filter| filter.process(sample));
+                self.step_energy += weighted * weighted;
+            }
+
+            self.step_frames += 1;
+            if self.step_frames == self.step_length {
+                self.steps.push(self.step_energy / self.step_length as f64);
+                self.step_energy = 0.0;
+                self.step_frames = 0;
+            }
+        }
+    }
+
+    fn get_loudness(&self) -> Option<f64> {
+        let blocks = self
+            .steps
+            .windows(BLOCK_STEPS)
+            .map(|steps| steps.iter().sum::<f64>() / BLOCK_STEPS as f64)
+            .filter(|energy| to_loudness(*energy) > ABSOLUTE_GATE)
+            .collect::<Vec<f64>>();
+        if blocks.is_empty() {
+            return None;
+        }
+
+        let relative_gate = to_loudness(blocks.iter().sum::<f64>() / blocks.len() as f64) + RELATIVE_GATE;
+        let gated = blocks.into_iter().filter(|energy| to_loudness(*energy) > relative_gate).collect::<Vec<f64>>();
+        Some(to_loudness(gated.iter().sum::<f64>() / gated.len() as f64))
+    }
+}
+
+fn to_loudness(energy: f64) -> f64 {
+    -0.691 + 10.0 * energy.log10()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 48000;
+
+    /// Interleaved samples of a 1 kHz sine with the peak level in dBFS on all channels
+    fn sine(rate: u32, channels: usize, level: f64, seconds: f64) -> Vec<f32> {
+        let amplitude = 10f64.powf(level / 20.0);
+        (0..(rate as f64 * seconds) as usize)
+            .flat_map(|index| {
+                let sample = amplitude * (2.0 * PI * 1000.0 * index as f64 / rate as f64).sin();
+                vec![sample as f32; channels]
+            })
+            .collect()
+    }
+
+    fn measure(rate: u32, channels: usize, samples: &[f32]) -> Option<f64> {
+        let mut meter = LoudnessMeter::new(rate, channels);
+        // Files are decoded in packets, so the samples are added in chunks which do not match the steps
+        for chunk in samples.chunks(1153 * channels) {
+            meter.add(chunk);
+        }
+        meter.get_loudness()
+    }
+
+    fn assert_loudness(loudness: Option<f64>, expected: f64) {
+        let loudness = loudness.expect("loudness is measured");
+        assert!((loudness - expected).abs() < 0.5, "loudness is {} instead of {}", loudness, expected);
+    }
+
+    #[test]
+    fn measures_sine_in_one_channel() {
+        // A sine of 1 kHz at 0 dBFS in one channel measures -3.01 LUFS according to BS.1770
+        assert_loudness(measure(RATE, 1, &sine(RATE, 1, -20.0, 5.0)), -23.01);
+        assert_loudness(measure(RATE, 1, &sine(RATE, 1, 0.0, 5.0)), -3.01);
+    }
+
+    #[test]
+    fn sums_loudness_of_channels() {
+        assert_loudness(measure(RATE, 2, &sine(RATE, 2, -20.0, 5.0)), -20.0);
+    }
+
+    #[test]
+    fn measures_independent_of_sample_rate() {
+        assert_loudness(measure(44100, 2, &sine(44100, 2, -20.0, 5.0)), -20.0);
+        assert_loudness(measure(96000, 2, &sine(96000, 2, -20.0, 5.0)), -20.0);
+    }
+
+    #[test]
+    fn ignores_silence_between_sounds() {
+        let mut samples = sine(RATE, 2, -20.0, 5.0);
+        samples.extend(vec![0.0; RATE as usize * 2 * 20]);
+
+        assert_loudness(measure(RATE, 2, &samples), -20.0);
+    }
+
+    #[test]
+    fn ignores_quiet_passages() {
+        let mut samples = sine(RATE, 2, -20.0, 5.0);
+        samples.extend(sine(RATE, 2, -50.0, 20.0));
+
+        assert_loudness(measure(RATE, 2, &samples), -20.0);
+    }
+
+    #[test]
+    fn measures_nothing_in_silence() {
+        assert_eq!(measure(RATE, 2, &vec![0.0; RATE as usize * 2 * 5]), None);
+        assert_eq!(measure(RATE, 2, &sine(RATE, 2, -80.0, 5.0)), None);
+    }
+
+    #[test]
+    fn measures_nothing_shorter_than_a_block() {
+        assert_eq!(measure(RATE, 2, &sine(RATE, 2, -20.0, 0.3)), None);
+    }
+
+    #[test]
+    fn uses_track_gain_as_album_gain_of_identical_tracks() {
+        let album_gain = get_album_gain(&[-4.5, -4.5, -4.5]).unwrap();
+
+        assert!((album_gain - -4.5).abs() < 1e-9, "album gain is {}", album_gain);
+    }
+
+    #[test]
+    fn weights_album_gain_by_energy() {
+        // Tracks 10 dB apart, the louder one dominates the energy
+        let album_gain = get_album_gain(&[-10.0, 0.0]).unwrap();
+
+        assert!((album_gain - -7.4).abs() < 0.05, "album gain is {}", album_gain);
+        assert_eq!(get_album_gain(&[]), None);
+    }
+}
@@ -1,18 +1,18 @@
+use actix_multipart::form::MultipartFormConfig;
 use actix_web::middleware::Logger;
 use actix_web::{web, App, HttpServer};
 use std::time::Duration;
 use tracing::info;
 use tracing::level_filters::LevelFilter;
 
-use crate::file_cache::FileCache;
-use crate::routes::*;
+use admin_interface_server::file_cache::FileCache;
+use admin_interface_server::library_import::ImportJobs;
+use admin_interface_server::loudness::LoudnessAnalyzer;
+use admin_interface_server::offline::OfflineDownloader;
+use admin_interface_server::podcast::PodcastRefresher;
+use admin_interface_server::routes::*;
 use database::connect;
 
-mod commands;
-mod error;
-mod file_cache;
-mod routes;
-
 #[tokio::main]
 async fn main() {
     tracing_subscriber::fmt().with_max_level(LevelFilter::DEBUG).init();
@@ -22,27 +22,56 @@ async fn main() {
 
     let connection = connect().await.expect("Failed to connect to database");
     let file_cache = FileCache::new(cache_folder.clone(), Duration::from_secs(600));
+    let import_jobs = ImportJobs::default();
+    let loudness_analyzer = LoudnessAnalyzer::start(connection.clone());
+    let podcast_refresher = PodcastRefresher::start(connection.clone());
+    let offline_downloader = OfflineDownloader::start(connection.clone());
 
     HttpServer::new(move || {
         App::new()
             .wrap(Logger::new("%r => %s sent %b bytes in %Dms"))
             .service(system_config::get)
             .service(system_config::update)
+            .service(system_config::get_audio)
+            .service(system_config::update_audio)
             .service(spotify::get_config)
             .service(spotify::update_config)
             .service(spotify::auth)
             .service(spotify::callback)
             .service(spotify::search)
             .service(spotify::children)
+            // Registered before get, which would match export as id otherwise
+            .service(library::export)
             .service(library::get)
             .service(library::delete)
             .service(library::create)
             .service(library::update)
+            .service(library::pin)
             .service(library::upload)
+            .service(library::bulk_import)
+            .service(library::get_bulk_import)
+            .service(library::import)
+            .service(card::get_all)
+            .service(card::learn)
+            .service(card::assign)
+            .service(card::delete)
+            .service(player::get_state)
+            .service(player::play)
+            .service(player::pause)
+            .service(player::resume)
+            .service(player::next)
+            .service(player::prev)
+            .service(player::seek)
+            .service(player::set_volume)
             .service(image::proxy_image)
             .service(static_files::get)
             .app_data(web::Data::new(connection.clone()))
             .app_data(web::Data::new(file_cache.clone()))
+            .app_data(web::Data::new(import_jobs.clone()))
+            .app_data(web::Data::new(loudness_analyzer.clone()))
+            .app_data(web::Data::new(podcast_refresher.clone()))
+            .app_data(web::Data::new(offline_downloader.clone()))
+            .app_data(MultipartFormConfig::default().total_limit(2 * 1024 * 1024 * 1024))
             .app_data(web::JsonConfig::default().limit(100 * 1024 * 1024))
     })
     .bind(("0.0.0.0", port))
@@ -0,0 +1,84 @@
+use std::fs::File;
+use std::path::Path;
+
+use serde::Serialize;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::{MetadataOptions, MetadataRevision, StandardTagKey, StandardVisualKey, Value};
+use symphonia::core::probe::Hint;
+
+/// Information read from the tags of an uploaded audio file. Used by the web ui to prefill the library entry.
+#[derive(Serialize, Default, Debug)]
+pub struct AudioMetadata {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub track_number: Option<u32>,
+    pub duration: Option<f64>, // seconds
+    pub image: Option<Vec<u8>>,
+}
+
+impl AudioMetadata {
+    pub fn from_file(path: &Path, name: &str) -> Result<Self, String> {
+        let file = File::open(path).map_err(|error| format!("could not open file: {}", error))?;
+        let stream = MediaSourceStream::new(Box::new(file), Default::default());
+
+        // Uploaded files are stored without extension, so the original name is used as hint
+        let mut hint = Hint::new();
+        if let Some(extension) = Path::new(name).extension().and_then(|extension| extension.to_str()) {
+            hint.with_extension(extension);
+        }
+
+        let mut probed = symphonia::default::get_probe()
+            .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())
+            .map_err(|error| format!("unsupported audio file: {}", error))?;
+
+        let mut metadata = AudioMetadata::default();
+        if let Some(track) = probed.format.default_track() {
+            if let (Some(time_base), Some(frames)) = (track.codec_params.time_base, track.codec_params.n_frames) {
+                let time = time_base.calc_time(frames);
+                metadata.duration = Some(time.seconds as f64 + time.frac);
+            }
+        }
+
+        // Tags are either in front of the container, like ID3v2 for mp3, or part of the container itself
+        if let Some(revision) = probed.metadata.get().as_ref().and_then(|log| log.current()) {
+            metadata.apply(revision);
+        }
+        if let Some(revision) = probed.format.metadata().current() {
+            metadata.apply(revision);
+        }
+
+        Ok(metadata)
+    }
+
+    fn apply(&mut self, revision: &MetadataRevision) {
+        for tag in revision.tags() {
+            match tag.std_key {
+                Some(StandardTagKey::TrackTitle) => self.title = Some(tag.value.to_string()),
+                Some(StandardTagKey::Artist) => self.artist = Some(tag.value.to_string()),
+                Some(StandardTagKey::Album) => self.album = Some(tag.value.to_string()),
+                Some(StandardTagKey::TrackNumber) => self.track_number = parse_track_number(&tag.value),
+                _ => {}
+            }
+        }
+
+        let visuals = revision.visuals();
+        let cover = visuals
+            .iter()
+            .find(|visual| matches!(visual.usage, Some(StandardVisualKey::FrontCover)))
+            .or(visuals.first());
+        if let Some(cover) = cover {
+            self.image = Some(cover.data.to_vec());
+        }
+    }
+}
+
+// Track numbers are often stored as text like "3/12"
+fn parse_track_number(value: &Value) -> Option<u32> {
+    match value {
+        Value::UnsignedInt(number) => Some(*number as u32),
+        Value::SignedInt(number) => Some(*number as u32),
+        value => value.to_string().split('/').next()?.trim().parse().ok(),
+    }
+}
@@ -0,0 +1,72 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::Notify;
+use tracing::{error, info, warn};
+
+use database::{AudioCache, DatabaseConnection, DbErr, LibraryEntryRepository, SystemConfigRepository};
+
+// Pinned podcasts get new episodes and played tracks grow the audio cache, so both are checked again regularly
+const CHECK_INTERVAL: Duration = Duration::from_secs(30 * 60);
+
+/// Downloads the remote tracks of pinned folders into the audio cache, so they play without network, and keeps the
+/// cache within its size
+#[derive(Clone)]
+pub struct OfflineDownloader {
+    pending: Arc<Notify>,
+}
+
+impl OfflineDownloader {
+    pub fn start(conn: DatabaseConnection) -> Self {
+        let downloader = Self { pending: Arc::new(Notify::new()) };
+
+        let pending = downloader.pending.clone();
+        tokio::spawn(async move {
+            loop {
+                if let Err(error) = download_pinned(&conn).await {
+                    error!("Failed to download pinned tracks: {:?}", error);
+                }
+                tokio::select! {
+                    _ = pending.notified() => {}
+                    _ = tokio::time::sleep(CHECK_INTERVAL) => {}
+                }
+            }
+        });
+
+        downloader
+    }
+
+    /// Lets the download check for newly pinned tracks
+    pub fn trigger(&self) {
+        self.pending.notify_one();
+    }
+}
+
+async fn download_pinned(conn: &DatabaseConnection) -> Result<(), DbErr> {
+    let size_limit = SystemConfigRepository::get_audio_cache_size(conn).await?;
+    let pinned_urls = LibraryEntryRepository::get_pinned_urls(conn).await?;
+    let missing_urls = pinned_urls.iter().filter(|url| !AudioCache::contains(url)).cloned().collect::<Vec<_>>();
+    if !missing_urls.is_empty() {
+        info!("Downloading {} pinned tracks", missing_urls.len());
+    }
+
+    let result = tokio::task::spawn_blocking(move || {
+        for url in missing_urls {
+            match AudioCache::store(&url, size_limit) {
+                Ok(true) => {}
+                Ok(false) => warn!("Pinned track {} can not be cached", url),
+                Err(error) => warn!("{}", error),
+            }
+        }
+        // Unpinned files make room for the pinned ones
+        AudioCache::evict(size_limit, &pinned_urls)
+    })
+    .await;
+    match result {
+        Ok(Err(error)) => error!("Failed to clean up audio cache: {}", error),
+        Err(error) => error!("Download of pinned tracks failed: {}", error),
+        _ => {}
+    }
+
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use serde_json::Value;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::UnixStream;
+
+pub enum PlayerError {
+    // The user interface is not running or does not listen on the socket
+    Unavailable(String),
+    // The player refused the command
+    Rejected(String),
+}
+
+/// Sends a single command to the socket of the user interface and returns the state included in the answer
+pub async fn send(request: Value) -> Result<Option<Value>, PlayerError> {
+    let path = std::env::var("PLAYER_SOCKET").unwrap_or("/tmp/tgb/player.sock".to_string());
+    let mut stream = UnixStream::connect(&path)
+        .await
+        .map_err(|error| PlayerError::Unavailable(format!("could not connect to player: {}", error)))?;
+
+    let mut line = request.to_string();
+    line.push('\n');
+    stream.write_all(line.as_bytes()).await.map_err(|error| PlayerError::Unavailable(error.to_string()))?;
+
+    let mut response = String::new();
+    BufReader::new(stream)
+        .read_line(&mut response)
+        .await
+        .map_err(|error| PlayerError::Unavailable(error.to_string()))?;
+    let response: Value = serde_json::from_str(&response)
+        .map_err(|error| PlayerError::Unavailable(format!("invalid answer of player: {}", error)))?;
+
+    if response["ok"].as_bool() != Some(true) {
+        let error = response["error"].as_str().unwrap_or("unknown error").to_string();
+        return Err(PlayerError::Rejected(error));
+    }
+    Ok(response.get("state").cloned())
+}
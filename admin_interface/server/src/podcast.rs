@@ -0,0 +1,207 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use feed_rs::model::{Entry, Feed};
+use tokio::sync::Notify;
+use tracing::{error, info, warn};
+
+use database::model::library_entry::{CreateModel as LibraryEntryCreateModel, Model as LibraryEntry, Variant};
+use database::model::track_source::CreateModel as TrackSourceCreateModel;
+use database::{fetch_image, DatabaseConnection, LibraryEntryRepository};
+
+// Episodes kept of a feed without its own limit
+const DEFAULT_EPISODE_LIMIT: usize = 10;
+
+/// Fetches the feeds of all podcasts periodically and keeps their newest episodes as children
+#[derive(Clone)]
+pub struct PodcastRefresher {
+    pending: Arc<Notify>,
+}
+
+impl PodcastRefresher {
+    /// Refreshes all podcasts right away and afterwards every PODCAST_REFRESH_INTERVAL minutes
+    pub fn start(conn: DatabaseConnection) -> Self {
+        let refresher = Self { pending: Arc::new(Notify::new()) };
+        let minutes = std::env::var("PODCAST_REFRESH_INTERVAL").unwrap_or("60".to_string()).parse::<u64>().unwrap();
+        let interval = Duration::from_secs(minutes.max(1) * 60);
+
+        let pending = refresher.pending.clone();
+        tokio::spawn(async move {
+            loop {
+                refresh_all(&conn).await;
+                tokio::select! {
+                    _ = pending.notified() => {}
+                    _ = tokio::time::sleep(interval) => {}
+                }
+            }
+        });
+
+        refresher
+    }
+
+    /// Refreshes all podcasts, e.g. after one was added
+    pub fn trigger(&self) {
+        self.pending.notify_one();
+    }
+}
+
+struct Episode {
+    title: String,
+    url: String,
+    published_at: Option<DateTime<Utc>>,
+    image_url: Option<String>,
+}
+
+impl Episode {
+    fn from_entry(entry: Entry) -> Option<Self> {
+        // RSS has the audio file as enclosure, which ends up as media content, Atom as link
+        let url = entry
+            .media
+            .iter()
+            .flat_map(|media| media.content.iter())
+            .filter(|content| {
+                content.content_type.as_ref().is_none_or(|content_type| content_type.to_string().starts_with("audio/"))
+            })
+            .find_map(|content| content.url.as_ref().map(|url| url.to_string()))
+            .or_else(|| {
+                entry.links.iter().find(|link| link.rel.as_deref() == Some("enclosure")).map(|link| link.href.clone())
+            })?;
+        let image_url = entry
+            .media
+            .iter()
+            .flat_map(|media| media.thumbnails.iter())
+            .next()
+            .map(|thumbnail| thumbnail.image.uri.clone());
+
+        Some(Self {
+            title: entry.title.map(|title| title.content).unwrap_or(url.clone()),
+            url,
+            published_at: entry.published.or(entry.updated),
+            image_url,
+        })
+    }
+}
+
+async fn refresh_all(conn: &DatabaseConnection) {
+    let podcasts = match LibraryEntryRepository::get_by_variant(conn, Variant::Podcast).await {
+        Ok(podcasts) => podcasts,
+        Err(error) => {
+            error!("Failed to load podcasts: {:?}", error);
+            return;
+        }
+    };
+
+    for podcast in podcasts {
+        if let Err(error) = refresh(conn, &podcast).await {
+            warn!("Could not refresh podcast {} '{}': {}", podcast.id, podcast.name, error);
+        }
+    }
+}
+
+/// Adds new episodes of the feed, removes the ones beyond the limit and orders them newest first
+pub async fn refresh(conn: &DatabaseConnection, podcast: &LibraryEntry) -> Result<(), String> {
+    let track_source = podcast.track_source.as_ref().ok_or("podcast has no track source".to_string())?;
+    let url = track_source.url.clone().ok_or("podcast has no feed url".to_string())?;
+    let limit = match track_source.episode_limit {
+        Some(limit) if limit > 0 => limit as usize,
+        _ => DEFAULT_EPISODE_LIMIT,
+    };
+
+    let feed = tokio::task::spawn_blocking(move || fetch_feed(&url))
+        .await
+        .map_err(|error| format!("fetching feed was aborted: {}", error))??;
+    let logo_url = feed.logo.as_ref().or(feed.icon.as_ref()).map(|image| image.uri.clone());
+    let mut episodes = feed.entries.into_iter().filter_map(Episode::from_entry).collect::<Vec<_>>();
+    episodes.sort_by_key(|episode| Reverse(episode.published_at));
+    episodes.truncate(limit);
+
+    let children = LibraryEntryRepository::get(conn, podcast.id)
+        .await
+        .map_err(|error| format!("could not load episodes: {}", error))?
+        .and_then(|podcast| podcast.children)
+        .unwrap_or_default();
+    let mut existing = HashMap::new();
+    for child in children {
+        match child.track_source.as_ref().and_then(|track_source| track_source.url.clone()) {
+            Some(url) if episodes.iter().any(|episode| episode.url == url) => {
+                existing.insert(url, child);
+            }
+            _ => {
+                LibraryEntryRepository::delete(conn, child.id)
+                    .await
+                    .map_err(|error| format!("could not remove episode {}: {}", child.id, error))?;
+            }
+        }
+    }
+
+    let mut new_episodes = vec![];
+    for (sort_key, episode) in episodes.into_iter().enumerate() {
+        let sort_key = sort_key as i32;
+        match existing.remove(&episode.url) {
+            Some(mut entry) if entry.sort_key != sort_key => {
+                entry.sort_key = sort_key;
+                LibraryEntryRepository::update(conn, entry.id, entry)
+                    .await
+                    .map_err(|error| format!("could not sort episode: {}", error))?;
+            }
+            Some(_) => {}
+            None => new_episodes.push((sort_key, episode)),
+        }
+    }
+
+    if !new_episodes.is_empty() {
+        info!("Adding {} episodes to podcast '{}'", new_episodes.len(), podcast.name);
+        let entries = tokio::task::spawn_blocking(move || {
+            new_episodes.into_iter().map(|(sort_key, episode)| into_create_model(sort_key, episode)).collect()
+        })
+        .await
+        .map_err(|error| format!("fetching episode images was aborted: {}", error))?;
+        LibraryEntryRepository::create(conn, Some(podcast.id), entries)
+            .await
+            .map_err(|error| format!("could not create episodes: {}", error))?;
+    }
+
+    if let (None, Some(logo_url)) = (podcast.image.as_ref(), logo_url) {
+        if let Ok(Some(image)) = tokio::task::spawn_blocking(move || fetch_image(&logo_url)).await {
+            let mut podcast = podcast.clone();
+            podcast.image = Some(image);
+            LibraryEntryRepository::update(conn, podcast.id, podcast)
+                .await
+                .map_err(|error| format!("could not set cover: {}", error))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Creates the entry of a new episode including its cover. Runs blocking.
+fn into_create_model(sort_key: i32, episode: Episode) -> LibraryEntryCreateModel {
+    LibraryEntryCreateModel {
+        parent_id: None,
+        variant: Variant::Episode,
+        name: episode.title.clone(),
+        image: episode.image_url.as_deref().and_then(fetch_image),
+        sort_key,
+        shuffle: false,
+        repeat_mode: Default::default(),
+        children: None,
+        track_source: Some(TrackSourceCreateModel {
+            title: episode.title,
+            url: Some(episode.url),
+            file: None,
+            file_hash: None,
+            spotify_id: None,
+            spotify_type: None,
+            published_at: episode.published_at,
+            episode_limit: None,
+        }),
+    }
+}
+
+fn fetch_feed(url: &str) -> Result<Feed, String> {
+    let response = ureq::get(url).call().map_err(|error| format!("could not fetch feed: {}", error))?;
+    feed_rs::parser::parse(response.into_reader()).map_err(|error| format!("could not parse feed: {}", error))
+}
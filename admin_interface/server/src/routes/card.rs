@@ -0,0 +1,71 @@
+use actix_web::{delete, get, put, web, HttpResponse, Responder};
+use database::{CardMappingRepository, DatabaseConnection};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::error;
+
+#[get("/api/cards")]
+pub async fn get_all(conn: web::Data<DatabaseConnection>) -> impl Responder {
+    match CardMappingRepository::get_all(conn.get_ref()).await {
+        Ok(cards) => HttpResponse::Ok().json(cards),
+        Err(error) => {
+            error!("Failed to get cards: {:?}", error);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct LearnQuery {
+    since: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Returns the card scanned after since. The first request is sent without since and returns the time to poll with,
+/// so the clocks of box and browser do not need to match.
+#[get("/api/cards/learn")]
+pub async fn learn(conn: web::Data<DatabaseConnection>, query: web::Query<LearnQuery>) -> impl Responder {
+    let since = match query.since {
+        Some(since) => since,
+        None => return HttpResponse::Ok().json(json!({ "since": chrono::Utc::now(), "card": null })),
+    };
+
+    match CardMappingRepository::get_last_seen(conn.get_ref(), since).await {
+        Ok(card) => HttpResponse::Ok().json(json!({ "since": since, "card": card })),
+        Err(error) => {
+            error!("Failed to get last seen card: {:?}", error);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct AssignBody {
+    library_entry_id: Option<i32>,
+}
+
+#[put("/api/cards/{card_uid}")]
+pub async fn assign(
+    conn: web::Data<DatabaseConnection>,
+    card_uid: web::Path<String>,
+    body: web::Json<AssignBody>,
+) -> impl Responder {
+    match CardMappingRepository::assign(conn.get_ref(), &card_uid, body.library_entry_id).await {
+        Ok(card) => HttpResponse::Ok().json(card),
+        Err(error) => {
+            error!("Failed to assign card {}: {:?}", card_uid, error);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+#[delete("/api/cards/{card_uid}")]
+pub async fn delete(conn: web::Data<DatabaseConnection>, card_uid: web::Path<String>) -> impl Responder {
+    match CardMappingRepository::delete(conn.get_ref(), &card_uid).await {
+        Ok(true) => HttpResponse::Ok().finish(),
+        Ok(false) => HttpResponse::NotFound().finish(),
+        Err(error) => {
+            error!("Failed to delete card {}: {:?}", card_uid, error);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
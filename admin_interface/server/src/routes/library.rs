@@ -1,15 +1,26 @@
+use crate::backup;
 use crate::file_cache::FileCache;
+use crate::library_import::{ImportJobs, ImportSource};
+use crate::loudness::LoudnessAnalyzer;
+use crate::metadata::AudioMetadata;
+use crate::offline::OfflineDownloader;
+use crate::podcast::PodcastRefresher;
+use crate::routes::system_config::run_update_commands;
+use actix_files::NamedFile;
 use actix_multipart::form::tempfile::TempFile;
 use actix_multipart::form::text::Text;
 use actix_multipart::form::MultipartForm;
-use actix_web::{delete, get, post, put, web, Responder};
+use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType};
+use actix_web::{delete, get, post, put, web, HttpRequest, Responder};
 use database::model::library_entry::Variant;
 use database::{
     model::library_entry::CreateModel as LibraryEntryCreateModel, model::library_entry::Model as LibraryEntry,
-    DatabaseConnection, DbErr, LibraryEntryRepository,
+    DatabaseConnection, DbErr, LibraryEntryRepository, SpotifyConfigRepository, SystemConfigRepository,
 };
 use serde::Deserialize;
-use tracing::{error, info};
+use serde_json::json;
+use std::path::PathBuf;
+use tracing::{error, info, warn};
 
 #[derive(Deserialize)]
 pub struct GetParams {
@@ -58,6 +69,34 @@ pub async fn update(
     }
 }
 
+#[derive(Deserialize)]
+pub struct PinBody {
+    pinned: bool,
+}
+
+/// Keeps the remote tracks of the entry and its children in the audio cache, so they play without network
+#[put("/api/library/{id}/pin")]
+pub async fn pin(
+    conn: web::Data<DatabaseConnection>,
+    offline_downloader: web::Data<OfflineDownloader>,
+    id: web::Path<i32>,
+    body: web::Json<PinBody>,
+) -> impl Responder {
+    match LibraryEntryRepository::set_pinned(&conn, id.into_inner(), body.pinned).await {
+        Ok(model) => {
+            offline_downloader.trigger();
+            actix_web::HttpResponse::Ok().json(model)
+        }
+        Err(error) => match error {
+            DbErr::RecordNotFound(_) => actix_web::HttpResponse::NotFound().finish(),
+            _ => {
+                error!("Failed to pin library entry: {:?}", error);
+                actix_web::HttpResponse::InternalServerError().finish()
+            }
+        },
+    }
+}
+
 #[derive(MultipartForm)]
 pub struct UploadForm {
     name: Text<String>,
@@ -70,11 +109,26 @@ pub async fn upload(
     file_cache: web::Data<FileCache>,
     MultipartForm(form): MultipartForm<UploadForm>,
 ) -> impl Responder {
-    std::fs::read(form.track.file.path())
-        .map_err(|error| format!("could not read temp file: {}", error))
-        .and_then(|binary| file_cache.add(form.name.clone(), binary.clone()))
-        .map(|_| actix_web::HttpResponse::Ok().finish())
-        .unwrap_or_else(|error| actix_web::HttpResponse::BadRequest().body(error))
+    let name = form.name.into_inner();
+    let upload = web::block(move || {
+        // Files without readable tags are still fine to play, so they are uploaded anyway
+        let metadata = AudioMetadata::from_file(form.track.file.path(), &name).unwrap_or_else(|error| {
+            warn!("Could not read metadata of {}: {}", name, error);
+            AudioMetadata::default()
+        });
+        let binary =
+            std::fs::read(form.track.file.path()).map_err(|error| format!("could not read temp file: {}", error))?;
+        file_cache.add(name, binary).map(|_| metadata)
+    });
+
+    match upload.await {
+        Ok(Ok(metadata)) => actix_web::HttpResponse::Ok().json(metadata),
+        Ok(Err(error)) => actix_web::HttpResponse::BadRequest().body(error),
+        Err(error) => {
+            error!("Failed to upload track: {}", error);
+            actix_web::HttpResponse::InternalServerError().finish()
+        }
+    }
 }
 
 #[derive(Deserialize)]
@@ -82,10 +136,185 @@ pub struct PostQuery {
     parent_id: Option<i32>,
 }
 
+#[derive(MultipartForm)]
+pub struct BulkImportForm {
+    #[multipart(limit = "2GB")]
+    archive: Option<TempFile>,
+    path: Option<Text<String>>,
+}
+
+/// Imports a ZIP archive or a folder on the box with all its audio files. Progress is polled by the returned id.
+#[post("/api/library/bulk-import")]
+pub async fn bulk_import(
+    conn: web::Data<DatabaseConnection>,
+    import_jobs: web::Data<ImportJobs>,
+    loudness_analyzer: web::Data<LoudnessAnalyzer>,
+    query: web::Query<PostQuery>,
+    MultipartForm(form): MultipartForm<BulkImportForm>,
+) -> impl Responder {
+    let source = match (form.archive, form.path) {
+        (Some(archive), _) => ImportSource::Archive(archive),
+        (None, Some(path)) => {
+            let path = PathBuf::from(path.into_inner());
+            if !path.is_dir() {
+                return actix_web::HttpResponse::BadRequest().body(format!("{:?} is no folder", path));
+            }
+            ImportSource::Directory(path)
+        }
+        (None, None) => return actix_web::HttpResponse::BadRequest().body("Either archive or path is needed"),
+    };
+
+    let id = import_jobs.start(conn.get_ref().clone(), loudness_analyzer.get_ref().clone(), query.parent_id, source);
+    actix_web::HttpResponse::Ok().json(json!({ "id": id }))
+}
+
+#[get("/api/library/bulk-import/{id}")]
+pub async fn get_bulk_import(import_jobs: web::Data<ImportJobs>, id: web::Path<u32>) -> impl Responder {
+    match import_jobs.get(id.into_inner()) {
+        Some(progress) => actix_web::HttpResponse::Ok().json(progress),
+        None => actix_web::HttpResponse::NotFound().finish(),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ExportQuery {
+    include_spotify: Option<bool>,
+}
+
+/// Exports the whole library with its files and the configs as zip archive. The spotify config contains credentials,
+/// so it is only included on request.
+#[get("/api/library/export")]
+pub async fn export(
+    conn: web::Data<DatabaseConnection>,
+    query: web::Query<ExportQuery>,
+    req: HttpRequest,
+) -> impl Responder {
+    let library = LibraryEntryRepository::get_tree(&conn, 0).await;
+    let system_config = SystemConfigRepository::get(&conn).await;
+    let spotify_config = match query.include_spotify.unwrap_or(false) {
+        true => SpotifyConfigRepository::get(&conn).await.map(Some),
+        false => Ok(None),
+    };
+    let (library, system_config, spotify_config) = match (library, system_config, spotify_config) {
+        (Ok(library), Ok(system_config), Ok(spotify_config)) => (library, system_config, spotify_config),
+        (Err(error), _, _) | (_, Err(error), _) | (_, _, Err(error)) => {
+            error!("Failed to load library for export: {:?}", error);
+            return actix_web::HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let path = match web::block(move || backup::export(library, system_config, spotify_config)).await {
+        Ok(Ok(path)) => path,
+        Ok(Err(error)) => {
+            error!("Failed to export library: {}", error);
+            return actix_web::HttpResponse::InternalServerError().body(error);
+        }
+        Err(error) => {
+            error!("Failed to export library: {}", error);
+            return actix_web::HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let file = NamedFile::open(&path);
+    // The opened file stays readable until it is sent, so it is not kept on disk
+    if let Err(error) = std::fs::remove_file(&path) {
+        warn!("Could not remove exported file {:?}: {}", path, error);
+    }
+    match file {
+        Ok(file) => file
+            .set_content_disposition(ContentDisposition {
+                disposition: DispositionType::Attachment,
+                parameters: vec![DispositionParam::Filename("tinyghettobox-backup.zip".to_string())],
+            })
+            .into_response(&req),
+        Err(error) => {
+            error!("Failed to open exported library: {}", error);
+            actix_web::HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+#[derive(MultipartForm)]
+pub struct ImportForm {
+    #[multipart(limit = "2GB")]
+    backup: TempFile,
+    // Deletes the current library instead of adding the backup to it
+    replace: Option<Text<bool>>,
+    restore_config: Option<Text<bool>>,
+}
+
+/// Restores a backup created by export
+#[post("/api/library/import")]
+pub async fn import(
+    conn: web::Data<DatabaseConnection>,
+    loudness_analyzer: web::Data<LoudnessAnalyzer>,
+    podcast_refresher: web::Data<PodcastRefresher>,
+    MultipartForm(form): MultipartForm<ImportForm>,
+) -> impl Responder {
+    let replace = form.replace.map(|replace| replace.into_inner()).unwrap_or(false);
+    let restore_config = form.restore_config.map(|restore_config| restore_config.into_inner()).unwrap_or(false);
+    let backup = form.backup;
+
+    let restore = match web::block(move || backup::read(backup.file.path())).await {
+        Ok(Ok(restore)) => restore,
+        Ok(Err(error)) => {
+            warn!("Failed to read backup: {}", error);
+            return actix_web::HttpResponse::BadRequest().body(error);
+        }
+        Err(error) => {
+            error!("Failed to read backup: {}", error);
+            return actix_web::HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    // Checked before anything is restored, so an invalid config does not leave the box restored halfway
+    if restore_config {
+        let system_config = restore.system_config.as_ref().map_or(Ok(()), SystemConfigRepository::validate_json);
+        let spotify_config = restore.spotify_config.as_ref().map_or(Ok(()), SpotifyConfigRepository::validate_json);
+        if let Err(error) = system_config.and(spotify_config) {
+            warn!("Backup contains an invalid config: {:?}", error);
+            return actix_web::HttpResponse::BadRequest().body(format!("invalid config in backup: {}", error));
+        }
+    }
+
+    let entries = match LibraryEntryRepository::restore(&conn, 0, restore.entries, replace).await {
+        Ok(entries) => entries,
+        Err(error) => {
+            error!("Failed to restore library: {:?}", error);
+            return actix_web::HttpResponse::InternalServerError().finish();
+        }
+    };
+    info!("Restored {} library entries, replaced library: {}", entries.len(), replace);
+    loudness_analyzer.trigger();
+    podcast_refresher.trigger();
+
+    if restore_config {
+        if let Some(system_config) = restore.system_config {
+            match SystemConfigRepository::update_from_json(&conn, system_config).await {
+                Ok((model, changed_fields)) => {
+                    if let Err(error) = run_update_commands(model, changed_fields) {
+                        warn!("Failed to apply restored system config: {:?}", error);
+                    }
+                }
+                Err(error) => warn!("Failed to restore system config: {:?}", error),
+            }
+        }
+        if let Some(spotify_config) = restore.spotify_config {
+            if let Err(error) = SpotifyConfigRepository::update_from_json(&conn, spotify_config).await {
+                warn!("Failed to restore spotify config: {:?}", error);
+            }
+        }
+    }
+
+    actix_web::HttpResponse::Ok().json(entries)
+}
+
 #[post("/api/library")]
 pub async fn create(
     conn: web::Data<DatabaseConnection>,
     file_cache: web::Data<FileCache>,
+    loudness_analyzer: web::Data<LoudnessAnalyzer>,
+    podcast_refresher: web::Data<PodcastRefresher>,
     query: web::Query<PostQuery>,
     entries: web::Json<Vec<LibraryEntryCreateModel>>,
 ) -> impl Responder {
@@ -97,7 +326,13 @@ pub async fn create(
     };
 
     match LibraryEntryRepository::create(&conn, query.parent_id, entries).await {
-        Ok(models) => actix_web::HttpResponse::Ok().json(models),
+        Ok(models) => {
+            loudness_analyzer.trigger();
+            if models.iter().any(|model| model.variant == Variant::Podcast) {
+                podcast_refresher.trigger();
+            }
+            actix_web::HttpResponse::Ok().json(models)
+        }
         Err(error) => match error {
             DbErr::Json(msg) => actix_web::HttpResponse::BadRequest().body(msg),
             DbErr::RecordNotFound(_) => actix_web::HttpResponse::NotFound().finish(),
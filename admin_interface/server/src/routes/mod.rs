@@ -1,4 +1,6 @@
+pub mod card;
 pub mod library;
+pub mod player;
 pub mod spotify;
 pub mod static_files;
 pub mod system_config;
@@ -0,0 +1,75 @@
+use actix_web::{get, post, put, web, HttpResponse, Responder};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tracing::warn;
+
+use crate::player_client::{self, PlayerError};
+
+async fn send(request: Value) -> HttpResponse {
+    match player_client::send(request).await {
+        Ok(Some(state)) => HttpResponse::Ok().json(state),
+        Ok(None) => HttpResponse::Ok().finish(),
+        Err(PlayerError::Unavailable(error)) => {
+            warn!("Player is not reachable: {}", error);
+            HttpResponse::ServiceUnavailable().body("player is not running")
+        }
+        Err(PlayerError::Rejected(error)) => HttpResponse::BadRequest().body(error),
+    }
+}
+
+#[get("/api/player/state")]
+pub async fn get_state() -> impl Responder {
+    send(json!({ "command": "state" })).await
+}
+
+#[derive(Deserialize)]
+pub struct PlayBody {
+    library_entry_id: i32,
+}
+
+#[post("/api/player/play")]
+pub async fn play(body: web::Json<PlayBody>) -> impl Responder {
+    send(json!({ "command": "play", "library_entry_id": body.library_entry_id })).await
+}
+
+#[post("/api/player/pause")]
+pub async fn pause() -> impl Responder {
+    send(json!({ "command": "pause" })).await
+}
+
+#[post("/api/player/resume")]
+pub async fn resume() -> impl Responder {
+    send(json!({ "command": "resume" })).await
+}
+
+#[post("/api/player/next")]
+pub async fn next() -> impl Responder {
+    send(json!({ "command": "next" })).await
+}
+
+#[post("/api/player/prev")]
+pub async fn prev() -> impl Responder {
+    send(json!({ "command": "prev" })).await
+}
+
+#[derive(Deserialize)]
+pub struct SeekBody {
+    // Percent of the current track 0-100
+    position: f64,
+}
+
+#[post("/api/player/seek")]
+pub async fn seek(body: web::Json<SeekBody>) -> impl Responder {
+    send(json!({ "command": "seek", "position": body.position })).await
+}
+
+#[derive(Deserialize)]
+pub struct VolumeBody {
+    // 0-1
+    volume: f64,
+}
+
+#[put("/api/player/volume")]
+pub async fn set_volume(body: web::Json<VolumeBody>) -> impl Responder {
+    send(json!({ "command": "volume", "volume": body.volume })).await
+}
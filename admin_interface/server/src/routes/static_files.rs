@@ -16,7 +16,7 @@ fn get_out_dir() -> Result<PathBuf, Error> {
         }
     }
 
-    let dir = current_dir().map_err(|e| ErrorInternalServerError(e))?;
+    let dir = current_dir().map_err(ErrorInternalServerError)?;
     let mut path = dir.as_path();
     loop {
         if path.join("admin_interface/web_ui").exists() {
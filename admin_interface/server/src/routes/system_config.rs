@@ -1,8 +1,9 @@
 use crate::error::Problem;
+use crate::player_client::{self, PlayerError};
 use actix_web::{get, put, web, HttpResponse, Responder, Result};
-use database::{model::system_config::Model, DatabaseConnection, SystemConfigRepository};
+use database::{model::system_config::Model, AudioConfigRepository, DatabaseConnection, SystemConfigRepository};
 use serde_json::json;
-use tracing::{error, warn};
+use tracing::{debug, error, warn};
 
 #[get("/api/system/config")]
 pub async fn get(conn: web::Data<DatabaseConnection>) -> impl Responder {
@@ -33,7 +34,37 @@ pub async fn update(conn: web::Data<DatabaseConnection>, json: web::Json<serde_j
     }
 }
 
-fn run_update_commands(updated_model: Model, changed_fields: Vec<String>) -> Result<(), Problem> {
+#[get("/api/system/audio")]
+pub async fn get_audio(conn: web::Data<DatabaseConnection>) -> impl Responder {
+    match AudioConfigRepository::get(&conn).await {
+        Ok(model) => HttpResponse::Ok().json(model),
+        Err(error) => {
+            error!("Failed to get audio config: {:?}", error);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+#[put("/api/system/audio")]
+pub async fn update_audio(conn: web::Data<DatabaseConnection>, json: web::Json<serde_json::Value>) -> impl Responder {
+    match AudioConfigRepository::update_from_json(&conn, json.into_inner()).await {
+        Ok((updated_model, _)) => {
+            // A player which is not running reads the config when it starts
+            match player_client::send(json!({ "command": "reload_audio_config" })).await {
+                Ok(_) => {}
+                Err(PlayerError::Unavailable(error)) => debug!("Player did not reload audio config: {}", error),
+                Err(PlayerError::Rejected(error)) => warn!("Player could not apply audio config: {}", error),
+            }
+            HttpResponse::Ok().json(updated_model)
+        }
+        Err(error) => {
+            warn!("Failed to set audio config: {:?}", error);
+            HttpResponse::BadRequest().json(json!({ "error": error.to_string() }))
+        }
+    }
+}
+
+pub fn run_update_commands(updated_model: Model, changed_fields: Vec<String>) -> Result<(), Problem> {
     if changed_fields.contains(&"hostname".to_string()) {
         crate::commands::set_hostname(updated_model.hostname.clone())?;
     }
@@ -41,34 +72,34 @@ fn run_update_commands(updated_model: Model, changed_fields: Vec<String>) -> Res
         crate::commands::set_cpu_governor(updated_model.cpu_governor.clone())?;
     }
     if changed_fields.contains(&"overclock_sd_card".to_string()) {
-        crate::commands::set_overclock_sd_card(updated_model.overclock_sd_card.clone())?;
+        crate::commands::set_overclock_sd_card(updated_model.overclock_sd_card)?;
     }
     if changed_fields.contains(&"log_to_ram".to_string()) {
-        crate::commands::set_log_to_ram(updated_model.log_to_ram.clone())?;
+        crate::commands::set_log_to_ram(updated_model.log_to_ram)?;
     }
     if changed_fields.contains(&"wait_for_network".to_string()) {
-        crate::commands::set_wait_for_network(updated_model.wait_for_network.clone())?;
+        crate::commands::set_wait_for_network(updated_model.wait_for_network)?;
     }
     if changed_fields.contains(&"initial_turbo".to_string()) {
-        crate::commands::set_initial_turbo(updated_model.initial_turbo.clone())?;
+        crate::commands::set_initial_turbo(updated_model.initial_turbo)?;
     }
     if changed_fields.contains(&"swap_enabled".to_string()) {
-        crate::commands::set_swap_enabled(updated_model.swap_enabled.clone())?;
+        crate::commands::set_swap_enabled(updated_model.swap_enabled)?;
     }
     if changed_fields.contains(&"hdmi_rotate".to_string()) {
-        crate::commands::set_hdmi_rotate(updated_model.hdmi_rotate.clone())?;
+        crate::commands::set_hdmi_rotate(updated_model.hdmi_rotate)?;
     }
     if changed_fields.contains(&"lcd_rotate".to_string()) {
-        crate::commands::set_lcd_rotate(updated_model.lcd_rotate.clone())?;
+        crate::commands::set_lcd_rotate(updated_model.lcd_rotate)?;
     }
     if changed_fields.contains(&"display_brightness".to_string()) {
-        crate::commands::set_display_brightness(updated_model.display_brightness.clone())?;
+        crate::commands::set_display_brightness(updated_model.display_brightness)?;
     }
     if changed_fields.contains(&"audio_device".to_string()) {
         crate::commands::set_audio_device(updated_model.audio_device.clone())?;
     }
     if changed_fields.contains(&"led_pin".to_string()) {
-        crate::commands::set_led_pin(updated_model.led_pin.clone())?;
+        crate::commands::set_led_pin(updated_model.led_pin)?;
     }
     Ok(())
 }
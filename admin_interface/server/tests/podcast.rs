@@ -0,0 +1,160 @@
+use admin_interface_server::podcast::refresh;
+use database::model::library_entry::{CreateModel as LibraryEntryCreateModel, Model as LibraryEntry, Variant};
+use database::model::track_source::CreateModel as TrackSourceCreateModel;
+use database::{Database, DatabaseConnection, LibraryEntryRepository, Migrator, MigratorTrait};
+use fixture_server::{fixture, serve};
+
+// Created by the migrations
+const ROOT_ID: i32 = 0;
+
+fn rss(base_url: &str) -> String {
+    let item = |number: u32, day: u32| {
+        format!(
+            r#"<item><title>Episode {number}</title><guid>{base_url}/{number}.mp3</guid>
+                <pubDate>{day:02} Jan 2024 08:00:00 GMT</pubDate>
+                <enclosure url="{base_url}/{number}.mp3" type="audio/mpeg" length="1000"/></item>"#
+        )
+    };
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?><rss version="2.0"><channel><title>Podcast</title>
+            <link>{base_url}</link><description>Podcast</description>{}{}{}</channel></rss>"#,
+        item(1, 1),
+        item(3, 3),
+        item(2, 2)
+    )
+}
+
+fn atom(base_url: &str) -> String {
+    let entry = |number: u32, day: u32| {
+        format!(
+            r#"<entry><title>Episode {number}</title><id>urn:episode:{number}</id>
+                <updated>2024-01-{day:02}T08:00:00Z</updated><published>2024-01-{day:02}T08:00:00Z</published>
+                <link rel="enclosure" type="audio/mpeg" href="{base_url}/{number}.mp3"/></entry>"#
+        )
+    };
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?><feed xmlns="http://www.w3.org/2005/Atom"><title>Podcast</title>
+            <id>urn:podcast</id><updated>2024-01-02T08:00:00Z</updated>{}{}</feed>"#,
+        entry(1, 1),
+        entry(2, 2)
+    )
+}
+
+async fn database() -> DatabaseConnection {
+    let conn = Database::connect("sqlite::memory:").await.unwrap();
+    Migrator::up(&conn, None).await.unwrap();
+    conn
+}
+
+async fn add_podcast(conn: &DatabaseConnection, feed_url: String, episode_limit: Option<i32>) -> LibraryEntry {
+    let podcast = LibraryEntryCreateModel {
+        parent_id: None,
+        variant: Variant::Podcast,
+        name: "Podcast".to_string(),
+        image: Some(vec![1, 2, 3]),
+        sort_key: 0,
+        shuffle: false,
+        repeat_mode: Default::default(),
+        children: None,
+        track_source: Some(TrackSourceCreateModel {
+            title: "Podcast".to_string(),
+            url: Some(feed_url),
+            file: None,
+            file_hash: None,
+            spotify_id: None,
+            spotify_type: None,
+            published_at: None,
+            episode_limit,
+        }),
+    };
+    LibraryEntryRepository::create(conn, Some(ROOT_ID), vec![podcast]).await.unwrap();
+    // Loaded the way the refresher does, which includes the track source
+    LibraryEntryRepository::get_by_variant(conn, Variant::Podcast).await.unwrap().remove(0)
+}
+
+/// Returns the names and urls of the episodes in their order
+async fn get_episodes(conn: &DatabaseConnection, podcast: &LibraryEntry) -> Vec<(String, String)> {
+    let podcast = LibraryEntryRepository::get(conn, podcast.id).await.unwrap().unwrap();
+    let mut episodes = podcast.children.unwrap_or_default();
+    episodes.sort_by_key(|episode| episode.sort_key);
+    episodes
+        .into_iter()
+        .map(|episode| {
+            assert!(matches!(episode.variant, Variant::Episode));
+            (episode.name, episode.track_source.unwrap().url.unwrap())
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn adds_episodes_of_rss_feed_newest_first() {
+    let base_url = serve(|base_url| vec![fixture("/feed.xml", "application/rss+xml", rss(base_url))]);
+    let conn = database().await;
+    let podcast = add_podcast(&conn, format!("{}/feed.xml", base_url), None).await;
+
+    refresh(&conn, &podcast).await.unwrap();
+
+    let episodes = get_episodes(&conn, &podcast).await;
+    assert_eq!(
+        episodes,
+        vec![
+            ("Episode 3".to_string(), format!("{}/3.mp3", base_url)),
+            ("Episode 2".to_string(), format!("{}/2.mp3", base_url)),
+            ("Episode 1".to_string(), format!("{}/1.mp3", base_url)),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn adds_episodes_of_atom_feed() {
+    let base_url = serve(|base_url| vec![fixture("/feed.atom", "application/atom+xml", atom(base_url))]);
+    let conn = database().await;
+    let podcast = add_podcast(&conn, format!("{}/feed.atom", base_url), None).await;
+
+    refresh(&conn, &podcast).await.unwrap();
+
+    let episodes = get_episodes(&conn, &podcast).await;
+    assert_eq!(
+        episodes,
+        vec![
+            ("Episode 2".to_string(), format!("{}/2.mp3", base_url)),
+            ("Episode 1".to_string(), format!("{}/1.mp3", base_url)),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn keeps_newest_episodes_up_to_limit() {
+    let base_url = serve(|base_url| vec![fixture("/feed.xml", "application/rss+xml", rss(base_url))]);
+    let conn = database().await;
+    let podcast = add_podcast(&conn, format!("{}/feed.xml", base_url), Some(2)).await;
+
+    refresh(&conn, &podcast).await.unwrap();
+
+    let names = get_episodes(&conn, &podcast).await.into_iter().map(|(name, _)| name).collect::<Vec<_>>();
+    assert_eq!(names, vec!["Episode 3", "Episode 2"]);
+}
+
+#[tokio::test]
+async fn adds_no_duplicates_on_repeated_refresh() {
+    let base_url = serve(|base_url| vec![fixture("/feed.xml", "application/rss+xml", rss(base_url))]);
+    let conn = database().await;
+    let podcast = add_podcast(&conn, format!("{}/feed.xml", base_url), None).await;
+
+    refresh(&conn, &podcast).await.unwrap();
+    let episodes = get_episodes(&conn, &podcast).await;
+    refresh(&conn, &podcast).await.unwrap();
+
+    assert_eq!(episodes.len(), 3);
+    assert_eq!(get_episodes(&conn, &podcast).await, episodes);
+}
+
+#[tokio::test]
+async fn fails_if_feed_is_missing() {
+    let base_url = serve(|_| vec![]);
+    let conn = database().await;
+    let podcast = add_podcast(&conn, format!("{}/feed.xml", base_url), None).await;
+
+    assert!(refresh(&conn, &podcast).await.is_err());
+    assert!(get_episodes(&conn, &podcast).await.is_empty());
+}
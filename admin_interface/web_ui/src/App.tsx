@@ -2,6 +2,8 @@ import {createBrowserRouter, redirect, RouterProvider} from 'react-router-dom';
 import SpotifyConfig from '@/pages/SpotifyConfig/SpotifyConfig';
 import SystemConfig from "@/pages/SystemConfig/SystemConfig";
 import MediaLibrary from "@/pages/MediaLibrary/MediaLibrary";
+import Cards from "@/pages/Cards/Cards";
+import Player from "@/pages/Player/Player";
 import Notification from "@/components/Notification";
 import Root from "@/pages/Root";
 import './App.model.css'
@@ -14,7 +16,9 @@ export const App = () => {
       {path: '', loader: () => redirect('/systemConfig')},
       {path: 'systemConfig', element: <SystemConfig />, id: 'System configuration'},
       {path: 'spotifyConfig/:step?', element: <SpotifyConfig />, id: 'Spotify configuration'},
-      {path: 'mediaLibrary/:id?', element: <MediaLibrary />, id: 'Media library'}
+      {path: 'mediaLibrary/:id?', element: <MediaLibrary />, id: 'Media library'},
+      {path: 'cards', element: <Cards />, id: 'RFID cards'},
+      {path: 'player', element: <Player />, id: 'Player'}
     ]}
   ]);
 
@@ -0,0 +1,105 @@
+import {useEffect, useState} from "react";
+import {
+  CircularProgress,
+  IconButton,
+  Table,
+  TableBody,
+  TableCell,
+  TableHead,
+  TableRow,
+  Typography
+} from "@mui/material";
+import {Delete, LinkOff} from "@mui/icons-material";
+import {Link} from "react-router-dom";
+import {CardMapping} from "@db-models/CardMapping";
+import {delCard, getCards, putCard} from "@/util/api";
+import {notify} from "@/components/Notification";
+
+export default function Cards() {
+  const [cards, setCards] = useState<CardMapping[]>();
+
+  const loadCards = async () => {
+    try {
+      setCards(await getCards());
+    } catch (e) {
+      notify('error', `Could not load cards: ${e}`);
+    }
+  }
+
+  useEffect(() => {
+    loadCards();
+  }, []);
+
+  const handleUnassign = async (card: CardMapping) => {
+    try {
+      await putCard(card.cardUid, undefined);
+      await loadCards();
+    } catch (e) {
+      notify('error', `Could not unassign card: ${e}`);
+    }
+  }
+
+  const handleDelete = async (card: CardMapping) => {
+    if (!confirm(`Are you sure you want to delete card ${card.cardUid}?`)) {
+      return;
+    }
+    try {
+      await delCard(card.cardUid);
+      await loadCards();
+    } catch (e) {
+      notify('error', `Could not delete card: ${e}`);
+    }
+  }
+
+  return (
+    <main>
+      <Typography variant={'h4'} sx={{mb: '16px', mt: '32px'}}>
+        RFID cards
+      </Typography>
+      <Typography variant="subtitle1" sx={{mb: 5}}>
+        Cards placed on the reader of the box are listed here. Assign them to a folder or track in the media library.
+      </Typography>
+      {cards ? (
+        <Table>
+          <TableHead>
+            <TableRow>
+              <TableCell>Card</TableCell>
+              <TableCell>Plays</TableCell>
+              <TableCell>Last seen</TableCell>
+              <TableCell/>
+            </TableRow>
+          </TableHead>
+          <TableBody>
+            {cards.map(card => (
+              <TableRow key={card.cardUid}>
+                <TableCell>{card.cardUid}</TableCell>
+                <TableCell>
+                  {card.libraryEntryId !== undefined && card.libraryEntryId !== null ? (
+                    <Link to={`/mediaLibrary/${card.libraryEntryId}`}>{card.libraryEntryName}</Link>
+                  ) : (
+                    'Not assigned'
+                  )}
+                </TableCell>
+                <TableCell>{card.lastSeenAt ? new Date(card.lastSeenAt).toLocaleString() : 'Never'}</TableCell>
+                <TableCell align="right">
+                  <IconButton
+                    size="small"
+                    onClick={() => handleUnassign(card)}
+                    disabled={card.libraryEntryId === undefined || card.libraryEntryId === null}
+                  >
+                    <LinkOff/>
+                  </IconButton>
+                  <IconButton size="small" color="error" onClick={() => handleDelete(card)}>
+                    <Delete/>
+                  </IconButton>
+                </TableCell>
+              </TableRow>
+            ))}
+          </TableBody>
+        </Table>
+      ) : (
+        <CircularProgress/>
+      )}
+    </main>
+  )
+}
@@ -4,6 +4,8 @@ import StreamAddForm from "@/pages/MediaLibrary/AddEntryDialog/StreamAddForm/Str
 import FileAddForm from "@/pages/MediaLibrary/AddEntryDialog/FileAddForm/FileAddForm";
 import {useState} from "react";
 import FolderAddForm from "./FolderAddForm/FolderAddForm";
+import ImportAddForm from "./ImportAddForm/ImportAddForm";
+import PodcastAddForm from "./PodcastAddForm/PodcastAddForm";
 import {useAddEntryState} from "./useAddEntryState";
 import {Variant} from "@db-models/Variant";
 
@@ -13,13 +15,13 @@ interface Props {
 
 export default function AddForm({allowedVariant}: Props) {
   const {setEntries, abort, submit} = useAddEntryState();
-  const [sourceType, setSourceType] = useState<'folder' | 'file' | 'stream' | 'spotify' | 'all'>(allowedVariant === 'folder' ?
+  const [sourceType, setSourceType] = useState<'folder' | 'file' | 'stream' | 'podcast' | 'spotify' | 'import' | 'all'>(allowedVariant === 'folder' ?
     'folder' :
     'spotify'
   );
 
   const handleSourceTypeChange = (_event: any, value: string) => {
-    setSourceType(value as 'folder' | 'file' | 'stream' | 'spotify');
+    setSourceType(value as 'folder' | 'file' | 'stream' | 'podcast' | 'spotify' | 'import');
     setEntries([]);
   }
 
@@ -52,13 +54,17 @@ export default function AddForm({allowedVariant}: Props) {
           />
           <FormControlLabel control={<Radio />} label={'File'} value={'file'} disabled={allowedVariant === 'folder'} />
           <FormControlLabel control={<Radio />} label={'Stream'} value={'stream'} disabled={allowedVariant === 'folder'} />
+          <FormControlLabel control={<Radio />} label={'Podcast'} value={'podcast'} disabled={allowedVariant && allowedVariant !== 'folder'} />
+          <FormControlLabel control={<Radio />} label={'Import'} value={'import'} />
         </RadioGroup>
       </FormControl>
 
       {sourceType === 'folder' && <FolderAddForm />}
       {sourceType === 'spotify' && <SpotifyAddForm allowedVariant={allowedVariant} />}
       {sourceType === 'stream' && <StreamAddForm />}
+      {sourceType === 'podcast' && <PodcastAddForm />}
       {sourceType === 'file' && <FileAddForm />}
+      {sourceType === 'import' && <ImportAddForm />}
     </>
   )
 }
\ No newline at end of file
@@ -5,8 +5,9 @@ import {useAddEntryState} from "@/pages/MediaLibrary/AddEntryDialog/useAddEntryS
 import {Sortable, SortableItem} from "@/components/Sortable";
 import sortableListStyles from "../../SortableList.module.scss";
 import {LibraryEntry} from "@db-models/LibraryEntry";
-import {uploadLibraryEntryFile} from "@/util/api";
+import {AudioMetadata, uploadLibraryEntryFile} from "@/util/api";
 import {notify} from "@/components/Notification";
+import {cropImage} from "@/pages/MediaLibrary/AddEntryDialog/helper";
 
 
 const VisuallyHiddenInput = styled('input')({
@@ -21,6 +22,11 @@ const VisuallyHiddenInput = styled('input')({
   width: 1,
 });
 
+// Entries are identified by the uploaded file name, as the name may change by the metadata of the file
+function getFileName(entry: LibraryEntry): string {
+  return entry.trackSource?.title || entry.name;
+}
+
 export default function FileAddForm() {
   const {entries, setEntries, removeEntry, getNextSortKey} = useAddEntryState();
   const [uploadProgress, setUploadProgress] = useState<{ [name: string]: number }>({});
@@ -30,7 +36,8 @@ export default function FileAddForm() {
       return;
     }
 
-    let sortKey = getNextSortKey();
+    const firstSortKey = getNextSortKey();
+    let sortKey = firstSortKey;
     const newEntries: LibraryEntry[] = [];
     for (let i = 0; i < event.target.files.length; i++) {
       const file = event.target.files?.[i] as File;
@@ -42,11 +49,14 @@ export default function FileAddForm() {
         (progress) => {
           setUploadProgress(prev => ({...prev, [file.name]: progress}));
         },
-        (error) => {
+        (error, metadata) => {
           if (error) {
             notify('error', `Failed to upload ${file.name}: ${error}`, 6000);
           }
           setUploadProgress(prev => ({...prev, [file.name]: !error ? 100 : -1}));
+          if (metadata) {
+            applyMetadata(file.name, metadata, firstSortKey);
+          }
         }
       );
     }
@@ -59,9 +69,35 @@ export default function FileAddForm() {
     ]);
   }
 
+  const applyMetadata = async (fileName: string, metadata: AudioMetadata, firstSortKey: number) => {
+    let image: number[] | undefined;
+    if (metadata.image?.length) {
+      try {
+        image = await cropImage(new Blob([new Uint8Array(metadata.image)]), 180, 180, 0.8);
+      } catch (e) {
+        notify('warning', `Could not use cover of ${fileName}: ${e}`, 4000);
+      }
+    }
+
+    setEntries(entries => entries
+      .map(entry => {
+        if (getFileName(entry) !== fileName) {
+          return entry;
+        }
+        return {
+          ...entry,
+          name: metadata.title || entry.name,
+          sortKey: metadata.trackNumber ? firstSortKey + metadata.trackNumber - 1 : entry.sortKey,
+          image: image || entry.image,
+        };
+      })
+      .sort((a, b) => a.sortKey - b.sortKey)
+    );
+  }
+
   const handleDragEnd = (itemIds: string[]) => {
     setEntries(entries =>
-      itemIds.map(id => entries.find(entry => entry.name === id) as LibraryEntry)
+      itemIds.map(id => entries.find(entry => getFileName(entry) === id) as LibraryEntry)
     );
   };
 
@@ -77,10 +113,10 @@ export default function FileAddForm() {
           <VisuallyHiddenInput type="file" name="image" accept="audio/*" multiple onChange={handleUpload}/>
         </Button>
       </Box>
-      <Sortable itemIds={(entries as LibraryEntry[]).map(entry => entry.name)} onDragEnd={handleDragEnd}>
+      <Sortable itemIds={(entries as LibraryEntry[]).map(getFileName)} onDragEnd={handleDragEnd}>
         <List>
           {(entries as LibraryEntry[]).map((entry) =>
-            <SortableItem itemId={entry.name} key={entry.name}>
+            <SortableItem itemId={getFileName(entry)} key={getFileName(entry)}>
               {(props, isDragging) => (
                 <ListItem
                   {...props}
@@ -91,17 +127,17 @@ export default function FileAddForm() {
                   secondaryAction={<IconButton onClickCapture={() => handleDelete(entry)}><Delete/></IconButton>}
                 >
                   <ListItemAvatar>
-                    {uploadProgress[entry.name] == 100 ? (
+                    {uploadProgress[getFileName(entry)] == 100 ? (
                       <Check/>
-                    ) : uploadProgress[entry.name] == -1 ? (
+                    ) : uploadProgress[getFileName(entry)] == -1 ? (
                       <ErrorOutline color={"error"}/>
                     ) : (
-                      <CircularProgress value={uploadProgress[entry.name] || 0}/>
+                      <CircularProgress value={uploadProgress[getFileName(entry)] || 0}/>
                     )}
                   </ListItemAvatar>
                   <ListItemText
                     primary={entry.name}
-                    secondary={`Filename: ${entry.trackSource?.title || entry.name}`}
+                    secondary={`Filename: ${getFileName(entry)}`}
                     sx={{wordWrap: 'break-word'}}
                   />
                 </ListItem>
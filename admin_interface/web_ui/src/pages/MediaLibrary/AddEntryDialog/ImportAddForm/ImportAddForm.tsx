@@ -0,0 +1,143 @@
+import {ChangeEvent, useEffect, useState} from "react";
+import {Box, Button, FormControl, InputLabel, LinearProgress, OutlinedInput, styled, Typography} from "@mui/material";
+import {FolderZip} from "@mui/icons-material";
+import {useAddEntryState} from "@/pages/MediaLibrary/AddEntryDialog/useAddEntryState";
+import {getLibraryImport, ImportProgress, importLibraryEntries} from "@/util/api";
+import {notify} from "@/components/Notification";
+
+const VisuallyHiddenInput = styled('input')({
+  clip: 'rect(0 0 0 0)',
+  clipPath: 'inset(50%)',
+  height: 1,
+  overflow: 'hidden',
+  position: 'absolute',
+  bottom: 0,
+  left: 0,
+  whiteSpace: 'nowrap',
+  width: 1,
+});
+
+function getProgressText(progress?: ImportProgress, uploadProgress?: number): string {
+  if (!progress) {
+    return `Uploading ${Math.round((uploadProgress || 0) * 100)}%`;
+  }
+  switch (progress.state) {
+    case 'storing_files':
+      return `Storing files ${progress.storedFiles} / ${progress.totalFiles}`;
+    case 'creating_entries':
+      return `Creating entries ${progress.createdEntries} / ${progress.totalEntries}`;
+    case 'done':
+      return 'Done';
+    case 'failed':
+      return `Failed: ${progress.error}`;
+  }
+}
+
+function getProgressValue(progress?: ImportProgress, uploadProgress?: number): number {
+  if (!progress) {
+    return (uploadProgress || 0) * 100;
+  }
+  switch (progress.state) {
+    case 'storing_files':
+      return progress.totalFiles ? progress.storedFiles / progress.totalFiles * 100 : 0;
+    case 'creating_entries':
+      return progress.totalEntries ? progress.createdEntries / progress.totalEntries * 100 : 0;
+    default:
+      return 100;
+  }
+}
+
+export default function ImportAddForm() {
+  const {parentId, close} = useAddEntryState();
+  const [path, setPath] = useState('');
+  const [running, setRunning] = useState(false);
+  const [uploadProgress, setUploadProgress] = useState<number>();
+  const [importId, setImportId] = useState<number>();
+  const [progress, setProgress] = useState<ImportProgress>();
+
+  useEffect(() => {
+    if (typeof importId === 'undefined') {
+      return;
+    }
+
+    const interval = setInterval(async () => {
+      try {
+        const progress = await getLibraryImport(importId);
+        setProgress(progress);
+        if (progress.state === 'done') {
+          clearInterval(interval);
+          notify('success', 'Import finished', 2000);
+          close(true);
+        } else if (progress.state === 'failed') {
+          clearInterval(interval);
+          setRunning(false);
+        }
+      } catch (e) {
+        clearInterval(interval);
+        setRunning(false);
+        notify('error', `Could not get import progress: ${e}`, 6000);
+      }
+    }, 1000);
+
+    return () => clearInterval(interval);
+  }, [importId]);
+
+  const startImport = (source: File | string) => {
+    setRunning(true);
+    setProgress(undefined);
+    setUploadProgress(0);
+    importLibraryEntries(
+      parentId as number,
+      source,
+      setUploadProgress,
+      (error, response) => {
+        if (error || !response) {
+          notify('error', `Failed to start import: ${error}`, 6000);
+          setRunning(false);
+          return;
+        }
+        setImportId(response.id);
+      }
+    );
+  }
+
+  const handleArchiveChange = (event: ChangeEvent<HTMLInputElement>) => {
+    const archive = event.target.files?.[0];
+    event.target.value = '';
+    if (archive) {
+      startImport(archive);
+    }
+  }
+
+  return (
+    <Box sx={{mt: 2}}>
+      <Typography variant="body2" sx={{mb: 2}}>
+        Imports all audio files with their folders. Tracks are named and ordered by their tags or file names.
+      </Typography>
+      <Box sx={{textAlign: 'center', mb: 3}}>
+        <Button component="label" variant="outlined" startIcon={<FolderZip/>} disabled={running}>
+          Import ZIP archive
+          <VisuallyHiddenInput type="file" name="archive" accept=".zip,application/zip" onChange={handleArchiveChange}/>
+        </Button>
+      </Box>
+      <Box sx={{display: 'flex', gap: 2, mb: 3}}>
+        <FormControl fullWidth size="small">
+          <InputLabel>Folder on the box</InputLabel>
+          <OutlinedInput
+            label="Folder on the box"
+            value={path}
+            onChange={(event) => setPath(event.target.value)}
+            disabled={running}
+          />
+        </FormControl>
+        <Button variant="outlined" onClick={() => startImport(path)} disabled={running || !path}>Import</Button>
+      </Box>
+      {(running || progress) && (
+        <Box>
+          <LinearProgress variant="determinate" value={getProgressValue(progress, uploadProgress)}/>
+          <Typography variant="caption">{getProgressText(progress, uploadProgress)}</Typography>
+        </Box>
+      )}
+    </Box>
+  );
+}
@@ -0,0 +1,140 @@
+import {ChangeEvent, useState} from "react";
+import {Box, FormControl, Grid, IconButton, InputLabel, OutlinedInput, Typography} from "@mui/material";
+import {Check, Delete} from "@mui/icons-material";
+import {useAddEntryState} from "@/pages/MediaLibrary/AddEntryDialog/useAddEntryState";
+import {LibraryEntry} from "@db-models/LibraryEntry";
+
+const DEFAULT_EPISODE_LIMIT = 10;
+
+export default function PodcastAddForm() {
+  const {entries, setEntries, addEntry, removeEntry, getNextSortKey} = useAddEntryState();
+  const [newFeedUrl, setNewFeedUrl] = useState('');
+  const [newPodcastName, setNewPodcastName] = useState('');
+  const [newEpisodeLimit, setNewEpisodeLimit] = useState(DEFAULT_EPISODE_LIMIT);
+
+  const handleAdd = () => {
+    if (!newPodcastName || !newFeedUrl) {
+      return;
+    }
+    addEntry({
+      variant: 'podcast',
+      name: newPodcastName,
+      trackSource: {title: newPodcastName, url: newFeedUrl, episodeLimit: newEpisodeLimit},
+      sortKey: getNextSortKey()
+    });
+    setNewFeedUrl('');
+    setNewPodcastName('');
+    setNewEpisodeLimit(DEFAULT_EPISODE_LIMIT);
+  }
+
+  const handleChange = (entry: LibraryEntry, change: (entry: LibraryEntry) => void) => {
+    const index = entries.indexOf(entry);
+    if (index === -1) {
+      throw new Error(`Can not find index of ${JSON.stringify(entry)} in podcasts: ${JSON.stringify(entries)}`);
+    }
+
+    change(entries[index]);
+
+    setEntries([...entries]);
+  }
+
+  const handleNameChange = (event: ChangeEvent<HTMLInputElement | HTMLTextAreaElement>, entry: LibraryEntry) => {
+    handleChange(entry, entry => entry.name = event.target.value);
+  }
+
+  const handleUrlChange = (event: ChangeEvent<HTMLInputElement | HTMLTextAreaElement>, entry: LibraryEntry) => {
+    handleChange(entry, entry => entry.trackSource!.url = event.target.value);
+  }
+
+  const handleEpisodeLimitChange = (event: ChangeEvent<HTMLInputElement | HTMLTextAreaElement>, entry: LibraryEntry) => {
+    handleChange(entry, entry => entry.trackSource!.episodeLimit = parseInt(event.target.value) || undefined);
+  }
+
+  return (
+    <Box sx={{mt: 2}}>
+      <Typography variant="body2" sx={{mb: 2}}>
+        The newest episodes of the feed are added and updated regularly by the box.
+      </Typography>
+      {entries.map((entry, index) =>
+        <Grid container spacing={2} key={index}>
+          <Grid item xs={4}>
+            <FormControl fullWidth sx={{mb: 3}} size="small">
+              <InputLabel>{index + 1}. Podcast Name</InputLabel>
+              <OutlinedInput
+                label={`${index + 1}. Podcast Name`}
+                onChange={(event) => handleNameChange(event, entry)}
+                value={entry.name}
+              />
+            </FormControl>
+          </Grid>
+          <Grid item xs={5}>
+            <FormControl fullWidth sx={{mb: 3}} size="small">
+              <InputLabel>{index + 1}. Feed URL</InputLabel>
+              <OutlinedInput
+                label={`${index + 1}. Feed URL`}
+                onChange={(event) => handleUrlChange(event, entry)}
+                value={entry.trackSource?.url}
+              />
+            </FormControl>
+          </Grid>
+          <Grid item xs={2}>
+            <FormControl fullWidth sx={{mb: 3}} size="small">
+              <InputLabel>Episodes</InputLabel>
+              <OutlinedInput
+                label={'Episodes'}
+                type="number"
+                inputProps={{min: 1}}
+                onChange={(event) => handleEpisodeLimitChange(event, entry)}
+                value={entry.trackSource?.episodeLimit ?? ''}
+              />
+            </FormControl>
+          </Grid>
+          <Grid item xs={1}>
+            <IconButton onClick={() => removeEntry(entry)} edge="end">
+              <Delete />
+            </IconButton>
+          </Grid>
+        </Grid>
+      )}
+      <Grid container spacing={2}>
+        <Grid item xs={4}>
+          <FormControl fullWidth sx={{mb: 3}} size="small">
+            <InputLabel>{entries.length + 1}. Podcast Name</InputLabel>
+            <OutlinedInput
+              label={`${entries.length + 1}. Podcast Name`}
+              value={newPodcastName}
+              onChange={(event) => setNewPodcastName(event.target.value)}
+            />
+          </FormControl>
+        </Grid>
+        <Grid item xs={5}>
+          <FormControl fullWidth sx={{mb: 3}} size="small">
+            <InputLabel>{entries.length + 1}. Feed URL</InputLabel>
+            <OutlinedInput
+              label={`${entries.length + 1}. Feed URL`}
+              value={newFeedUrl}
+              onChange={(event) => setNewFeedUrl(event.target.value)}
+            />
+          </FormControl>
+        </Grid>
+        <Grid item xs={2}>
+          <FormControl fullWidth sx={{mb: 3}} size="small">
+            <InputLabel>Episodes</InputLabel>
+            <OutlinedInput
+              label={'Episodes'}
+              type="number"
+              inputProps={{min: 1}}
+              value={newEpisodeLimit}
+              onChange={(event) => setNewEpisodeLimit(parseInt(event.target.value) || DEFAULT_EPISODE_LIMIT)}
+            />
+          </FormControl>
+        </Grid>
+        <Grid item xs={1}>
+          <IconButton onClick={handleAdd} edge="end" disabled={!newPodcastName || !newFeedUrl}>
+            <Check />
+          </IconButton>
+        </Grid>
+      </Grid>
+    </Box>
+  )
+}
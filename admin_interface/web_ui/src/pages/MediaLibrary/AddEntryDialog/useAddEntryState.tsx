@@ -13,6 +13,7 @@ type AddEntryState = {
   isEntryAdded: (entry: LibraryEntry) => boolean;
   abort: () => void;
   submit: (event: MouseEvent) => void;
+  close: (submitted?: true) => void;
   getNextSortKey: () => number;
 }
 
@@ -106,6 +107,7 @@ export const AddEntryStateProvider = (props: Props) => {
     isEntryAdded,
     abort,
     submit,
+    close: props.onClose,
     getNextSortKey
   }
 
@@ -0,0 +1,84 @@
+import {useEffect, useState} from "react";
+import {Button, CircularProgress, Dialog, DialogActions, DialogContent, DialogTitle, Stack, Typography} from "@mui/material";
+import {LibraryEntry} from "@db-models/LibraryEntry";
+import {CardMapping} from "@db-models/CardMapping";
+import {getLearnedCard, putCard} from "@/util/api";
+import {notify} from "@/components/Notification";
+
+interface Props {
+  libraryEntry: LibraryEntry;
+  open: boolean;
+  onClose: () => void;
+}
+
+export default function AssignCardDialog({libraryEntry, open, onClose}: Props) {
+  const [card, setCard] = useState<CardMapping>();
+
+  useEffect(() => {
+    if (!open) {
+      return;
+    }
+
+    setCard(undefined);
+    let since: string | undefined;
+    let stopped = false;
+    const interval = setInterval(async () => {
+      try {
+        const learnState = await getLearnedCard(since);
+        since = learnState.since;
+        if (learnState.card && !stopped) {
+          clearInterval(interval);
+          setCard(learnState.card);
+        }
+      } catch (e) {
+        clearInterval(interval);
+        notify('error', `Could not read card: ${e}`, 6000);
+      }
+    }, 1000);
+
+    return () => {
+      stopped = true;
+      clearInterval(interval);
+    };
+  }, [open]);
+
+  const handleAssign = async () => {
+    if (!card) {
+      return;
+    }
+    try {
+      await putCard(card.cardUid, libraryEntry.id);
+      notify('success', `Card assigned to ${libraryEntry.name}`, 2000);
+      onClose();
+    } catch (e) {
+      notify('error', `Could not assign card: ${e}`, 6000);
+    }
+  }
+
+  return (
+    <Dialog open={open} onClose={onClose}>
+      <DialogTitle>Assign card to {libraryEntry.name}</DialogTitle>
+      <DialogContent>
+        {card ? (
+          <Stack gap={1}>
+            <Typography>Found card <b>{card.cardUid}</b></Typography>
+            {!!card.libraryEntryId && card.libraryEntryId !== libraryEntry.id && (
+              <Typography color="warning.main">
+                The card is assigned to {card.libraryEntryName} yet and gets reassigned.
+              </Typography>
+            )}
+          </Stack>
+        ) : (
+          <Stack direction="row" gap={2} alignItems="center">
+            <CircularProgress size={24}/>
+            <Typography>Place the card on the reader of the box</Typography>
+          </Stack>
+        )}
+      </DialogContent>
+      <DialogActions>
+        <Button onClick={onClose}>Cancel</Button>
+        <Button variant="contained" onClick={handleAssign} disabled={!card}>Assign</Button>
+      </DialogActions>
+    </Dialog>
+  )
+}
@@ -2,20 +2,45 @@ import {useState} from "react";
 import {Box, Breadcrumbs, Button, CircularProgress, Grid, Stack, Typography} from "@mui/material";
 import FolderList from "./FolderList";
 import TrackList from "./TrackList";
-import {AddOutlined, ArrowLeft, Home, WestOutlined} from "@mui/icons-material";
+import {AddOutlined, ArrowLeft, Home, Nfc, OfflinePin, OfflinePinOutlined, PlayArrow, WestOutlined} from "@mui/icons-material";
 import FolderAvatar from "@/components/FolderAvatar";
 import {useLibraryEntry} from "@/pages/MediaLibrary/useLibraryEntry";
 import SortButton from "@/pages/MediaLibrary/SortButton";
 import AddEntryDialog from "@/pages/MediaLibrary/AddEntryDialog/AddEntryDialog";
+import AssignCardDialog from "@/pages/MediaLibrary/AssignCardDialog";
 import {LibraryEntry} from "@db-models/LibraryEntry";
 import {useParams, Link} from "react-router-dom";
+import {playLibraryEntry, putLibraryEntryPin} from "@/util/api";
+import {notify} from "@/components/Notification";
 
 export default function MediaLibrary() {
   const params = useParams();
   const entityId = params.id && parseInt(params.id) || 0;
   const {libraryEntry, loading, error, reloadLibraryEntry, deleteLibraryEntry, updateLibraryEntry} = useLibraryEntry(entityId);
   const [dialogOpen, setDialogOpen] = useState(false);
-  const usedVariant = libraryEntry?.children?.map(child => child.variant)[0];
+  const [cardDialogOpen, setCardDialogOpen] = useState(false);
+  // Podcasts are shown and added along with folders
+  const usedVariant = libraryEntry?.children?.map(child => child.variant === 'podcast' ? 'folder' : child.variant)[0];
+  const isFolder = (entry: LibraryEntry) => entry.variant === 'folder' || entry.variant === 'podcast';
+
+  const handlePlay = async () => {
+    try {
+      await playLibraryEntry(entityId);
+      notify('success', `Playing ${libraryEntry?.name} on the box`, 3000);
+    } catch (e) {
+      notify('error', `Could not play: ${e}`);
+    }
+  }
+
+  const handleTogglePin = async () => {
+    try {
+      await putLibraryEntryPin(entityId, !libraryEntry?.pinned);
+      notify('success', libraryEntry?.pinned ? 'Not kept offline anymore' : 'Downloading for offline playback', 3000);
+      reloadLibraryEntry();
+    } catch (e) {
+      notify('error', `Could not change offline availability: ${e}`);
+    }
+  }
 
   const handleOpenAddDialog = () => {
     setDialogOpen(true)
@@ -84,10 +109,30 @@ export default function MediaLibrary() {
                 <Typography variant="h5" sx={{mb: 1}}>{libraryEntry ? libraryEntry.name : ''}</Typography>
                 <Grid container gap={2}>
                   <Stack direction={'row'} spacing={2}>
-                    <Button variant="contained" onClick={handleOpenAddDialog}>
-                      <AddOutlined/>&nbsp;
-                      Add entries
-                    </Button>
+                    {libraryEntry.variant !== 'podcast' && (
+                      <Button variant="contained" onClick={handleOpenAddDialog}>
+                        <AddOutlined/>&nbsp;
+                        Add entries
+                      </Button>
+                    )}
+                    {libraryEntry.id !== 0 && (
+                      <Button variant="outlined" onClick={handlePlay}>
+                        <PlayArrow/>&nbsp;
+                        Play on box
+                      </Button>
+                    )}
+                    {libraryEntry.id !== 0 && (
+                      <Button variant="outlined" onClick={() => setCardDialogOpen(true)}>
+                        <Nfc/>&nbsp;
+                        Assign card
+                      </Button>
+                    )}
+                    {libraryEntry.id !== 0 && isFolder(libraryEntry) && (
+                      <Button variant={libraryEntry.pinned ? "contained" : "outlined"} onClick={handleTogglePin}>
+                        {libraryEntry.pinned ? <OfflinePin/> : <OfflinePinOutlined/>}&nbsp;
+                        Keep offline
+                      </Button>
+                    )}
                     {!!libraryEntry.children && (
                       <SortButton libraryEntries={libraryEntry.children} onSorted={handleSorted}/>
                     )}
@@ -97,15 +142,15 @@ export default function MediaLibrary() {
             </Grid>
           </Box>
           {libraryEntry.children && (
-            libraryEntry.children.some(entry => entry.variant === 'folder') ? (
+            libraryEntry.children.some(isFolder) ? (
               <FolderList
-                folders={libraryEntry.children.filter(entry => entry.variant === 'folder')}
+                folders={libraryEntry.children.filter(isFolder)}
                 onSortEnd={handleSortEnd}
                 onDelete={handleDelete}
               />
             ) : (
               <TrackList
-                tracks={libraryEntry.children.filter(entry => entry.variant !== 'folder')}
+                tracks={libraryEntry.children.filter(entry => !isFolder(entry))}
                 onSortEnd={handleSortEnd}
                 onDelete={handleDelete}
               />
@@ -114,6 +159,9 @@ export default function MediaLibrary() {
           {!!libraryEntry &&
             <AddEntryDialog parent={libraryEntry} open={dialogOpen} onClose={handleCloseAddDialog} allowedVariant={usedVariant} />
           }
+          {!!libraryEntry &&
+            <AssignCardDialog libraryEntry={libraryEntry} open={cardDialogOpen} onClose={() => setCardDialogOpen(false)} />
+          }
         </Box>
 
       ) : (
@@ -33,6 +33,8 @@ export default function TrackList({tracks, onSortEnd, onDelete}: Props) {
                       <>
                         {track.variant === 'file' && `Filename: ${track.trackSource?.title}`}
                         {track.variant === 'stream' && `URL: ${track.trackSource?.url}`}
+                        {track.variant === 'episode' && track.trackSource?.publishedAt &&
+                          `Published: ${new Date(track.trackSource.publishedAt).toLocaleDateString()}`}
                         {track.variant === 'spotify' && `SpotifyID: ${track.trackSource?.spotifyId}`}
                       </>
                     }
@@ -0,0 +1,117 @@
+import {useEffect, useState} from "react";
+import {Alert, Card, CardContent, IconButton, Slider, Stack, Typography} from "@mui/material";
+import {Pause, PlayArrow, SkipNext, SkipPrevious, VolumeUp} from "@mui/icons-material";
+import {Link} from "react-router-dom";
+import {controlPlayer, getPlayerState, PlayerState, seekPlayer, setPlayerVolume} from "@/util/api";
+import {notify} from "@/components/Notification";
+
+const POLL_INTERVAL = 1000;
+
+export default function Player() {
+  const [state, setState] = useState<PlayerState>();
+  const [unavailable, setUnavailable] = useState(false);
+  // Values of the sliders while they are dragged, polling would reset them otherwise
+  const [seeking, setSeeking] = useState<number>();
+  const [volume, setVolume] = useState<number>();
+
+  const loadState = async () => {
+    try {
+      setState(await getPlayerState());
+      setUnavailable(false);
+    } catch (e) {
+      setUnavailable(true);
+    }
+  }
+
+  useEffect(() => {
+    loadState();
+    const interval = setInterval(loadState, POLL_INTERVAL);
+    return () => clearInterval(interval);
+  }, []);
+
+  const handleCommand = (command: 'pause' | 'resume' | 'next' | 'prev') => async () => {
+    try {
+      await controlPlayer(command);
+      await loadState();
+    } catch (e) {
+      notify('error', `Could not ${command}: ${e}`);
+    }
+  }
+
+  const handleSeek = async (position: number) => {
+    try {
+      await seekPlayer(position);
+      await loadState();
+    } catch (e) {
+      notify('error', `Could not seek: ${e}`);
+    }
+    setSeeking(undefined);
+  }
+
+  const handleVolume = async (value: number) => {
+    try {
+      await setPlayerVolume(value / 100);
+      await loadState();
+    } catch (e) {
+      notify('error', `Could not set volume: ${e}`);
+    }
+    setVolume(undefined);
+  }
+
+  return (
+    <main>
+      <Typography variant={'h4'} sx={{mb: '16px', mt: '32px'}}>
+        Player
+      </Typography>
+      <Typography variant="subtitle1" sx={{mb: 5}}>
+        Control what the box is playing right now.
+      </Typography>
+      {unavailable && <Alert severity="warning" sx={{mb: 3}}>The player of the box is not running.</Alert>}
+      {state && (
+        <Card>
+          <CardContent>
+            <Stack rowGap={2}>
+              <Typography variant="h6">
+                {state.name ?? 'Nothing is playing'}
+              </Typography>
+              {state.parentId !== undefined && state.parentId !== null && (
+                <Link to={`/mediaLibrary/${state.parentId}`}>Open folder</Link>
+              )}
+              <Slider
+                value={seeking ?? state.progress}
+                min={0}
+                max={100}
+                step={0.1}
+                disabled={state.libraryEntryId === undefined || state.libraryEntryId === null || state.live}
+                onChange={(_, value) => setSeeking(value as number)}
+                onChangeCommitted={(_, value) => handleSeek(value as number)}
+              />
+              <Stack direction="row" justifyContent="center" columnGap={2}>
+                <IconButton size="large" onClick={handleCommand('prev')}>
+                  <SkipPrevious/>
+                </IconButton>
+                <IconButton size="large" onClick={handleCommand(state.paused ? 'resume' : 'pause')}>
+                  {state.paused ? <PlayArrow/> : <Pause/>}
+                </IconButton>
+                <IconButton size="large" onClick={handleCommand('next')}>
+                  <SkipNext/>
+                </IconButton>
+              </Stack>
+              <Stack direction="row" alignItems="center" columnGap={2}>
+                <VolumeUp/>
+                <Slider
+                  value={volume ?? Math.round(state.volume * 100)}
+                  min={0}
+                  max={100}
+                  valueLabelDisplay="auto"
+                  onChange={(_, value) => setVolume(value as number)}
+                  onChangeCommitted={(_, value) => handleVolume(value as number)}
+                />
+              </Stack>
+            </Stack>
+          </CardContent>
+        </Card>
+      )}
+    </main>
+  )
+}
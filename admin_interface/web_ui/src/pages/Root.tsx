@@ -24,6 +24,12 @@ export default function Root() {
             <Button onClick={handleNavigate("/mediaLibrary")} sx={{my: 2, color: 'white', display: 'block', ...activeStyle('/mediaLibrary')}}>
               Media library
             </Button>
+            <Button onClick={handleNavigate("/cards")} sx={{my: 2, color: 'white', display: 'block', ...activeStyle('/cards')}}>
+              RFID cards
+            </Button>
+            <Button onClick={handleNavigate("/player")} sx={{my: 2, color: 'white', display: 'block', ...activeStyle('/player')}}>
+              Player
+            </Button>
           </Box>
         </Container>
       </AppBar>
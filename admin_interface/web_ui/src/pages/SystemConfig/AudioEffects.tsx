@@ -0,0 +1,130 @@
+import {useEffect, useState} from "react";
+import {
+  Accordion,
+  AccordionDetails,
+  AccordionSummary,
+  Checkbox,
+  FormControl,
+  FormControlLabel,
+  FormHelperText,
+  FormLabel,
+  Grid,
+  Slider,
+  Stack,
+  Typography
+} from "@mui/material";
+import {AudioConfig} from "@db-models/AudioConfig";
+import {notify} from "@/components/Notification";
+import {getAudioConfig, putAudioConfig} from "@/util/api";
+
+/**
+ * Effects are applied by the player while playing, so they are saved on every change to be heard right away
+ */
+export default function AudioEffects() {
+  const [config, setConfig] = useState<AudioConfig>();
+
+  useEffect(() => {
+    (async () => {
+      try {
+        setConfig(await getAudioConfig());
+      } catch (e) {
+        notify('error', `Could not load audio effects: ${e}`);
+      }
+    })();
+  }, []);
+
+  const save = async (changes: Partial<AudioConfig>) => {
+    if (!config) {
+      return;
+    }
+    try {
+      setConfig(await putAudioConfig({...config, ...changes}));
+    } catch (e) {
+      notify('error', `Saving audio effects failed: ${e}`);
+    }
+  }
+
+  if (!config) {
+    return null;
+  }
+
+  const renderShelf = (name: 'bass' | 'treble', label: string) => (
+    <FormControl fullWidth>
+      <FormLabel>{label}</FormLabel>
+      <Grid container spacing={2} alignItems={"center"}>
+        <Grid item xs={6}>
+          <Slider
+            aria-label={label}
+            valueLabelDisplay="auto"
+            step={1}
+            min={-12}
+            max={12}
+            marks={[{value: 0, label: '0 dB'}]}
+            value={config[name]}
+            onChange={(_, value) => setConfig({...config, [name]: value as number})}
+            onChangeCommitted={(_, value) => save({[name]: value as number})}
+          />
+        </Grid>
+      </Grid>
+    </FormControl>
+  );
+
+  return (
+    <Accordion>
+      <AccordionSummary>
+        <Typography variant={"h5"}>Audio effects</Typography>
+      </AccordionSummary>
+      <AccordionDetails>
+        <Stack rowGap={3}>
+          <div>
+            {renderShelf('bass', 'Bass')}
+            {renderShelf('treble', 'Treble')}
+            <FormHelperText sx={{ml: 0}}>
+              Raises or lowers low and high frequencies in decibels. Small speakers often sound fuller with more bass
+            </FormHelperText>
+          </div>
+          <div>
+            <FormControlLabel
+              label="Limiter"
+              control={
+                <Checkbox checked={config.limiter} onChange={(event) => save({limiter: event.target.checked})}/>
+              }
+            />
+            <FormControl fullWidth>
+              <FormLabel>Limiter threshold</FormLabel>
+              <Grid container spacing={2} alignItems={"center"}>
+                <Grid item xs={6}>
+                  <Slider
+                    aria-label="Limiter threshold"
+                    valueLabelDisplay="auto"
+                    step={1}
+                    min={-30}
+                    max={0}
+                    disabled={!config.limiter}
+                    value={config.limiterThreshold}
+                    onChange={(_, value) => setConfig({...config, limiterThreshold: value as number})}
+                    onChangeCommitted={(_, value) => save({limiterThreshold: value as number})}
+                  />
+                </Grid>
+              </Grid>
+            </FormControl>
+            <FormHelperText sx={{ml: 0}}>
+              Keeps peaks below the threshold in decibels, so raised bass or loud tracks do not distort
+            </FormHelperText>
+          </div>
+          <div>
+            <FormControlLabel
+              label="Mono"
+              control={
+                <Checkbox checked={config.mono} onChange={(event) => save({mono: event.target.checked})}/>
+              }
+            />
+            <FormHelperText sx={{ml: 0}}>
+              Plays both stereo channels on every speaker, for boxes with a single speaker. Applies from the next track on
+            </FormHelperText>
+          </div>
+        </Stack>
+      </AccordionDetails>
+    </Accordion>
+  )
+}
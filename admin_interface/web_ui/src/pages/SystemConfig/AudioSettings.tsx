@@ -150,6 +150,143 @@ export default function AudioSettings({control}: Props) {
               }
             />
           </div>
+          <div>
+            <FormLabel>Quiet hours</FormLabel>
+            <Grid container spacing={2} alignItems={"center"}>
+              <Grid item xs={2}>
+                <Controller
+                  name={'quietHoursStart'}
+                  control={control}
+                  render={({field, fieldState}) =>
+                    <TextField type="time" label="Start" InputLabelProps={{shrink: true}} error={fieldState.invalid} {...field}/>
+                  }
+                />
+              </Grid>
+              <Grid item xs={2}>
+                <Controller
+                  name={'quietHoursEnd'}
+                  control={control}
+                  render={({field, fieldState}) =>
+                    <TextField type="time" label="End" InputLabelProps={{shrink: true}} error={fieldState.invalid} {...field}/>
+                  }
+                />
+              </Grid>
+              <Grid item xs={4}>
+                <Controller
+                  name={'quietHoursVolume'}
+                  rules={{
+                    required: true, max: 100, min: 0, validate: (value) => !isNaN(Number(value))
+                  }}
+                  control={control}
+                  render={({field}) =>
+                    <Slider
+                      aria-label="Maximum volume during quiet hours"
+                      valueLabelDisplay="auto"
+                      step={1}
+                      min={0}
+                      max={100}
+                      {...field}
+                    />
+                  }
+                />
+              </Grid>
+            </Grid>
+            <FormHelperText sx={{ml: 0}}>
+              Maximum volume in percent between start and end, e.g. 19:00 to 07:00. The volume lowers gradually when they
+              start. Leave the times empty to disable them
+            </FormHelperText>
+          </div>
+          <div>
+            <Controller
+              name={'crossfade'}
+              rules={{
+                required: true, max: 10, min: 0, validate: (value) => !isNaN(Number(value))
+              }}
+              control={control}
+              render={({field, fieldState}) =>
+                <FormControl fullWidth error={fieldState.invalid}>
+                  <FormLabel>Crossfade</FormLabel>
+                  <Grid container spacing={2} alignItems={"center"}>
+                    <Grid item xs={1}>
+                      <TextField
+                        error={fieldState.invalid}
+                        {...field}
+                      />
+                    </Grid>
+                    <Grid item xs={5}>
+                      <Slider
+                        aria-label="Crossfade"
+                        valueLabelDisplay="auto"
+                        step={1}
+                        min={0}
+                        max={10}
+                        {...field}
+                      />
+                    </Grid>
+                  </Grid>
+                  <FormHelperText sx={{ml: 0}}>Specify in seconds how long tracks are faded into each other. 0 plays them gapless</FormHelperText>
+                </FormControl>
+              }
+            />
+          </div>
+          <div>
+            <Controller
+              name={'loudnessNormalization'}
+              control={control}
+              render={({field, fieldState}) =>
+                <FormControl error={fieldState.invalid}>
+                  <InputLabel id={"loudness-normalization-label"}>Loudness normalization</InputLabel>
+                  <Select
+                    variant="outlined"
+                    labelId={"loudness-normalization-label"}
+                    label={"Loudness normalization"} {...field}>
+                    <MenuItem value={"off"}>Off</MenuItem>
+                    <MenuItem value={"track"}>Per track</MenuItem>
+                    <MenuItem value={"album"}>Per folder</MenuItem>
+                  </Select>
+                  <FormHelperText sx={{ml: 0}}>
+                    Plays uploaded tracks equally loud. Per folder keeps the differences between the tracks of a folder
+                  </FormHelperText>
+                </FormControl>
+              }
+            />
+          </div>
+          <div>
+            <Controller
+              name={'audioCacheSize'}
+              rules={{
+                required: true, max: 65536, min: 0, validate: (value) => !isNaN(Number(value))
+              }}
+              control={control}
+              render={({field, fieldState}) =>
+                <FormControl fullWidth error={fieldState.invalid}>
+                  <FormLabel>Audio cache size</FormLabel>
+                  <Grid container spacing={2} alignItems={"center"}>
+                    <Grid item xs={1}>
+                      <TextField
+                        error={fieldState.invalid}
+                        {...field}
+                      />
+                    </Grid>
+                    <Grid item xs={5}>
+                      <Slider
+                        aria-label="Audio cache size"
+                        valueLabelDisplay="auto"
+                        step={256}
+                        min={0}
+                        max={65536}
+                        {...field}
+                      />
+                    </Grid>
+                  </Grid>
+                  <FormHelperText sx={{ml: 0}}>
+                    Megabytes of podcasts and streamed files kept on the box to play them again without network. Pinned
+                    folders are always kept, the files played longest ago are removed first
+                  </FormHelperText>
+                </FormControl>
+              }
+            />
+          </div>
         </Stack>
       </AccordionDetails>
     </Accordion>
@@ -0,0 +1,106 @@
+import {ChangeEvent, useState} from "react";
+import {
+  Accordion,
+  AccordionDetails,
+  AccordionSummary,
+  Box,
+  Button,
+  Checkbox,
+  FormControlLabel,
+  FormHelperText,
+  FormLabel,
+  LinearProgress,
+  Stack,
+  styled,
+  Typography
+} from "@mui/material";
+import {notify} from "@/components/Notification";
+import {getLibraryExportUrl, importLibraryBackup} from "@/util/api";
+
+const VisuallyHiddenInput = styled('input')({
+  clip: 'rect(0 0 0 0)',
+  clipPath: 'inset(50%)',
+  height: 1,
+  overflow: 'hidden',
+  position: 'absolute',
+  bottom: 0,
+  left: 0,
+  whiteSpace: 'nowrap',
+  width: 1,
+});
+
+export default function BackupSettings() {
+  const [includeSpotify, setIncludeSpotify] = useState(false);
+  const [replace, setReplace] = useState(false);
+  const [restoreConfig, setRestoreConfig] = useState(true);
+  const [uploadProgress, setUploadProgress] = useState<number>();
+
+  const handleImport = (event: ChangeEvent<HTMLInputElement>) => {
+    const backup = event.target.files?.[0];
+    event.target.value = '';
+    if (!backup) {
+      return;
+    }
+    if (replace && !confirm('The current library will be deleted. Continue?')) {
+      return;
+    }
+
+    const notificationKey = Math.random().toString();
+    setUploadProgress(0);
+    importLibraryBackup(backup, replace, restoreConfig, setUploadProgress, (error, entries) => {
+      setUploadProgress(undefined);
+      if (error) {
+        notify('error', `Restoring backup failed: ${error}`, undefined, notificationKey);
+      } else {
+        notify('success', `Restored ${entries?.length || 0} library entries`, 2000, notificationKey);
+      }
+    });
+  }
+
+  return (
+    <Accordion>
+      <AccordionSummary>
+        <Typography variant={"h5"}>Backup</Typography>
+      </AccordionSummary>
+      <AccordionDetails>
+        <Stack rowGap={3}>
+          <div>
+            <FormLabel>Export</FormLabel>
+            <Box>
+              <FormControlLabel
+                control={<Checkbox checked={includeSpotify} onChange={(_, checked) => setIncludeSpotify(checked)}/>}
+                label={"Include spotify configuration"}
+              />
+            </Box>
+            <Button variant="outlined" href={getLibraryExportUrl(includeSpotify)}>Download backup</Button>
+            <FormHelperText sx={{ml: 0}}>
+              Contains the media library with all files and the configuration. The spotify configuration contains your
+              credentials, only include it if you keep the backup safe.
+            </FormHelperText>
+          </div>
+          <div>
+            <FormLabel>Restore</FormLabel>
+            <Box>
+              <FormControlLabel
+                control={<Checkbox checked={replace} onChange={(_, checked) => setReplace(checked)}/>}
+                label={"Replace current library"}
+              />
+              <FormControlLabel
+                control={<Checkbox checked={restoreConfig} onChange={(_, checked) => setRestoreConfig(checked)}/>}
+                label={"Restore configuration"}
+              />
+            </Box>
+            <Button component="label" variant="outlined" disabled={uploadProgress !== undefined}>
+              Restore backup
+              <VisuallyHiddenInput type="file" name="backup" accept=".zip,application/zip" onChange={handleImport}/>
+            </Button>
+            {uploadProgress !== undefined && <LinearProgress variant="determinate" value={uploadProgress * 100} sx={{mt: 2}}/>}
+            <FormHelperText sx={{ml: 0}}>
+              Without replacing, the entries of the backup are added behind the current library.
+            </FormHelperText>
+          </div>
+        </Stack>
+      </AccordionDetails>
+    </Accordion>
+  )
+}
@@ -0,0 +1,68 @@
+import {
+  Accordion,
+  AccordionDetails,
+  AccordionSummary,
+  FormControl,
+  FormHelperText,
+  InputLabel,
+  MenuItem,
+  Select,
+  Stack,
+  Typography
+} from "@mui/material";
+import {Control, Controller} from "react-hook-form";
+
+interface Props {
+  control: Control<any>,
+}
+
+const PINS = [5, 6, 12, 13, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27];
+
+const BUTTONS = [
+  {name: 'playPausePin', label: 'Play / pause button', help: 'Holding it for the power off button delay shuts the box down.'},
+  {name: 'nextPin', label: 'Next track button'},
+  {name: 'prevPin', label: 'Previous track button'},
+  {name: 'volumeUpPin', label: 'Volume up button'},
+  {name: 'volumeDownPin', label: 'Volume down button'},
+  {name: 'rotaryClkPin', label: 'Rotary encoder CLK', help: 'Turning the rotary encoder changes the volume.'},
+  {name: 'rotaryDtPin', label: 'Rotary encoder DT'},
+];
+
+export default function ButtonSettings({control}: Props) {
+
+  return (
+    <Accordion>
+      <AccordionSummary>
+        <Typography variant={"h5"}>Button settings</Typography>
+      </AccordionSummary>
+      <AccordionDetails>
+        <Stack rowGap={3}>
+          <Typography variant="body2">
+            Buttons connect the GPIO pin to ground while pressed. Changes are applied after restarting the box.
+          </Typography>
+          {BUTTONS.map(button => (
+            <div key={button.name}>
+              <Controller
+                name={button.name}
+                control={control}
+                render={({field, fieldState}) =>
+                  <FormControl error={fieldState.invalid}>
+                    <InputLabel id={`${button.name}-label`}>{button.label}</InputLabel>
+                    <Select
+                      variant="outlined"
+                      labelId={`${button.name}-label`}
+                      label={button.label} {...field}>
+                      <MenuItem value={0}>None</MenuItem>
+                      {PINS.map(pin => <MenuItem key={pin} value={pin}>GPIO {pin}</MenuItem>)}
+                    </Select>
+                    {button.help && <FormHelperText sx={{ml: 0}}>{button.help}</FormHelperText>}
+                  </FormControl>
+                }
+              />
+            </div>
+          ))}
+        </Stack>
+      </AccordionDetails>
+    </Accordion>
+  )
+}
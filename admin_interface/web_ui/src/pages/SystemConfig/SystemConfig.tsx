@@ -5,7 +5,10 @@ import TimerConfig from "./TimerConfig";
 import SystemSettings from "./SystemSettings";
 import DisplaySettings from "./DisplaySettings";
 import AudioSettings from "./AudioSettings";
+import AudioEffects from "./AudioEffects";
 import PowerSettings from "./PowerSettings";
+import ButtonSettings from "./ButtonSettings";
+import BackupSettings from "./BackupSettings";
 import {notify} from "@/components/Notification";
 import {getSystemConfig, putSystemConfig} from "@/util/api";
 
@@ -37,6 +40,11 @@ export default function SystemConfig() {
       volume: 40, // command x
       powerOnVolume: 40, // ???
       maxVolume: 100, // self handled
+      crossfade: 0, // self handled
+      quietHoursStart: '', // self handled
+      quietHoursEnd: '', // self handled
+      quietHoursVolume: 30, // self handled
+      loudnessNormalization: 'track', // self handled
       // power
       ledPin: 25, // command x
       ledBrightness: 100, // script mupi_start_led.sh
@@ -44,6 +52,14 @@ export default function SystemConfig() {
       powerOffBtnDelay: 2, // script handled
       powerOffPin: 4,
       cutPin: 17,
+      // buttons
+      playPausePin: 0, // self handled
+      nextPin: 0, // self handled
+      prevPin: 0, // self handled
+      volumeUpPin: 0, // self handled
+      volumeDownPin: 0, // self handled
+      rotaryClkPin: 0, // self handled
+      rotaryDtPin: 0, // self handled
     }
   });
 
@@ -99,8 +115,11 @@ export default function SystemConfig() {
           <DisplaySettings control={control}/>
           <AudioSettings control={control}/>
           <PowerSettings control={control}/>
+          <ButtonSettings control={control}/>
         </div>
       </form>
+      <AudioEffects/>
+      <BackupSettings/>
     </main>
   )
 }
\ No newline at end of file
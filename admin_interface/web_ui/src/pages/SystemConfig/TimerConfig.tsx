@@ -53,7 +53,7 @@ export default function TimerConfig({control}: Props) {
                       />
                     </Grid>
                   </Grid>
-                  <FormHelperText sx={{ml: 0}}>How many minutes to shut down TinyGhettoBox</FormHelperText>
+                  <FormHelperText sx={{ml: 0}}>How many minutes the sleep timer of the player bar plays before fading out and stopping</FormHelperText>
                 </FormControl>
               }
             />
@@ -87,7 +87,7 @@ export default function TimerConfig({control}: Props) {
                     </Grid>
                   </Grid>
                   <FormHelperText sx={{ml: 0}}>
-                    How many minutes can the TinyGhettoBox be idle (without playing) before it gets shutdown
+                    How many minutes can the TinyGhettoBox be idle (without playing) before it gets shutdown, 0 disables it
                   </FormHelperText>
                 </FormControl>
               }
@@ -122,7 +122,7 @@ export default function TimerConfig({control}: Props) {
                     </Grid>
                   </Grid>
                   <FormHelperText sx={{ml: 0}}>
-                    How many minutes after boot the display should be turned off? <br />
+                    How many minutes without touching the display it should be turned off? It dims shortly before, 0 disables it <br />
                     <i>Note: Depending on the display, the screen goes black but the backlight remains on.</i>
                   </FormHelperText>
                 </FormControl>
@@ -1,6 +1,9 @@
 import {SystemConfig} from "@db-models/SystemConfig";
+import {AudioConfig} from "@db-models/AudioConfig";
 import {SpotifyConfig} from "@db-models/SpotifyConfig";
 import {LibraryEntry} from "@db-models/LibraryEntry";
+import {CardMapping} from "@db-models/CardMapping";
+import {RepeatMode} from "@db-models/RepeatMode";
 import {Form} from "react-router-dom";
 
 function snakeToCamel(some: string): string {
@@ -47,7 +50,7 @@ async function api<T>(method: 'GET' | 'POST' | 'PUT' | 'DELETE', path: string, p
   return await response.text() as T;
 }
 
-export function upload(path: string, form: FormData, onProgress: (progress: number) => void, onLoad: (error?: string) => void): void {
+export function upload(path: string, form: FormData, onProgress: (progress: number) => void, onLoad: (error?: string, response?: any) => void): void {
   const xhr = new XMLHttpRequest();
   xhr.open('POST', path, true);
   xhr.upload.addEventListener('progress', (event: ProgressEvent) => {
@@ -61,7 +64,8 @@ export function upload(path: string, form: FormData, onProgress: (progress: numb
   });
   xhr.addEventListener('load', () => {
     if (xhr.status >= 200 && xhr.status < 300) {
-      onLoad();
+      const isJson = xhr.getResponseHeader('Content-Type') === 'application/json';
+      onLoad(undefined, isJson ? convertCaseDeep(snakeToCamel, JSON.parse(xhr.responseText)) : undefined);
     } else {
       onLoad(xhr.statusText);
     }
@@ -96,6 +100,14 @@ export async function putSystemConfig(config: SystemConfig): Promise<SystemConfi
   return put<SystemConfig>('/api/system/config', config);
 }
 
+export async function getAudioConfig(): Promise<AudioConfig> {
+  return get<AudioConfig>('/api/system/audio');
+}
+
+export async function putAudioConfig(config: AudioConfig): Promise<AudioConfig> {
+  return put<AudioConfig>('/api/system/audio', config);
+}
+
 export async function getSpotifyConfig(): Promise<SpotifyConfig> {
   return get<SpotifyConfig>('/api/spotify/config');
 }
@@ -112,6 +124,10 @@ export async function putLibraryEntry(id: number, entry: LibraryEntry): Promise<
   return put<LibraryEntry>(`/api/library/${id}`, entry);
 }
 
+export async function putLibraryEntryPin(id: number, pinned: boolean): Promise<LibraryEntry> {
+  return api('PUT', `/api/library/${id}/pin`, {pinned});
+}
+
 export async function delLibraryEntry(id: number): Promise<void> {
   return del<void>(`/api/library/${id}`);
 }
@@ -120,10 +136,107 @@ export async function postLibraryEntries(parent_id: number, entries: LibraryEntr
   return post<LibraryEntry[]>(`/api/library?parent_id=${parent_id}`, entries);
 }
 
-export function uploadLibraryEntryFile(file: File, onProgress: (progress: number) => void, onLoad: (error?: string) => void) {
+export type AudioMetadata = {
+  title?: string,
+  artist?: string,
+  album?: string,
+  trackNumber?: number,
+  duration?: number,
+  image?: number[],
+};
+
+export function uploadLibraryEntryFile(file: File, onProgress: (progress: number) => void, onLoad: (error?: string, metadata?: AudioMetadata) => void) {
   const formData = new FormData();
   formData.append('name', file.name);
   formData.append('track', file);
   upload('/api/library/upload', formData, onProgress, onLoad);
 }
 
+export type ImportProgress = {
+  state: 'storing_files' | 'creating_entries' | 'done' | 'failed',
+  totalFiles: number,
+  storedFiles: number,
+  totalEntries: number,
+  createdEntries: number,
+  error?: string,
+};
+
+export function importLibraryEntries(parentId: number, source: File | string, onProgress: (progress: number) => void, onLoad: (error?: string, response?: { id: number }) => void) {
+  const formData = new FormData();
+  if (typeof source === 'string') {
+    formData.append('path', source);
+  } else {
+    formData.append('archive', source);
+  }
+  upload(`/api/library/bulk-import?parent_id=${parentId}`, formData, onProgress, onLoad);
+}
+
+export async function getLibraryImport(id: number): Promise<ImportProgress> {
+  return get<ImportProgress>(`/api/library/bulk-import/${id}`);
+}
+
+export function getLibraryExportUrl(includeSpotify: boolean): string {
+  return `/api/library/export?include_spotify=${includeSpotify}`;
+}
+
+export function importLibraryBackup(backup: File, replace: boolean, restoreConfig: boolean, onProgress: (progress: number) => void, onLoad: (error?: string, entries?: LibraryEntry[]) => void) {
+  const formData = new FormData();
+  formData.append('backup', backup);
+  formData.append('replace', String(replace));
+  formData.append('restore_config', String(restoreConfig));
+  upload('/api/library/import', formData, onProgress, onLoad);
+}
+
+export async function getCards(): Promise<CardMapping[]> {
+  return get<CardMapping[]>('/api/cards');
+}
+
+export type CardLearnState = {
+  since: string,
+  card?: CardMapping,
+};
+
+export async function getLearnedCard(since?: string): Promise<CardLearnState> {
+  return get<CardLearnState>(since ? `/api/cards/learn?since=${encodeURIComponent(since)}` : '/api/cards/learn');
+}
+
+export async function putCard(cardUid: string, libraryEntryId?: number): Promise<CardMapping> {
+  return api('PUT', `/api/cards/${encodeURIComponent(cardUid)}`, {libraryEntryId: libraryEntryId ?? null});
+}
+
+export async function delCard(cardUid: string): Promise<void> {
+  return del<void>(`/api/cards/${encodeURIComponent(cardUid)}`);
+}
+
+export type PlayerState = {
+  playbackState: 'stopped' | 'loading' | 'playing' | 'paused' | 'buffering' | 'ended',
+  paused: boolean,
+  libraryEntryId?: number,
+  parentId?: number,
+  name?: string,
+  progress: number, // 0-100
+  live: boolean, // Live streams have no progress and can not be seeked
+  volume: number, // 0-1
+  shuffle: boolean,
+  repeatMode: RepeatMode,
+};
+
+export async function getPlayerState(): Promise<PlayerState> {
+  return get<PlayerState>('/api/player/state');
+}
+
+export async function playLibraryEntry(libraryEntryId: number): Promise<void> {
+  return api('POST', '/api/player/play', {libraryEntryId});
+}
+
+export async function controlPlayer(command: 'pause' | 'resume' | 'next' | 'prev'): Promise<void> {
+  return api('POST', `/api/player/${command}`);
+}
+
+export async function seekPlayer(position: number): Promise<void> {
+  return api('POST', '/api/player/seek', {position});
+}
+
+export async function setPlayerVolume(volume: number): Promise<void> {
+  return api('PUT', '/api/player/volume', {volume});
+}
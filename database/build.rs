@@ -1,13 +1,17 @@
 use regex::Regex;
 use ts_rs::TS;
 
-use model::library_entry::{Model as LibraryEntry, Variant};
+use model::audio_config::Model as AudioConfig;
+use model::card_mapping::Model as CardMapping;
+use model::library_entry::{Model as LibraryEntry, RepeatMode, Variant};
 use model::spotify_config::Model as SpotifyConfig;
-use model::system_config::Model as SystemConfig;
+use model::system_config::{Model as SystemConfig, NormalizationMode};
 use model::track_source::Model as TrackSource;
 
 #[path = "src/util.rs"]
 mod util;
+// Only the types are exported, everything else of the models is unused here
+#[allow(dead_code)]
 #[path = "src/model/mod.rs"]
 mod model;
 
@@ -31,6 +35,14 @@ fn main() {
     std::fs::write("types/Variant.d.ts", fix_types(variant))
         .expect("Failed to write file");
 
+    let repeat_mode = RepeatMode::export_to_string().unwrap();
+    std::fs::write("types/RepeatMode.d.ts", fix_types(repeat_mode))
+        .expect("Failed to write file");
+
+    let normalization_mode = NormalizationMode::export_to_string().unwrap();
+    std::fs::write("types/NormalizationMode.d.ts", fix_types(normalization_mode))
+        .expect("Failed to write file");
+
     let system_config = SystemConfig::export_to_string().unwrap();
     std::fs::write("types/SystemConfig.d.ts", fix_types(system_config))
         .expect("Failed to write file");
@@ -39,10 +51,18 @@ fn main() {
     std::fs::write("types/SpotifyConfig.d.ts", fix_types(spotify_config))
         .expect("Failed to write file");
 
+    let audio_config = AudioConfig::export_to_string().unwrap();
+    std::fs::write("types/AudioConfig.d.ts", fix_types(audio_config))
+        .expect("Failed to write file");
+
     let track_source = TrackSource::export_to_string().unwrap();
     std::fs::write("types/TrackSource.d.ts", fix_types(track_source))
         .expect("Failed to write file");
 
+    let card_mapping = CardMapping::export_to_string().unwrap();
+    std::fs::write("types/CardMapping.d.ts", fix_types(card_mapping))
+        .expect("Failed to write file");
+
     // Sea orm requires id field to be ignored for serde deserialize which leads to id field missing in types.
     // We are adding the id field here manually.
     let library_entry = LibraryEntry::export_to_string().unwrap();
@@ -0,0 +1,131 @@
+use std::collections::HashSet;
+use std::fs::{create_dir_all, read_dir, remove_file, rename, File};
+use std::io::{copy, Read};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+
+/// Keeps remote tracks on disk, addressed by the sha256 hash of their url, so they play again without network. The
+/// modification time of a file is the time it was used last, the ones not used for the longest time are removed
+/// first once the cache grows beyond its size.
+pub struct AudioCache;
+
+impl AudioCache {
+    fn get_folder() -> PathBuf {
+        PathBuf::from(std::env::var("AUDIO_CACHE_FOLDER").unwrap_or("audio_cache".to_string()))
+    }
+
+    fn get_hash(url: &str) -> String {
+        format!("{:x}", Sha256::digest(url.as_bytes()))
+    }
+
+    fn get_path(url: &str) -> PathBuf {
+        let hash = Self::get_hash(url);
+        // Files are spread over subfolders to keep the folders small
+        Self::get_folder().join(&hash[..2]).join(hash)
+    }
+
+    pub fn contains(url: &str) -> bool {
+        Self::get_path(url).exists()
+    }
+
+    /// Returns the file of the url if it is cached and marks it as used
+    pub fn get(url: &str) -> Option<PathBuf> {
+        let path = Self::get_path(url);
+        if !path.exists() {
+            return None;
+        }
+        if let Err(error) =
+            File::options().append(true).open(&path).and_then(|file| file.set_modified(SystemTime::now()))
+        {
+            warn!("Could not mark cached file of {} as used: {}", url, error);
+        }
+        Some(path)
+    }
+
+    /// Downloads the url into the cache, unless it is cached already. Returns whether it is cached afterwards, live
+    /// streams without length, playlists and files larger than the size limit are not. Runs blocking.
+    pub fn store(url: &str, size_limit: u64) -> Result<bool, String> {
+        if Self::contains(url) {
+            return Ok(true);
+        }
+
+        let response = ureq::get(url).call().map_err(|error| format!("Could not fetch {}: {}", url, error))?;
+        let content_type = response.content_type().to_lowercase();
+        let is_audio = (content_type.starts_with("audio/") || content_type == "application/octet-stream")
+            && !content_type.contains("mpegurl")
+            && !content_type.contains("scpls");
+        let length = match response.header("content-length").and_then(|length| length.parse::<u64>().ok()) {
+            Some(length) if is_audio && length <= size_limit && response.header("icy-metaint").is_none() => length,
+            _ => return Ok(false),
+        };
+
+        let folder = Self::get_folder();
+        create_dir_all(&folder).map_err(|error| format!("Could not create audio cache folder: {}", error))?;
+        // Files are only moved into place once complete, so an interrupted download is never played
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos();
+        let temp_path = folder.join(format!(".{}.tmp", nanos));
+        let written =
+            File::create(&temp_path).and_then(|mut file| copy(&mut response.into_reader().take(length), &mut file));
+        let path = Self::get_path(url);
+        let result = match written {
+            Ok(written) if written == length => create_dir_all(path.parent().unwrap())
+                .and_then(|_| rename(&temp_path, &path))
+                .map_err(|error| error.to_string()),
+            Ok(written) => Err(format!("download ended after {} of {} bytes", written, length)),
+            Err(error) => Err(error.to_string()),
+        };
+        if let Err(error) = result {
+            let _ = remove_file(&temp_path);
+            return Err(format!("Could not cache {}: {}", url, error));
+        }
+
+        info!("Cached {} bytes of {}", length, url);
+        Ok(true)
+    }
+
+    /// Removes the files used least recently until the cache fits into the size limit. Files of pinned urls are kept.
+    pub fn evict(size_limit: u64, pinned_urls: &HashSet<String>) -> Result<(), String> {
+        let folder = Self::get_folder();
+        if !folder.exists() {
+            return Ok(());
+        }
+
+        let pinned_hashes = pinned_urls.iter().map(|url| Self::get_hash(url)).collect::<HashSet<_>>();
+        let mut size = 0;
+        let mut unpinned = vec![];
+        let sub_folders = read_dir(folder).map_err(|error| format!("Could not read audio cache folder: {}", error))?;
+        for sub_folder in sub_folders.flatten() {
+            let files = match read_dir(sub_folder.path()) {
+                Ok(files) => files,
+                Err(_) => continue,
+            };
+            for file in files.flatten() {
+                let metadata = match file.metadata() {
+                    Ok(metadata) => metadata,
+                    Err(_) => continue,
+                };
+                size += metadata.len();
+                if !pinned_hashes.contains(file.file_name().to_string_lossy().as_ref()) {
+                    unpinned.push((metadata.modified().unwrap_or(UNIX_EPOCH), metadata.len(), file.path()));
+                }
+            }
+        }
+
+        unpinned.sort_by_key(|(used_at, _, _)| *used_at);
+        for (_, length, path) in unpinned {
+            if size <= size_limit {
+                break;
+            }
+            info!("Removing cached file {:?}", path.file_name().unwrap_or_default());
+            match remove_file(&path) {
+                Ok(_) => size -= length,
+                Err(error) => warn!("Could not remove cached file {:?}: {}", path, error),
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,35 @@
+use std::io::Read;
+
+use tracing::warn;
+
+// Covers are stored in the database, so larger ones are skipped
+const MAX_IMAGE_SIZE: u64 = 5 * 1024 * 1024;
+
+/// Downloads the cover at the url. A missing cover is no error, as the one of the parent entry is shown then. Runs
+/// blocking.
+pub fn fetch_image(url: &str) -> Option<Vec<u8>> {
+    let response = match ureq::get(url).call() {
+        Ok(response) if response.content_type().starts_with("image/") => response,
+        Ok(response) => {
+            warn!("Cover {} has no image content type but {}", url, response.content_type());
+            return None;
+        }
+        Err(error) => {
+            warn!("Could not fetch cover {}: {}", url, error);
+            return None;
+        }
+    };
+
+    let mut image = vec![];
+    match response.into_reader().take(MAX_IMAGE_SIZE + 1).read_to_end(&mut image) {
+        Ok(size) if size as u64 <= MAX_IMAGE_SIZE => Some(image),
+        Ok(_) => {
+            warn!("Cover {} is larger than {} bytes", url, MAX_IMAGE_SIZE);
+            None
+        }
+        Err(error) => {
+            warn!("Could not read cover {}: {}", url, error);
+            None
+        }
+    }
+}
@@ -3,12 +3,21 @@ pub use sea_orm::{Database, DatabaseConnection, DbErr};
 use tracing::info;
 use tracing::log::LevelFilter;
 
+pub use audio_cache::AudioCache;
+pub use image::fetch_image;
+pub use media_store::MediaStore;
 pub use migration::{Migrator, MigratorTrait};
+pub use repository::audio_config::AudioConfigRepository;
+pub use repository::card_mapping::CardMappingRepository;
 pub use repository::library_entry::LibraryEntryRepository;
+pub use repository::playback_position::PlaybackPositionRepository;
 pub use repository::spotify_config::SpotifyConfigRepository;
 pub use repository::system_config::SystemConfigRepository;
 pub use repository::track_source::TrackSourceRepository;
 
+mod audio_cache;
+mod image;
+mod media_store;
 pub mod model;
 mod repository;
 mod util;
@@ -0,0 +1,147 @@
+use std::collections::HashSet;
+use std::fs::{create_dir_all, read_dir, remove_file, rename, write, File};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use sea_orm::DbErr;
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+
+// Files stored this recently are kept by remove_unused, as the track sources using them may not be committed yet
+const UNCOMMITTED_GRACE: Duration = Duration::from_secs(10 * 60);
+
+// Start times of the running imports, which only commit their track sources after storing all of their files
+static RUNNING_IMPORTS: Mutex<Vec<SystemTime>> = Mutex::new(vec![]);
+
+/// Keeps remove_unused from deleting the files stored since the import started, until it is dropped
+pub struct ImportHold {
+    started_at: SystemTime,
+}
+
+impl Drop for ImportHold {
+    fn drop(&mut self) {
+        let mut imports = RUNNING_IMPORTS.lock().expect("couldn't lock");
+        if let Some(index) = imports.iter().position(|started_at| *started_at == self.started_at) {
+            imports.swap_remove(index);
+        }
+    }
+}
+
+/// Keeps the audio files on disk, addressed by the sha256 hash of their content. This way the same file is only
+/// stored once, no matter how often it was added to the library.
+pub struct MediaStore;
+
+impl MediaStore {
+    fn get_folder() -> PathBuf {
+        PathBuf::from(std::env::var("MEDIA_FOLDER").unwrap_or("media".to_string()))
+    }
+
+    pub fn get_path(hash: &str) -> PathBuf {
+        // Files are spread over subfolders to keep the folders small
+        Self::get_folder().join(&hash[..2]).join(hash)
+    }
+
+    /// Has to be held by imports while they store files, as those may take longer than the grace period of
+    /// remove_unused
+    pub fn hold_for_import() -> ImportHold {
+        let started_at = SystemTime::now();
+        RUNNING_IMPORTS.lock().expect("couldn't lock").push(started_at);
+        ImportHold { started_at }
+    }
+
+    /// Stores the file if it is not stored yet and returns its hash
+    pub fn add(file: &[u8]) -> Result<String, DbErr> {
+        let hash = format!("{:x}", Sha256::digest(file));
+        let path = Self::get_path(&hash);
+
+        if !path.exists() {
+            create_dir_all(path.parent().unwrap())
+                .map_err(|error| DbErr::Custom(format!("Could not create media folder: {}", error)))?;
+            write(&path, file).map_err(|error| DbErr::Custom(format!("Could not write media file: {}", error)))?;
+        } else {
+            Self::touch(&path).map_err(|error| DbErr::Custom(format!("Could not touch media file: {}", error)))?;
+        }
+
+        Ok(hash)
+    }
+
+    /// Stores the file read from the reader and returns its hash. Other than add, the file is never held in memory
+    /// completely, which allows to store large imports.
+    pub fn add_from_reader(reader: &mut impl Read) -> Result<String, DbErr> {
+        let folder = Self::get_folder();
+        create_dir_all(&folder).map_err(|error| DbErr::Custom(format!("Could not create media folder: {}", error)))?;
+
+        // The hash is only known after reading everything, so the file is written to a temporary location first
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos();
+        let temp_path = folder.join(format!(".{}.tmp", nanos));
+        let hash = Self::write_hashed(reader, &temp_path).map_err(|error| {
+            let _ = remove_file(&temp_path);
+            DbErr::Custom(format!("Could not write media file: {}", error))
+        })?;
+
+        let path = Self::get_path(&hash);
+        let result = match path.exists() {
+            true => remove_file(&temp_path).and_then(|_| Self::touch(&path)),
+            false => create_dir_all(path.parent().unwrap()).and_then(|_| rename(&temp_path, &path)),
+        };
+        result.map_err(|error| DbErr::Custom(format!("Could not move media file: {}", error)))?;
+
+        Ok(hash)
+    }
+
+    /// Marks a file which was stored already as new, so remove_unused keeps it for the track source about to use it
+    fn touch(path: &PathBuf) -> std::io::Result<()> {
+        File::options().append(true).open(path)?.set_modified(SystemTime::now())
+    }
+
+    fn write_hashed(reader: &mut impl Read, path: &PathBuf) -> std::io::Result<String> {
+        let mut hasher = Sha256::new();
+        let mut file = File::create(path)?;
+        let mut buffer = [0u8; 64 * 1024];
+        loop {
+            let read = reader.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+            file.write_all(&buffer[..read])?;
+        }
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    /// Deletes all stored files whose hash is not in the given set. Files stored within the grace period or since the
+    /// start of a running import are kept, as uploads and imports store them before their track source is committed.
+    pub fn remove_unused(used_hashes: &HashSet<String>) -> Result<(), DbErr> {
+        let oldest_import = RUNNING_IMPORTS.lock().expect("couldn't lock").iter().min().copied();
+        let grace_start = SystemTime::now() - UNCOMMITTED_GRACE;
+        let stored_before = oldest_import.map_or(grace_start, |started_at| started_at.min(grace_start));
+        let folder = Self::get_folder();
+        if !folder.exists() {
+            return Ok(());
+        }
+
+        let sub_folders =
+            read_dir(folder).map_err(|error| DbErr::Custom(format!("Could not read media folder: {}", error)))?;
+        for sub_folder in sub_folders.flatten() {
+            let files = match read_dir(sub_folder.path()) {
+                Ok(files) => files,
+                Err(_) => continue,
+            };
+            for file in files.flatten() {
+                let hash = file.file_name().to_string_lossy().to_string();
+                let modified = file.metadata().and_then(|metadata| metadata.modified());
+                if used_hashes.contains(&hash) || modified.is_ok_and(|modified| modified > stored_before) {
+                    continue;
+                }
+                info!("Removing unused media file {}", hash);
+                if let Err(error) = remove_file(file.path()) {
+                    warn!("Could not remove media file {}: {}", hash, error);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
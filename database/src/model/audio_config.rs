@@ -0,0 +1,37 @@
+use crate::util::{with_change_tracking, ChangeTracking};
+use sea_orm::entity::prelude::*;
+use sea_orm::Iterable;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// Effects applied to all local and remote tracks, e.g. to make small speakers sound fuller
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, TS)]
+#[sea_orm(table_name = "audio_config")]
+#[serde(rename = "AudioConfig")]
+#[ts(export)]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(skip)]
+    pub id: i32,
+    // Decibels the low and high frequencies are raised or lowered, -12 to 12
+    pub bass: i32,
+    pub treble: i32,
+    // Keeps peaks below the threshold in decibels, so raised bass does not distort
+    pub limiter: bool,
+    pub limiter_threshold: i32,
+    // Mixes both sides together, for boxes with a single speaker. Applies from the next track on
+    pub mono: bool,
+}
+
+impl Default for Model {
+    fn default() -> Self {
+        Self { id: 1, bass: 0, treble: 0, limiter: false, limiter_threshold: -6, mono: false }
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+with_change_tracking!(ActiveModel);
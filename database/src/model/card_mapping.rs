@@ -0,0 +1,46 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// Links the uid of a rfid card to the library entry played when the card is placed on the reader
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, TS)]
+#[sea_orm(table_name = "card_mapping")]
+#[serde(rename = "CardMapping")]
+#[ts(export)]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub card_uid: String,
+    // Cards are known once scanned, but only play something after being assigned
+    #[ts(optional)]
+    pub library_entry_id: Option<i32>,
+    #[ts(type = "string", optional)]
+    pub last_seen_at: Option<DateTimeUtc>,
+    // Only relevant for the user interface
+    #[sea_orm(ignore)]
+    #[ts(optional)]
+    pub library_entry_name: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    LibraryEntry,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Relation::LibraryEntry => Entity::belongs_to(super::library_entry::Entity)
+                .from(Column::LibraryEntryId)
+                .to(super::library_entry::Column::Id)
+                .into(),
+        }
+    }
+}
+
+impl Related<super::library_entry::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::LibraryEntry.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
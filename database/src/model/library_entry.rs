@@ -20,6 +20,12 @@ pub enum Variant {
     File,
     #[sea_orm(string_value = "spotify")]
     Spotify,
+    // Feed whose episodes are refreshed by the admin interface, played like a folder
+    #[sea_orm(string_value = "podcast")]
+    Podcast,
+    // Audio file of a podcast feed, streamed from its url
+    #[sea_orm(string_value = "episode")]
+    Episode,
 }
 
 impl Display for Variant {
@@ -29,11 +35,28 @@ impl Display for Variant {
             Variant::Stream => "stream".to_string(),
             Variant::File => "file".to_string(),
             Variant::Spotify => "spotify".to_string(),
+            Variant::Podcast => "podcast".to_string(),
+            Variant::Episode => "episode".to_string(),
         };
         write!(f, "{}", str)
     }
 }
 
+/// How the tracks of a folder are repeated once the end of the queue or a track is reached
+#[derive(Clone, Copy, Debug, Default, PartialEq, EnumIter, DeriveActiveEnum, Serialize, Deserialize, TS)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::N(3))")]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum RepeatMode {
+    #[default]
+    #[sea_orm(string_value = "off")]
+    Off,
+    #[sea_orm(string_value = "all")]
+    All,
+    #[sea_orm(string_value = "one")]
+    One,
+}
+
 #[derive(Clone, PartialEq, DeriveEntityModel, Serialize, Deserialize, TS)]
 #[sea_orm(table_name = "library_entry")]
 #[serde(rename = "LibraryEntry")]
@@ -51,6 +74,18 @@ pub struct Model {
     #[ts(type = "string", optional)]
     pub played_at: Option<DateTimeUtc>,
     pub sort_key: i32,
+    // Playback mode of a folder, used when the folder is played
+    #[serde(default)]
+    pub shuffle: bool,
+    #[serde(default)]
+    pub repeat_mode: RepeatMode,
+    // Decibels which bring the tracks of a folder to the reference loudness together, none until they are analyzed
+    #[serde(default)]
+    #[ts(optional)]
+    pub album_gain: Option<f64>,
+    // Remote tracks of a pinned folder are kept in the audio cache, so they play without network
+    #[serde(default)]
+    pub pinned: bool,
     #[sea_orm(ignore)]
     #[ts(optional)]
     pub children: Option<Vec<Model>>, // Just used to pass children from API to client
@@ -65,6 +100,10 @@ pub struct Model {
     #[sea_orm(ignore)]
     #[ts(optional)]
     pub parent_image: Option<Vec<u8>>,
+    // Length of the tracks the player resolves from a Spotify album, playlist or show. They keep the id of it.
+    #[sea_orm(ignore)]
+    #[ts(optional)]
+    pub duration_ms: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -87,10 +126,15 @@ impl Debug for Model {
             .field("image", &FormatImage(self.image.as_ref()))
             .field("played_at", &self.played_at)
             .field("sort_key", &self.sort_key)
+            .field("shuffle", &self.shuffle)
+            .field("repeat_mode", &self.repeat_mode)
+            .field("album_gain", &self.album_gain)
+            .field("pinned", &self.pinned)
             .field("children", &self.children)
             .field("track_source", &self.track_source)
             .field("parent_name", &self.parent_name)
             .field("parent_image", &FormatImage(self.parent_image.as_ref()))
+            .field("duration_ms", &self.duration_ms)
             .finish()
     }
 }
@@ -131,7 +175,8 @@ impl<'a> Debug for FormatImage<'a> {
 
 impl ActiveModel {
     pub fn update_from_model(&mut self, model: Model) {
-        for column in Column::iter() {
+        // The album gain is calculated by the loudness analysis and pinning has its own endpoint, both are not edited
+        for column in Column::iter().filter(|column| !matches!(column, Column::AlbumGain | Column::Pinned)) {
             let old_value = self.get(column);
             let new_value = model.get(column);
 
@@ -149,6 +194,10 @@ pub struct CreateModel {
     pub name: String,
     pub image: Option<Vec<u8>>,
     pub sort_key: i32,
+    #[serde(default)]
+    pub shuffle: bool,
+    #[serde(default)]
+    pub repeat_mode: RepeatMode,
     pub children: Option<Vec<CreateModel>>, // Just used to pass children from API to client
     pub track_source: Option<TrackSourceCreateModel>, // Just used to pass children from API to client
 }
@@ -158,11 +207,13 @@ impl CreateModel {
     #[allow(dead_code)]
     pub fn to_active_model(&self) -> ActiveModel {
         let mut model = ActiveModel::new();
-        model.parent_id = Set(self.parent_id.clone());
-        model.variant = Set(self.variant.clone());
+        model.parent_id = Set(self.parent_id);
+        model.variant = Set(self.variant);
         model.name = Set(self.name.clone());
         model.image = Set(self.image.clone());
-        model.sort_key = Set(self.sort_key.clone());
+        model.sort_key = Set(self.sort_key);
+        model.shuffle = Set(self.shuffle);
+        model.repeat_mode = Set(self.repeat_mode);
         model
     }
 }
@@ -1,4 +1,7 @@
+pub mod audio_config;
 pub mod spotify_config;
 pub mod system_config;
+pub mod card_mapping;
 pub mod library_entry;
+pub mod playback_position;
 pub mod track_source;
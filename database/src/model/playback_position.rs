@@ -0,0 +1,37 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Last known position of a track, used to resume playback after a restart
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "playback_position")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub library_entry_id: i32,
+    /// Position in milliseconds
+    pub position: i64,
+    pub updated_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    LibraryEntry,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Relation::LibraryEntry => Entity::belongs_to(super::library_entry::Entity)
+                .from(Column::LibraryEntryId)
+                .to(super::library_entry::Column::Id)
+                .into(),
+        }
+    }
+}
+
+impl Related<super::library_entry::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::LibraryEntry.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -4,6 +4,23 @@ use sea_orm::Iterable;
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 
+/// Which gain of the loudness analysis is applied to local tracks
+#[derive(Clone, Copy, Debug, Default, PartialEq, EnumIter, DeriveActiveEnum, Serialize, Deserialize, TS)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::N(5))")]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum NormalizationMode {
+    #[sea_orm(string_value = "off")]
+    Off,
+    // Every track plays at the same loudness
+    #[default]
+    #[sea_orm(string_value = "track")]
+    Track,
+    // The tracks of a folder keep their loudness relative to each other, like the songs of an album
+    #[sea_orm(string_value = "album")]
+    Album,
+}
+
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, TS)]
 #[sea_orm(table_name = "system_config")]
 #[serde(rename = "SystemConfig")]
@@ -36,6 +53,24 @@ pub struct Model {
     pub power_off_btn_delay: i32,
     pub power_off_pin: i32,
     pub cut_pin: i32,
+    // Seconds the end of a track is faded into the next one
+    pub crossfade: i32,
+    // Times of day (HH:MM) between which the volume is limited to quiet_hours_volume, empty if disabled
+    pub quiet_hours_start: String,
+    pub quiet_hours_end: String,
+    pub quiet_hours_volume: u8,
+    pub loudness_normalization: NormalizationMode,
+    // GPIO pins of buttons pulling the pin low while pressed, 0 if not connected
+    pub play_pause_pin: i32,
+    pub next_pin: i32,
+    pub prev_pin: i32,
+    pub volume_up_pin: i32,
+    pub volume_down_pin: i32,
+    // GPIO pins of a rotary encoder changing the volume
+    pub rotary_clk_pin: i32,
+    pub rotary_dt_pin: i32,
+    // Megabytes of remote tracks kept on disk, pinned ones are kept even beyond
+    pub audio_cache_size: i32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -18,13 +18,25 @@ pub struct Model {
     pub title: String,
     #[ts(optional)]
     pub url: Option<String>,
-    #[serde(skip_serializing)]
+    // Hash of the file in the media store
     #[ts(optional)]
-    pub file: Option<Vec<u8>>,
+    pub file_hash: Option<String>,
     #[ts(optional)]
     pub spotify_id: Option<String>,
     #[ts(optional)]
     pub spotify_type: Option<String>,
+    // Decibels which bring the file to the reference loudness, none until it is analyzed
+    #[serde(default)]
+    #[ts(optional)]
+    pub gain: Option<f64>,
+    // Publish date of a podcast episode
+    #[serde(default)]
+    #[ts(type = "string", optional)]
+    pub published_at: Option<DateTimeUtc>,
+    // Number of the newest episodes kept of a podcast feed, the default limit applies if none
+    #[serde(default)]
+    #[ts(optional)]
+    pub episode_limit: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter)]
@@ -73,28 +85,27 @@ impl Debug for Model {
             .field("spotify_id", &self.spotify_id)
             .field("spotify_type", &self.spotify_type)
             .field("url", &self.url)
-            .field("file", &FormatFile(self.file.as_ref()))
+            .field("file_hash", &self.file_hash)
+            .field("gain", &self.gain)
+            .field("published_at", &self.published_at)
+            .field("episode_limit", &self.episode_limit)
             .finish()
     }
 }
 
-struct FormatFile<'a>(pub Option<&'a Vec<u8>>);
-impl<'a> Debug for FormatFile<'a> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        match &self.0 {
-            None => write!(f, "None"),
-            Some(buffer) => write!(f, "[u8; {}]", buffer.len()),
-        }
-    }
-}
-
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct CreateModel {
     pub title: String,
     pub url: Option<String>,
-    pub file: Option<Vec<u8>>,
+    pub file: Option<Vec<u8>>, // Moved into the media store on creation
+    #[serde(skip)]
+    pub file_hash: Option<String>, // Set instead of file, if the file is in the media store already
     pub spotify_id: Option<String>,
     pub spotify_type: Option<String>,
+    #[serde(default)]
+    pub published_at: Option<DateTimeUtc>,
+    #[serde(default)]
+    pub episode_limit: Option<i32>,
 }
 
 impl CreateModel {
@@ -104,9 +115,10 @@ impl CreateModel {
         let mut model = ActiveModel::new();
         model.title = Set(self.title.clone());
         model.url = Set(self.url.clone());
-        model.file = Set(self.file.clone());
         model.spotify_id = Set(self.spotify_id.clone());
         model.spotify_type = Set(self.spotify_type.clone());
+        model.published_at = Set(self.published_at);
+        model.episode_limit = Set(self.episode_limit);
         model
     }
 }
@@ -0,0 +1,25 @@
+use crate::model::audio_config::{ActiveModel, Entity as AudioConfig, Model};
+use crate::util::ChangeTracking;
+use sea_orm::{ActiveModelTrait, DatabaseConnection, DbErr, EntityTrait};
+
+pub struct AudioConfigRepository;
+
+impl AudioConfigRepository {
+    pub async fn get(conn: &DatabaseConnection) -> Result<Model, DbErr> {
+        AudioConfig::find_by_id(1).one(conn).await.map(Option::unwrap_or_default)
+    }
+
+    pub async fn update_from_json(
+        conn: &DatabaseConnection,
+        json: serde_json::Value,
+    ) -> Result<(Model, Vec<String>), DbErr> {
+        let existing =
+            AudioConfig::find_by_id(1).one(conn).await?.ok_or(DbErr::RecordNotFound("AudioConfig".to_string()))?;
+
+        let mut model = ActiveModel::from(existing);
+        let changed_fields = model.update_from_json(json)?;
+        let updated_model = model.update(conn).await?;
+
+        Ok((updated_model, changed_fields))
+    }
+}
@@ -0,0 +1,80 @@
+use sea_orm::prelude::DateTimeUtc;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::ActiveValue::{NotSet, Set};
+use sea_orm::Order::Desc;
+use sea_orm::{ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder};
+
+use crate::model::card_mapping::{ActiveModel, Column, Entity, Model};
+use crate::model::library_entry::Entity as LibraryEntryEntity;
+
+pub struct CardMappingRepository;
+
+impl CardMappingRepository {
+    pub async fn get_all<C: ConnectionTrait>(conn: &C) -> Result<Vec<Model>, DbErr> {
+        let cards =
+            Entity::find().find_also_related(LibraryEntryEntity).order_by(Column::LastSeenAt, Desc).all(conn).await?;
+
+        Ok(cards
+            .into_iter()
+            .map(|(mut card, library_entry)| {
+                card.library_entry_name = library_entry.map(|entry| entry.name);
+                card
+            })
+            .collect())
+    }
+
+    pub async fn get<C: ConnectionTrait>(conn: &C, card_uid: &str) -> Result<Option<Model>, DbErr> {
+        Entity::find_by_id(card_uid).one(conn).await
+    }
+
+    /// Returns the card scanned most recently after the given time, used to learn new cards
+    pub async fn get_last_seen<C: ConnectionTrait>(conn: &C, since: DateTimeUtc) -> Result<Option<Model>, DbErr> {
+        Entity::find().filter(Column::LastSeenAt.gt(since)).order_by(Column::LastSeenAt, Desc).one(conn).await
+    }
+
+    /// Remembers the card was scanned, creating it if it is unknown, and returns its mapping
+    pub async fn mark_seen<C: ConnectionTrait>(conn: &C, card_uid: &str) -> Result<Model, DbErr> {
+        let model = ActiveModel {
+            card_uid: Set(card_uid.to_string()),
+            library_entry_id: NotSet,
+            last_seen_at: Set(Some(chrono::Utc::now())),
+        };
+
+        Entity::insert(model)
+            .on_conflict(OnConflict::column(Column::CardUid).update_column(Column::LastSeenAt).to_owned())
+            .exec_without_returning(conn)
+            .await?;
+
+        Self::get(conn, card_uid).await?.ok_or(DbErr::RecordNotFound(format!("No card {} found", card_uid)))
+    }
+
+    pub async fn assign<C: ConnectionTrait>(
+        conn: &C,
+        card_uid: &str,
+        library_entry_id: Option<i32>,
+    ) -> Result<Model, DbErr> {
+        let existing = Self::get(conn, card_uid).await?;
+
+        match existing {
+            Some(prev_model) => {
+                let mut model = ActiveModel::from(prev_model);
+                model.library_entry_id = Set(library_entry_id);
+                model.update(conn).await
+            }
+            None => {
+                let model = ActiveModel {
+                    card_uid: Set(card_uid.to_string()),
+                    library_entry_id: Set(library_entry_id),
+                    last_seen_at: Set(None),
+                };
+                model.insert(conn).await
+            }
+        }
+    }
+
+    pub async fn delete<C: ConnectionTrait>(conn: &C, card_uid: &str) -> Result<bool, DbErr> {
+        let result = Entity::delete_by_id(card_uid).exec(conn).await?;
+
+        Ok(result.rows_affected > 0)
+    }
+}
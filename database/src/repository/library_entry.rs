@@ -1,14 +1,14 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use sea_orm::prelude::DateTimeUtc;
 use sea_orm::ActiveValue::Set;
 use sea_orm::Order::Asc;
 use sea_orm::{
     ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbBackend, DbErr, EntityTrait, QueryFilter,
-    QueryOrder, QuerySelect, Statement, TransactionTrait,
+    QueryOrder, Statement, TransactionTrait,
 };
 
-use crate::model::library_entry::{ActiveModel, Column, CreateModel, Entity, Model, ParentLink};
+use crate::model::library_entry::{ActiveModel, Column, CreateModel, Entity, Model, ParentLink, RepeatMode, Variant};
 use crate::model::track_source::{Column as TrackSourceColumn, Entity as TrackSourceEntity};
 use crate::repository::track_source::TrackSourceRepository;
 
@@ -30,16 +30,81 @@ impl LibraryEntryRepository {
         conn: &DatabaseConnection,
         parent_id: Option<i32>,
         entries: Vec<CreateModel>,
+    ) -> Result<Vec<Model>, DbErr> {
+        Self::create_with_progress(conn, parent_id, entries, &|_| {}).await
+    }
+
+    /// Creates the entries with all their children in one transaction. The callback receives the number of entries
+    /// created so far.
+    pub async fn create_with_progress(
+        conn: &DatabaseConnection,
+        parent_id: Option<i32>,
+        entries: Vec<CreateModel>,
+        on_progress: &(dyn Fn(usize) + Send + Sync),
     ) -> Result<Vec<Model>, DbErr> {
         let tx = conn.begin().await?;
 
-        let models = Self::create_recursive(&tx, parent_id, entries).await?;
+        let models = Self::create_recursive(&tx, parent_id, entries, on_progress).await?;
 
         tx.commit().await?;
 
         Ok(models)
     }
 
+    /// Adds the entries to the children of the parent in one transaction. If replace is set, the existing children are
+    /// deleted beforehand, otherwise the entries are sorted behind them.
+    pub async fn restore(
+        conn: &DatabaseConnection,
+        parent_id: i32,
+        mut entries: Vec<CreateModel>,
+        replace: bool,
+    ) -> Result<Vec<Model>, DbErr> {
+        let tx = conn.begin().await?;
+
+        if replace {
+            Entity::delete_many().filter(Column::ParentId.eq(parent_id)).exec(&tx).await?;
+        } else {
+            let next_sort_key = Self::get_children(&tx, parent_id)
+                .await?
+                .iter()
+                .map(|entry| entry.sort_key + 1)
+                .max()
+                .unwrap_or(0);
+            for entry in entries.iter_mut() {
+                entry.sort_key += next_sort_key;
+            }
+        }
+
+        let models = Self::create_recursive(&tx, Some(parent_id), entries, &|_| {}).await?;
+
+        tx.commit().await?;
+
+        TrackSourceRepository::remove_unused_files(conn).await?;
+
+        Ok(models)
+    }
+
+    /// Returns all descendants of the entry with their track sources, nested as children and ordered by sort key
+    pub async fn get_tree(conn: &DatabaseConnection, id: i32) -> Result<Vec<Model>, DbErr> {
+        let entries = Entity::find().order_by(Column::SortKey, Asc).all(conn).await?;
+        let mut track_sources = TrackSourceEntity::find()
+            .all(conn)
+            .await?
+            .into_iter()
+            .map(|track_source| (track_source.library_entry_id, track_source))
+            .collect::<HashMap<i32, _>>();
+
+        let mut children_by_parent: HashMap<i32, Vec<Model>> = HashMap::new();
+        for mut entry in entries {
+            entry.track_source = track_sources.remove(&entry.id);
+            if let Some(parent_id) = entry.parent_id {
+                children_by_parent.entry(parent_id).or_default().push(entry);
+            }
+        }
+
+        Ok(Self::take_children(&mut children_by_parent, id))
+    }
+
     pub async fn update(conn: &DatabaseConnection, id: i32, entry: Model) -> Result<Model, DbErr> {
         let tx = conn.begin().await?;
 
@@ -74,6 +139,7 @@ impl LibraryEntryRepository {
 
     pub async fn delete(conn: &DatabaseConnection, id: i32) -> Result<bool, DbErr> {
         let result = Entity::delete_by_id(id).exec(conn).await?;
+        TrackSourceRepository::remove_unused_files(conn).await?;
         Ok(result.rows_affected > 0)
     }
 
@@ -93,6 +159,90 @@ impl LibraryEntryRepository {
         Ok(())
     }
 
+    /// Returns all entries of the variant with their track source, but without children
+    pub async fn get_by_variant(conn: &DatabaseConnection, variant: Variant) -> Result<Vec<Model>, DbErr> {
+        Ok(Entity::find()
+            .filter(Column::Variant.eq(variant))
+            .find_also_related(TrackSourceEntity)
+            .all(conn)
+            .await?
+            .into_iter()
+            .map(|(mut model, track_source)| {
+                model.track_source = track_source;
+                model
+            })
+            .collect::<Vec<Model>>())
+    }
+
+    /// Returns the entry without loading its children or track source
+    pub async fn get_flat(conn: &DatabaseConnection, id: i32) -> Result<Option<Model>, DbErr> {
+        Entity::find_by_id(id).one(conn).await
+    }
+
+    pub async fn set_playback_mode(
+        conn: &DatabaseConnection,
+        library_entry_id: i32,
+        shuffle: bool,
+        repeat_mode: RepeatMode,
+    ) -> Result<(), DbErr> {
+        let mut model: ActiveModel = Entity::find_by_id(library_entry_id)
+            .one(conn)
+            .await?
+            .ok_or(DbErr::RecordNotFound("No library entry found".to_string()))?
+            .into();
+        model.shuffle = Set(shuffle);
+        model.repeat_mode = Set(repeat_mode);
+
+        model.update(conn).await?;
+        Ok(())
+    }
+
+    pub async fn set_album_gain(conn: &DatabaseConnection, id: i32, album_gain: Option<f64>) -> Result<(), DbErr> {
+        let mut model: ActiveModel = Entity::find_by_id(id)
+            .one(conn)
+            .await?
+            .ok_or(DbErr::RecordNotFound("No library entry found".to_string()))?
+            .into();
+        model.album_gain = Set(album_gain);
+
+        model.update(conn).await?;
+        Ok(())
+    }
+
+    pub async fn set_pinned(conn: &DatabaseConnection, id: i32, pinned: bool) -> Result<Model, DbErr> {
+        let mut model: ActiveModel = Entity::find_by_id(id)
+            .one(conn)
+            .await?
+            .ok_or(DbErr::RecordNotFound("No library entry found".to_string()))?
+            .into();
+        model.pinned = Set(pinned);
+
+        model.update(conn).await
+    }
+
+    /// Returns the urls of the streams and episodes which are pinned or inside a pinned folder
+    pub async fn get_pinned_urls(conn: &DatabaseConnection) -> Result<HashSet<String>, DbErr> {
+        let pinned =
+            Entity::find().filter(Column::Pinned.eq(true)).find_also_related(TrackSourceEntity).all(conn).await?;
+
+        let mut urls = HashSet::new();
+        for (mut entry, track_source) in pinned {
+            entry.track_source = track_source;
+            let tracks = match entry.variant {
+                Variant::Folder | Variant::Podcast => Self::get_tracks_in_parent(conn, entry.id).await?,
+                _ => vec![entry],
+            };
+            urls.extend(
+                tracks
+                    .into_iter()
+                    .filter(|track| matches!(track.variant, Variant::Stream | Variant::Episode))
+                    .filter_map(|track| track.track_source.and_then(|track_source| track_source.url)),
+            );
+        }
+
+        Ok(urls)
+    }
+
     pub async fn get_tracks_in_parent(conn: &DatabaseConnection, library_entry_id: i32) -> Result<Vec<Model>, DbErr> {
         let library_entries = Entity::find()
             .from_raw_sql(Statement::from_sql_and_values(
@@ -109,7 +259,7 @@ impl LibraryEntryRepository {
                     FROM library_entry le
                     INNER JOIN library_hierarchy lh ON le.parent_id = lh.id
                 )
-                SELECT * FROM library_hierarchy WHERE variant != 'folder' ORDER BY path ASC;
+                SELECT * FROM library_hierarchy WHERE variant NOT IN ('folder', 'podcast') ORDER BY path ASC;
             "#,
                 [library_entry_id.into()],
             ))
@@ -127,7 +277,7 @@ impl LibraryEntryRepository {
             .into_iter()
             .map(|mut entry| {
                 entry.track_source = track_sources.iter().find(|source| source.library_entry_id == entry.id).cloned();
-                if let Some(parent_id) = entry.parent_id.clone() {
+                if let Some(parent_id) = entry.parent_id {
                     if let Some(parent) = parent_entries.iter().find(|parent| parent_id == parent.id) {
                         entry.parent_name = Some(parent.name.to_string());
                         entry.parent_image = parent.image.clone();
@@ -144,12 +294,14 @@ impl LibraryEntryRepository {
         tx: &C,
         parent_id: Option<i32>,
         entries: Vec<CreateModel>,
+        on_progress: &(dyn Fn(usize) + Send + Sync),
     ) -> Result<Vec<Model>, DbErr> {
+        let mut created_count = 0;
         let mut created_model_ids = vec![];
         let mut stack =
             entries
                 .iter()
-                .map(|entry| (entry.clone(), parent_id.clone(), 0))
+                .map(|entry| (entry.clone(), parent_id, 0))
                 .collect::<Vec<(CreateModel, Option<i32>, i32)>>();
         while let Some((entry, parent_id, level)) = stack.pop() {
             let mut model = entry.to_active_model();
@@ -165,6 +317,8 @@ impl LibraryEntryRepository {
             if level == 0 {
                 created_model_ids.push(model.id);
             }
+            created_count += 1;
+            on_progress(created_count);
 
             if let Some(children) = entry.children.as_ref() {
                 for child in children {
@@ -186,19 +340,21 @@ impl LibraryEntryRepository {
             .collect::<Vec<Model>>())
     }
 
+    fn take_children(children_by_parent: &mut HashMap<i32, Vec<Model>>, id: i32) -> Vec<Model> {
+        let mut children = children_by_parent.remove(&id).unwrap_or_default();
+        for child in children.iter_mut() {
+            if child.variant == Variant::Folder {
+                child.children = Some(Self::take_children(children_by_parent, child.id));
+            }
+        }
+        children
+    }
+
     async fn get_children<C: ConnectionTrait>(conn: &C, id: i32) -> Result<Vec<Model>, DbErr> {
         let entries = Entity::find().filter(Column::ParentId.eq(id)).order_by(Column::SortKey, Asc).all(conn).await?;
         let entry_ids = entries.iter().map(|e| e.id).collect::<Vec<i32>>();
         let track_sources = TrackSourceEntity::find()
             .filter(TrackSourceColumn::LibraryEntryId.is_in(entry_ids))
-            // Skip file. Due to its size it heavily impacts query performance and isn't needed here
-            .columns(vec![
-                TrackSourceColumn::Id,
-                TrackSourceColumn::Url,
-                TrackSourceColumn::SpotifyId,
-                TrackSourceColumn::SpotifyType,
-                TrackSourceColumn::Title,
-            ])
             .all(conn)
             .await?;
 
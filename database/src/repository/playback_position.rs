@@ -0,0 +1,42 @@
+use std::time::Duration;
+
+use sea_orm::sea_query::OnConflict;
+use sea_orm::ActiveValue::Set;
+use sea_orm::{ConnectionTrait, DbErr, EntityTrait};
+
+use crate::model::playback_position::{ActiveModel, Column, Entity};
+
+pub struct PlaybackPositionRepository;
+
+impl PlaybackPositionRepository {
+    pub async fn get<C: ConnectionTrait>(conn: &C, library_entry_id: i32) -> Result<Option<Duration>, DbErr> {
+        let model = Entity::find_by_id(library_entry_id).one(conn).await?;
+
+        Ok(model.map(|model| Duration::from_millis(model.position.max(0) as u64)))
+    }
+
+    pub async fn set<C: ConnectionTrait>(conn: &C, library_entry_id: i32, position: Duration) -> Result<(), DbErr> {
+        let model = ActiveModel {
+            library_entry_id: Set(library_entry_id),
+            position: Set(position.as_millis() as i64),
+            updated_at: Set(chrono::Utc::now()),
+        };
+
+        Entity::insert(model)
+            .on_conflict(
+                OnConflict::column(Column::LibraryEntryId)
+                    .update_columns([Column::Position, Column::UpdatedAt])
+                    .to_owned(),
+            )
+            .exec(conn)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete<C: ConnectionTrait>(conn: &C, library_entry_id: i32) -> Result<(), DbErr> {
+        Entity::delete_by_id(library_entry_id).exec(conn).await?;
+
+        Ok(())
+    }
+}
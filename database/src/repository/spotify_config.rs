@@ -49,11 +49,16 @@ impl SpotifyConfigRepository {
         }
     }
 
+    /// Checks whether the json fits the config, e.g. before restoring it along with other data
+    pub fn validate_json(json: &serde_json::Value) -> Result<(), DbErr> {
+        ActiveModel::from_json(json.clone()).map(|_| ())
+    }
+
     pub async fn update_from_json(conn: &DatabaseConnection, json: serde_json::Value) -> Result<(Model, Vec<String>), DbErr> {
         let existing = SpotifyConfig::find_by_id(1).one(conn).await?.ok_or(DbErr::RecordNotFound("SpotifyConfig".to_string()))?;
 
         let mut model = ActiveModel::from(existing);
-        let changed_fields = model.update_from_json(json);
+        let changed_fields = model.update_from_json(json)?;
         let updated_model = model.update(conn).await?;
 
         Ok((updated_model, changed_fields))
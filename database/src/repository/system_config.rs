@@ -26,11 +26,16 @@ impl SystemConfigRepository {
         })
     }
 
+    /// Checks whether the json fits the config, e.g. before restoring it along with other data
+    pub fn validate_json(json: &serde_json::Value) -> Result<(), DbErr> {
+        ActiveModel::from_json(json.clone()).map(|_| ())
+    }
+
     pub async fn update_from_json(conn: &DatabaseConnection, json: serde_json::Value) -> Result<(Model, Vec<String>), DbErr> {
         let existing = SystemConfig::find_by_id(1).one(conn).await?.ok_or(DbErr::RecordNotFound("SystemConfig".to_string()))?;
 
         let mut model = ActiveModel::from(existing);
-        let changed_fields = model.update_from_json(json);
+        let changed_fields = model.update_from_json(json)?;
         let updated_model = model.update(conn).await?;
 
         Ok((updated_model, changed_fields))
@@ -60,4 +65,12 @@ impl SystemConfigRepository {
             }
         }
     }
+
+    /// Returns the size of the audio cache in bytes
+    pub async fn get_audio_cache_size(conn: &DatabaseConnection) -> Result<u64, DbErr> {
+        match Self::get(conn).await? {
+            Some(model) => Ok(model.audio_cache_size.max(0) as u64 * 1024 * 1024),
+            None => Err(DbErr::RecordNotFound("SystemConfig".to_string()))
+        }
+    }
 }
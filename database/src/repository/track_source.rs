@@ -1,15 +1,20 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
 use sea_orm::ActiveValue::Set;
-use sea_orm::{ActiveModelTrait, ConnectionTrait, DbErr, EntityTrait, SelectColumns};
+use sea_orm::{ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QuerySelect};
 
+use crate::media_store::MediaStore;
 use crate::model::track_source;
 
 pub struct TrackSourceRepository;
 
 impl TrackSourceRepository {
-    pub async fn get_file<C: ConnectionTrait>(conn: &C, id: i32) -> Result<Option<Vec<u8>>, DbErr> {
-        let model = track_source::Entity::find_by_id(id).select_column(track_source::Column::File).one(conn).await?;
+    /// Returns the path of the file in the media store
+    pub async fn get_file_path<C: ConnectionTrait>(conn: &C, id: i32) -> Result<Option<PathBuf>, DbErr> {
+        let model = track_source::Entity::find_by_id(id).one(conn).await?;
 
-        Ok(model.and_then(|m| m.file))
+        Ok(model.and_then(|m| m.file_hash).map(|hash| MediaStore::get_path(&hash)))
     }
 
     pub async fn create<C: ConnectionTrait>(
@@ -19,8 +24,49 @@ impl TrackSourceRepository {
     ) -> Result<track_source::Model, DbErr> {
         let mut model = entity.to_active_model();
         model.library_entry_id = Set(library_entry_id);
+        if let Some(file_hash) = entity.file_hash.as_ref() {
+            model.file_hash = Set(Some(file_hash.clone()));
+        } else if let Some(file) = entity.file.as_ref() {
+            model.file_hash = Set(Some(MediaStore::add(file)?));
+        }
         let created_model = model.insert(conn).await?;
 
         Ok(created_model)
     }
+
+    /// Returns the track sources with a file whose loudness is not analyzed yet
+    pub async fn get_unanalyzed<C: ConnectionTrait>(conn: &C) -> Result<Vec<track_source::Model>, DbErr> {
+        track_source::Entity::find()
+            .filter(track_source::Column::FileHash.is_not_null())
+            .filter(track_source::Column::Gain.is_null())
+            .all(conn)
+            .await
+    }
+
+    pub async fn set_gain<C: ConnectionTrait>(conn: &C, id: i32, gain: f64) -> Result<(), DbErr> {
+        let mut model: track_source::ActiveModel = track_source::Entity::find_by_id(id)
+            .one(conn)
+            .await?
+            .ok_or(DbErr::RecordNotFound(format!("No track source with id {} found", id)))?
+            .into();
+        model.gain = Set(Some(gain));
+
+        model.update(conn).await?;
+        Ok(())
+    }
+
+    /// Deletes the files of the media store which are not used by any track source anymore
+    pub async fn remove_unused_files<C: ConnectionTrait>(conn: &C) -> Result<(), DbErr> {
+        let used_hashes = track_source::Entity::find()
+            .select_only()
+            .column(track_source::Column::FileHash)
+            .filter(track_source::Column::FileHash.is_not_null())
+            .into_tuple::<String>()
+            .all(conn)
+            .await?
+            .into_iter()
+            .collect::<HashSet<String>>();
+
+        MediaStore::remove_unused(&used_hashes)
+    }
 }
@@ -1,19 +1,21 @@
+use sea_orm::DbErr;
+
 #[allow(dead_code)]
 pub trait ChangeTracking {
-    fn update_from_json(&mut self, value: serde_json::Value) -> Vec<String>;
+    fn update_from_json(&mut self, value: serde_json::Value) -> Result<Vec<String>, DbErr>;
 }
 
 /// Enriches passed ActiveModel with a function to update the model from a JSON value.
 /// It will only update fields that actually have different value and will return the model
-/// and the names of changed fields.
+/// and the names of changed fields. Values which do not fit the model fail with DbErr::Json.
 /// The macro requires sea_orm::Iterable to be imported
 macro_rules! with_change_tracking {
     ($AM: tt) => {
         impl ChangeTracking for $AM {
-            fn update_from_json(&mut self, value: serde_json::Value) -> Vec<String> {
-                let model = $AM::from_json(value.clone()).unwrap();
+            fn update_from_json(&mut self, value: serde_json::Value) -> Result<Vec<String>, sea_orm::DbErr> {
+                let model = $AM::from_json(value.clone())?;
 
-                Column::iter()
+                let changed_fields = Column::iter()
                     .filter_map(|column| {
                         // Skip primary keys
                         if PrimaryKey::from_column(column).is_some() {
@@ -33,7 +35,8 @@ macro_rules! with_change_tracking {
                             None
                         }
                     })
-                    .collect::<Vec<String>>()
+                    .collect::<Vec<String>>();
+                Ok(changed_fields)
             }
         }
     };
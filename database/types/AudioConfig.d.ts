@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Effects applied to all local and remote tracks, e.g. to make small speakers sound fuller
+ */
+export type AudioConfig = { bass: number, treble: number, limiter: boolean, limiterThreshold: number, mono: boolean, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Links the uid of a rfid card to the library entry played when the card is placed on the reader
+ */
+export type CardMapping = { cardUid: string, libraryEntryId?: number, lastSeenAt?: string, libraryEntryName?: string, };
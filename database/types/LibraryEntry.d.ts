@@ -1,5 +1,6 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RepeatMode } from "./RepeatMode";
 import type { TrackSource } from "./TrackSource";
 import type { Variant } from "./Variant";
 
-export type LibraryEntry = { id?: number, parentId?: number, variant: Variant, name: string, image?: Array<number>, playedAt?: string, sortKey: number, children?: Array<LibraryEntry>, trackSource?: TrackSource, parentName?: string, parentImage?: Array<number>, };
+export type LibraryEntry = { id?: number, parentId?: number, variant: Variant, name: string, image?: Array<number>, playedAt?: string, sortKey: number, shuffle: boolean, repeatMode: RepeatMode, albumGain?: number, pinned: boolean, children?: Array<LibraryEntry>, trackSource?: TrackSource, parentName?: string, parentImage?: Array<number>, durationMs?: number, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Which gain of the loudness analysis is applied to local tracks
+ */
+export type NormalizationMode = "off" | "track" | "album";
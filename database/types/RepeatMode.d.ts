@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * How the tracks of a folder are repeated once the end of the queue or a track is reached
+ */
+export type RepeatMode = "off" | "all" | "one";
@@ -1,3 +1,4 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { NormalizationMode } from "./NormalizationMode";
 
-export type SystemConfig = { sleepTimer: number, idleShutdownTimer: number, displayOffTimer: number, hostname: string, cpuGovernor: string, overclockSdCard: boolean, logToRam: boolean, waitForNetwork: boolean, initialTurbo: boolean, swapEnabled: boolean, hdmiRotate: number, lcdRotate: number, displayBrightness: number, displayResolutionX: number, displayResolutionY: number, audioDevice: string, volume: number, maxVolume: number, ledPin: number, ledBrightness: number, ledBrightnessDimmed: number, powerOffBtnDelay: number, powerOffPin: number, cutPin: number, };
+export type SystemConfig = { sleepTimer: number, idleShutdownTimer: number, displayOffTimer: number, hostname: string, cpuGovernor: string, overclockSdCard: boolean, logToRam: boolean, waitForNetwork: boolean, initialTurbo: boolean, swapEnabled: boolean, hdmiRotate: number, lcdRotate: number, displayBrightness: number, displayResolutionX: number, displayResolutionY: number, audioDevice: string, volume: number, maxVolume: number, ledPin: number, ledBrightness: number, ledBrightnessDimmed: number, powerOffBtnDelay: number, powerOffPin: number, cutPin: number, crossfade: number, quietHoursStart: string, quietHoursEnd: string, quietHoursVolume: number, loudnessNormalization: NormalizationMode, playPausePin: number, nextPin: number, prevPin: number, volumeUpPin: number, volumeDownPin: number, rotaryClkPin: number, rotaryDtPin: number, audioCacheSize: number, };
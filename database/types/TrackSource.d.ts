@@ -1,3 +1,3 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type TrackSource = { id?: number, title: string, url?: string, file?: Array<number>, spotifyId?: string, spotifyType?: string, };
+export type TrackSource = { id?: number, title: string, url?: string, fileHash?: string, spotifyId?: string, spotifyType?: string, gain?: number, publishedAt?: string, episodeLimit?: number, };
@@ -0,0 +1,58 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+
+/// Response served for a path
+pub struct Fixture {
+    path: String,
+    content_type: String,
+    body: Vec<u8>,
+}
+
+pub fn fixture(path: &str, content_type: &str, body: impl Into<Vec<u8>>) -> Fixture {
+    Fixture { path: path.to_string(), content_type: content_type.to_string(), body: body.into() }
+}
+
+/// Serves the fixtures created for the base url of the server until the test ends. Query parameters like the page of
+/// an API are ignored, other paths are not found.
+pub fn serve(create_fixtures: impl FnOnce(&str) -> Vec<Fixture>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let fixtures = Arc::new(create_fixtures(&base_url));
+
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let fixtures = fixtures.clone();
+            thread::spawn(move || respond(stream, &fixtures));
+        }
+    });
+
+    base_url
+}
+
+fn respond(mut stream: TcpStream, fixtures: &[Fixture]) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).unwrap();
+    let mut header = String::new();
+    while reader.read_line(&mut header).unwrap() > 2 {
+        header.clear();
+    }
+
+    let target = request_line.split_whitespace().nth(1).unwrap_or_default();
+    let path = target.split('?').next().unwrap_or_default();
+    let response = match fixtures.iter().find(|fixture| fixture.path == path) {
+        Some(fixture) => {
+            let head = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                fixture.content_type,
+                fixture.body.len()
+            );
+            [head.into_bytes(), fixture.body.clone()].concat()
+        }
+        None => b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_vec(),
+    };
+    // The client may close the connection early, e.g. after probing
+    let _ = stream.write_all(&response);
+}
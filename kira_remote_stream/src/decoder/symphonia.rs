@@ -2,8 +2,9 @@ use kira::sound::streaming::Decoder as KiraDecoder;
 use kira::sound::FromFileError;
 use kira::Frame;
 use std::convert::TryInto;
+use std::time::Duration;
 
-use symphonia::core::audio::{AudioBuffer, AudioBufferRef, Signal};
+use symphonia::core::audio::{AudioBuffer, AudioBufferRef, Channels, Signal};
 use symphonia::core::conv::{FromSample, IntoSample};
 use symphonia::core::sample::Sample;
 use symphonia::core::{
@@ -13,23 +14,40 @@ use symphonia::core::{
     probe::Hint,
 };
 
+// Surround and centre channels are lowered by 3 dB when mixed into the front channels, like in ITU-R BS.775
+const SIDE_LEVEL: f32 = std::f32::consts::FRAC_1_SQRT_2;
+const REAR_CENTRE_LEVEL: f32 = 0.5;
+// Downmixed samples above this level are compressed softly, so loud passages on all channels do not clip
+const LIMITER_THRESHOLD: f32 = 0.8;
+
+/// How the channels of a track are mixed into the output
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum ChannelMode {
+    #[default]
+    Stereo,
+    // Plays the sum of both sides on both sides, so a box with a single speaker does not lose one of them
+    Mono,
+}
+
 pub struct SymphoniaDecoder {
     format_reader: Box<dyn FormatReader>,
     decoder: Box<dyn Decoder>,
     sample_rate: u32,
-    num_frames: usize,
+    // Not known for live streams
+    num_frames: Option<usize>,
     track_id: u32,
+    channel_mode: ChannelMode,
 }
 
 impl SymphoniaDecoder {
-    pub(crate) fn new(media_source: Box<dyn MediaSource>) -> Result<Self, FromFileError> {
+    pub fn new(media_source: Box<dyn MediaSource>) -> Result<Self, FromFileError> {
         let codecs = symphonia::default::get_codecs();
         let probe = symphonia::default::get_probe();
         let mss = MediaSourceStream::new(media_source, Default::default());
         let format_reader = probe.format(&Hint::default(), mss, &Default::default(), &Default::default())?.format;
         let default_track = format_reader.default_track().ok_or(FromFileError::NoDefaultTrack)?;
         let sample_rate = default_track.codec_params.sample_rate.ok_or(FromFileError::UnknownSampleRate)?;
-        let num_frames = default_track.codec_params.n_frames.unwrap_or(u64::MAX) as usize;
+        let num_frames = default_track.codec_params.n_frames.map(|n_frames| n_frames as usize);
         let decoder = codecs.make(&default_track.codec_params, &Default::default())?;
         let track_id = default_track.id;
         Ok(Self {
@@ -38,8 +56,19 @@ impl SymphoniaDecoder {
             sample_rate,
             num_frames,
             track_id,
+            channel_mode: ChannelMode::default(),
         })
     }
+
+    /// Returns the length of the track, or None if it is live and plays until it is stopped
+    pub fn duration(&self) -> Option<Duration> {
+        self.num_frames.map(|num_frames| Duration::from_secs_f64(num_frames as f64 / self.sample_rate as f64))
+    }
+
+    pub fn with_channel_mode(mut self, channel_mode: ChannelMode) -> Self {
+        self.channel_mode = channel_mode;
+        self
+    }
 }
 
 impl KiraDecoder for SymphoniaDecoder {
@@ -50,13 +79,13 @@ impl KiraDecoder for SymphoniaDecoder {
     }
 
     fn num_frames(&self) -> usize {
-        self.num_frames
+        self.num_frames.unwrap_or(usize::MAX)
     }
 
     fn decode(&mut self) -> Result<Vec<Frame>, Self::Error> {
         let packet = self.format_reader.next_packet()?;
         let buffer = self.decoder.decode(&packet)?;
-        load_frames_from_buffer_ref(&buffer)
+        load_frames_from_buffer_ref(&buffer, self.channel_mode)
     }
 
     fn seek(&mut self, index: usize) -> Result<usize, Self::Error> {
@@ -71,33 +100,107 @@ impl KiraDecoder for SymphoniaDecoder {
     }
 }
 
-pub fn load_frames_from_buffer_ref(buffer: &AudioBufferRef) -> Result<Vec<Frame>, FromFileError> {
+pub fn load_frames_from_buffer_ref(
+    buffer: &AudioBufferRef,
+    channel_mode: ChannelMode,
+) -> Result<Vec<Frame>, FromFileError> {
     match buffer {
-        AudioBufferRef::U8(buffer) => load_frames_from_buffer(buffer),
-        AudioBufferRef::U16(buffer) => load_frames_from_buffer(buffer),
-        AudioBufferRef::U24(buffer) => load_frames_from_buffer(buffer),
-        AudioBufferRef::U32(buffer) => load_frames_from_buffer(buffer),
-        AudioBufferRef::S8(buffer) => load_frames_from_buffer(buffer),
-        AudioBufferRef::S16(buffer) => load_frames_from_buffer(buffer),
-        AudioBufferRef::S24(buffer) => load_frames_from_buffer(buffer),
-        AudioBufferRef::S32(buffer) => load_frames_from_buffer(buffer),
-        AudioBufferRef::F32(buffer) => load_frames_from_buffer(buffer),
-        AudioBufferRef::F64(buffer) => load_frames_from_buffer(buffer),
+        AudioBufferRef::U8(buffer) => load_frames_from_buffer(buffer, channel_mode),
+        AudioBufferRef::U16(buffer) => load_frames_from_buffer(buffer, channel_mode),
+        AudioBufferRef::U24(buffer) => load_frames_from_buffer(buffer, channel_mode),
+        AudioBufferRef::U32(buffer) => load_frames_from_buffer(buffer, channel_mode),
+        AudioBufferRef::S8(buffer) => load_frames_from_buffer(buffer, channel_mode),
+        AudioBufferRef::S16(buffer) => load_frames_from_buffer(buffer, channel_mode),
+        AudioBufferRef::S24(buffer) => load_frames_from_buffer(buffer, channel_mode),
+        AudioBufferRef::S32(buffer) => load_frames_from_buffer(buffer, channel_mode),
+        AudioBufferRef::F32(buffer) => load_frames_from_buffer(buffer, channel_mode),
+        AudioBufferRef::F64(buffer) => load_frames_from_buffer(buffer, channel_mode),
     }
 }
 
-pub fn load_frames_from_buffer<S: Sample>(buffer: &AudioBuffer<S>) -> Result<Vec<Frame>, FromFileError>
+/// Mixes buffers with more than two channels down to stereo and, if wanted, stereo down to mono
+pub fn load_frames_from_buffer<S: Sample>(
+    buffer: &AudioBuffer<S>,
+    channel_mode: ChannelMode,
+) -> Result<Vec<Frame>, FromFileError>
 where
     f32: FromSample<S>,
 {
-    match buffer.spec().channels.count() {
-        1 => Ok(buffer.chan(0).iter().map(|sample| Frame::from_mono((*sample).into_sample())).collect()),
-        2 => Ok(buffer
+    let frames: Vec<Frame> = match buffer.spec().channels.count() {
+        0 => return Err(FromFileError::UnsupportedChannelConfiguration),
+        1 => buffer.chan(0).iter().map(|sample| Frame::from_mono((*sample).into_sample())).collect(),
+        2 => buffer
             .chan(0)
             .iter()
             .zip(buffer.chan(1).iter())
             .map(|(left, right)| Frame::new((*left).into_sample(), (*right).into_sample()))
-            .collect()),
-        _ => Err(FromFileError::UnsupportedChannelConfiguration),
+            .collect(),
+        _ => downmix(buffer),
+    };
+
+    Ok(match channel_mode {
+        ChannelMode::Stereo => frames,
+        ChannelMode::Mono => {
+            frames.into_iter().map(|frame| Frame::from_mono((frame.left + frame.right) / 2.0)).collect()
+        }
+    })
+}
+
+fn downmix<S: Sample>(buffer: &AudioBuffer<S>) -> Vec<Frame>
+where
+    f32: FromSample<S>,
+{
+    let mut frames = vec![Frame::ZERO; buffer.frames()];
+    // The planes of the buffer are ordered like the bits of their channels
+    for (index, (left_level, right_level)) in get_downmix_levels(buffer.spec().channels).into_iter().enumerate() {
+        for (frame, sample) in frames.iter_mut().zip(buffer.chan(index).iter()) {
+            let sample: f32 = (*sample).into_sample();
+            frame.left += sample * left_level;
+            frame.right += sample * right_level;
+        }
+    }
+    frames.into_iter().map(|frame| Frame::new(limit(frame.left), limit(frame.right))).collect()
+}
+
+/// Returns the levels each channel is mixed into the left and right output with. The front channels keep their
+/// level and the low frequency channels are left out, as usual for a stereo downmix.
+fn get_downmix_levels(channels: Channels) -> Vec<(f32, f32)> {
+    channels
+        .iter()
+        .map(|channel| match channel {
+            Channels::FRONT_LEFT
+            | Channels::FRONT_LEFT_CENTRE
+            | Channels::FRONT_LEFT_WIDE
+            | Channels::FRONT_LEFT_HIGH
+            | Channels::TOP_FRONT_LEFT => (1.0, 0.0),
+            Channels::FRONT_RIGHT
+            | Channels::FRONT_RIGHT_CENTRE
+            | Channels::FRONT_RIGHT_WIDE
+            | Channels::FRONT_RIGHT_HIGH
+            | Channels::TOP_FRONT_RIGHT => (0.0, 1.0),
+            Channels::FRONT_CENTRE
+            | Channels::FRONT_CENTRE_HIGH
+            | Channels::TOP_FRONT_CENTRE
+            | Channels::TOP_CENTRE => (SIDE_LEVEL, SIDE_LEVEL),
+            Channels::REAR_LEFT | Channels::REAR_LEFT_CENTRE | Channels::SIDE_LEFT | Channels::TOP_REAR_LEFT => {
+                (SIDE_LEVEL, 0.0)
+            }
+            Channels::REAR_RIGHT | Channels::REAR_RIGHT_CENTRE | Channels::SIDE_RIGHT | Channels::TOP_REAR_RIGHT => {
+                (0.0, SIDE_LEVEL)
+            }
+            Channels::REAR_CENTRE | Channels::TOP_REAR_CENTRE => (REAR_CENTRE_LEVEL, REAR_CENTRE_LEVEL),
+            _ => (0.0, 0.0),
+        })
+        .collect()
+}
+
+/// Leaves samples up to the threshold untouched and bends louder ones towards full scale, which they never exceed
+fn limit(sample: f32) -> f32 {
+    let level = sample.abs();
+    if level <= LIMITER_THRESHOLD {
+        return sample;
     }
+    let headroom = 1.0 - LIMITER_THRESHOLD;
+    let limited = LIMITER_THRESHOLD + headroom * ((level - LIMITER_THRESHOLD) / headroom).tanh();
+    limited.copysign(sample)
 }
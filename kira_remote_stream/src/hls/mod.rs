@@ -0,0 +1,199 @@
+mod mpeg_ts;
+
+use std::io::{Error, ErrorKind, Read, Seek, SeekFrom};
+use stream_download::http::reqwest::{Client, Url};
+use symphonia::core::io::MediaSource;
+use tokio::sync::mpsc;
+
+use crate::hls::mpeg_ts::TsDemuxer;
+use crate::media_source::{OnStreamState, StreamState};
+use crate::playlist::{fetch_playlist, MediaPlaylist, Playlist};
+
+// Segments downloaded ahead of the decoder, which limits the memory used by long playlists which are not live
+const BUFFERED_SEGMENTS: usize = 3;
+// Live playlists are joined this many segments before their end, as recommended by the HLS spec
+const LIVE_START_SEGMENTS: usize = 3;
+// Downloads failing in a row after which the stream ends
+const MAX_FAILURES: u32 = 5;
+
+/// Plays the segments of a HLS media playlist as one stream. Live playlists are reloaded in background to fetch new
+/// segments until they end. Segments in a transport stream are demuxed to their audio.
+pub struct HlsMediaSource {
+    receiver: mpsc::Receiver<Result<Vec<u8>, String>>,
+    chunk: Vec<u8>,
+    position: usize,
+}
+
+impl HlsMediaSource {
+    /// Starts downloading the segments. Has to be called within a tokio runtime.
+    pub fn start(client: Client, url: Url, playlist: MediaPlaylist, on_stream_state: Option<OnStreamState>) -> Self {
+        let (sender, receiver) = mpsc::channel(BUFFERED_SEGMENTS);
+        tokio::spawn(async move {
+            let notify = |state| {
+                if let Some(on_stream_state) = on_stream_state.as_ref() {
+                    on_stream_state(state);
+                }
+            };
+            if let Err(error) = download_segments(&client, &url, playlist, &sender, &notify).await {
+                notify(StreamState::Failed);
+                // Fails as well if the source was dropped already, which is fine
+                let _ = sender.send(Err(error)).await;
+            }
+        });
+
+        Self { receiver, chunk: vec![], position: 0 }
+    }
+}
+
+async fn download_segments(
+    client: &Client,
+    url: &Url,
+    mut playlist: MediaPlaylist,
+    sender: &mpsc::Sender<Result<Vec<u8>, String>>,
+    notify: &impl Fn(StreamState),
+) -> Result<(), String> {
+    let start = match playlist.ended {
+        true => 0,
+        false => playlist.segments.len().saturating_sub(LIVE_START_SEGMENTS),
+    };
+    let mut next_sequence = playlist.segments.get(start).map(|segment| segment.sequence).unwrap_or(0);
+    let mut sent_map = None;
+    let mut demuxer = TsDemuxer::default();
+    let mut failures = 0;
+    // Set while segments are missing, until the next one could be downloaded
+    let mut buffering = false;
+
+    loop {
+        if playlist.map.is_some() && playlist.map != sent_map {
+            let map = playlist.map.clone().expect("map is checked above");
+            let data = fetch_segment(client, &map).await?;
+            if sender.send(Ok(data)).await.is_err() {
+                return Ok(());
+            }
+            sent_map = Some(map);
+        }
+
+        let mut added = false;
+        for segment in playlist.segments.iter() {
+            if segment.sequence < next_sequence {
+                continue;
+            }
+            match fetch_segment(client, &segment.url).await {
+                Ok(data) => {
+                    failures = 0;
+                    if buffering {
+                        buffering = false;
+                        notify(StreamState::Recovered);
+                    }
+                    let audio = match mpeg_ts::is_transport_stream(&data) {
+                        true => demuxer.demux(&data),
+                        false => skip_id3(&data).to_vec(),
+                    };
+                    if sender.send(Ok(audio)).await.is_err() {
+                        return Ok(());
+                    }
+                }
+                // A missing segment of a live stream is only a short gap
+                Err(_) if !playlist.ended && failures < MAX_FAILURES => {
+                    failures += 1;
+                    if !buffering {
+                        buffering = true;
+                        notify(StreamState::Buffering);
+                    }
+                }
+                Err(error) => return Err(error),
+            }
+            next_sequence = segment.sequence + 1;
+            added = true;
+        }
+
+        if playlist.ended {
+            return Ok(());
+        }
+
+        // Servers add a segment every target duration, an unchanged playlist is checked again sooner
+        let delay = match added {
+            true => playlist.target_duration,
+            false => playlist.target_duration / 2,
+        };
+        tokio::time::sleep(delay).await;
+        if sender.is_closed() {
+            return Ok(());
+        }
+
+        match fetch_playlist(client, url).await {
+            Ok(Playlist::Media(reloaded)) => {
+                failures = 0;
+                playlist = reloaded;
+            }
+            Ok(Playlist::Entries(_)) => return Err("Reloaded playlist is no media playlist".to_string()),
+            Err(_) if failures < MAX_FAILURES => {
+                failures += 1;
+                playlist.segments.clear();
+                if !buffering {
+                    buffering = true;
+                    notify(StreamState::Buffering);
+                }
+            }
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+async fn fetch_segment(client: &Client, url: &Url) -> Result<Vec<u8>, String> {
+    let response = client
+        .get(url.clone())
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|error| format!("Could not fetch segment {}: {}", url, error))?;
+    let data = response.bytes().await.map_err(|error| format!("Could not read segment {}: {}", url, error))?;
+    Ok(data.to_vec())
+}
+
+/// Segments of plain audio start with an ID3 tag holding their timestamp, which decoders do not expect mid-stream
+fn skip_id3(mut data: &[u8]) -> &[u8] {
+    while data.len() >= 10 && data.starts_with(b"ID3") {
+        let size = data[6..10].iter().fold(0usize, |size, byte| (size << 7) | (byte & 0x7f) as usize);
+        let footer = if data[5] & 0x10 != 0 { 10 } else { 0 };
+        data = data.get(10 + size + footer..).unwrap_or_default();
+    }
+    data
+}
+
+impl Read for HlsMediaSource {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        // Waits for the next segment, the decoder runs on its own thread
+        while self.position == self.chunk.len() {
+            match self.receiver.blocking_recv() {
+                Some(Ok(chunk)) => {
+                    self.chunk = chunk;
+                    self.position = 0;
+                }
+                Some(Err(error)) => return Err(Error::other(error)),
+                None => return Ok(0),
+            }
+        }
+
+        let length = buf.len().min(self.chunk.len() - self.position);
+        buf[..length].copy_from_slice(&self.chunk[self.position..self.position + length]);
+        self.position += length;
+        Ok(length)
+    }
+}
+
+impl Seek for HlsMediaSource {
+    fn seek(&mut self, _pos: SeekFrom) -> std::io::Result<u64> {
+        Err(Error::new(ErrorKind::Unsupported, "HLS streams can not be seeked"))
+    }
+}
+
+impl MediaSource for HlsMediaSource {
+    fn is_seekable(&self) -> bool {
+        false
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        None
+    }
+}
@@ -0,0 +1,94 @@
+// Packets of a transport stream have a fixed size and start with a sync byte
+const PACKET_SIZE: usize = 188;
+const SYNC_BYTE: u8 = 0x47;
+// Program association table, which points to the program map table listing the streams
+const PAT_PID: u16 = 0;
+// Stream types of MPEG audio and AAC in ADTS, which symphonia decodes as elementary stream
+const AUDIO_STREAM_TYPES: [u8; 3] = [0x03, 0x04, 0x0f];
+const CRC_SIZE: usize = 4;
+
+pub fn is_transport_stream(data: &[u8]) -> bool {
+    data.len() >= PACKET_SIZE && data[0] == SYNC_BYTE && data.get(PACKET_SIZE).is_none_or(|byte| *byte == SYNC_BYTE)
+}
+
+/// Extracts the first audio stream of transport stream segments. The tables are kept over the segments, as not
+/// every segment has to repeat them.
+#[derive(Default)]
+pub struct TsDemuxer {
+    pmt_pid: Option<u16>,
+    audio_pid: Option<u16>,
+}
+
+impl TsDemuxer {
+    pub fn demux(&mut self, data: &[u8]) -> Vec<u8> {
+        let mut audio = vec![];
+        for packet in data.chunks_exact(PACKET_SIZE).filter(|packet| packet[0] == SYNC_BYTE) {
+            let unit_start = packet[1] & 0x40 != 0;
+            let pid = u16::from_be_bytes([packet[1] & 0x1f, packet[2]]);
+            let adaptation_field_control = (packet[3] >> 4) & 0x03;
+            let mut offset = 4;
+            if adaptation_field_control & 0x02 != 0 {
+                offset += 1 + packet[4] as usize;
+            }
+            if adaptation_field_control & 0x01 == 0 || offset >= PACKET_SIZE {
+                continue;
+            }
+            let payload = &packet[offset..];
+
+            if pid == PAT_PID && unit_start {
+                self.pmt_pid = parse_pat(payload).or(self.pmt_pid);
+            } else if Some(pid) == self.pmt_pid && unit_start {
+                self.audio_pid = parse_pmt(payload).or(self.audio_pid);
+            } else if Some(pid) == self.audio_pid {
+                let payload = if unit_start { skip_pes_header(payload) } else { Some(payload) };
+                audio.extend_from_slice(payload.unwrap_or_default());
+            }
+        }
+        audio
+    }
+}
+
+/// Returns the table after the pointer field without its checksum
+fn get_section(payload: &[u8]) -> Option<&[u8]> {
+    let pointer = *payload.first()? as usize;
+    let section = payload.get(1 + pointer..)?;
+    let length = u16::from_be_bytes([*section.get(1)? & 0x0f, *section.get(2)?]) as usize;
+    section.get(..(3 + length).checked_sub(CRC_SIZE)?.min(section.len()))
+}
+
+/// Returns the pid of the first program map table
+fn parse_pat(payload: &[u8]) -> Option<u16> {
+    let section = get_section(payload)?;
+    section.get(8..)?.chunks_exact(4).find_map(|program| {
+        let number = u16::from_be_bytes([program[0], program[1]]);
+        // Program 0 points to the network information table
+        (number != 0).then(|| u16::from_be_bytes([program[2] & 0x1f, program[3]]))
+    })
+}
+
+/// Returns the pid of the first audio stream
+fn parse_pmt(payload: &[u8]) -> Option<u16> {
+    let section = get_section(payload)?;
+    let program_info_length = u16::from_be_bytes([*section.get(10)? & 0x0f, *section.get(11)?]) as usize;
+    let mut streams = section.get(12 + program_info_length..)?;
+    while streams.len() >= 5 {
+        let stream_type = streams[0];
+        let pid = u16::from_be_bytes([streams[1] & 0x1f, streams[2]]);
+        if AUDIO_STREAM_TYPES.contains(&stream_type) {
+            return Some(pid);
+        }
+        let info_length = u16::from_be_bytes([streams[3] & 0x0f, streams[4]]) as usize;
+        streams = streams.get(5 + info_length..)?;
+    }
+    None
+}
+
+/// Audio packets start with a start code, stream id, length and two flag bytes followed by the length of the
+/// optional header fields
+fn skip_pes_header(payload: &[u8]) -> Option<&[u8]> {
+    if !payload.starts_with(&[0x00, 0x00, 0x01]) {
+        return None;
+    }
+    let header_length = *payload.get(8)? as usize;
+    payload.get(9 + header_length..)
+}
@@ -1,8 +1,10 @@
 pub mod decoder;
+pub mod hls;
 pub mod remote_decoder;
 pub mod media_source;
+pub mod playlist;
 
-/// Helper crate to create a remote stream with kira
+/// Helper crate to create a remote stream with kira. Urls of M3U, PLS and HLS playlists are resolved to their stream.
 ///
 /// ```
 /// use kira_remote_stream::RemoteStreamDecoder;
@@ -11,5 +13,6 @@ pub mod media_source;
 /// let settings = StreamingSoundSettings::default();
 /// let stream = StreamingSoundData::from_decoder(RemoteStreamDecoder::from_url("http://".to_string())?, settings);
 /// ```
-pub use decoder::symphonia::SymphoniaDecoder;
+pub use decoder::symphonia::{ChannelMode, SymphoniaDecoder};
+pub use media_source::{OnStreamState, OnStreamTitle, StreamCallbacks, StreamState};
 pub use remote_decoder::RemoteStreamDecoder;
@@ -1,4 +1,8 @@
-use std::io::{Read, Seek, SeekFrom};
+use std::io::{Error, ErrorKind, Read, Seek, SeekFrom};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use stream_download::http::reqwest::header::{HeaderMap, HeaderValue};
 use stream_download::http::reqwest::{Client, Url};
 
 use stream_download::http::HttpStream;
@@ -6,44 +10,241 @@ use stream_download::source::SourceStream;
 use stream_download::storage::memory::MemoryStorageProvider;
 use stream_download::{Settings, StreamDownload};
 use symphonia::core::io::MediaSource;
+use tokio::runtime::Handle;
+
+// Reconnecting waits this long at first and doubles the delay with every failed attempt up to the maximum
+const RECONNECT_DELAY: Duration = Duration::from_millis(500);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(8);
+// Attempts after which the stream fails, which is about half a minute without network
+const MAX_RECONNECTS: u32 = 6;
+// Stalled connections are dropped after this long without data, so they are reconnected as well
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Receives the title of the song an internet radio is playing, whenever it changes
+pub type OnStreamTitle = Arc<dyn Fn(String) + Send + Sync>;
+/// Receives the state of the connection, whenever the stream is interrupted or continues
+pub type OnStreamState = Arc<dyn Fn(StreamState) + Send + Sync>;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StreamState {
+    // Connection was lost, the stream waits for it to be reconnected
+    Buffering,
+    // Stream plays again after it was reconnected
+    Recovered,
+    // Stream could not be reconnected and ends
+    Failed,
+}
+
+/// Callbacks of a remote stream, all of them are optional
+#[derive(Clone, Default)]
+pub struct StreamCallbacks {
+    pub on_stream_title: Option<OnStreamTitle>,
+    pub on_stream_state: Option<OnStreamState>,
+}
 
 pub struct RemoteMediaSource {
+    url: Url,
     reader: StreamDownload<MemoryStorageProvider>,
     content_length: Option<u64>,
+    // Position in the download, where a reconnected stream continues
+    position: u64,
+    icy: Option<IcyMetadata>,
+    on_stream_state: Option<OnStreamState>,
+    // Reconnects are done from the decoder thread, which has no runtime on its own
+    runtime: Handle,
+    // Attempts since the last successful read
+    reconnects: u32,
+}
+
+/// Radio servers interleave the audio with metadata blocks, if the client asks for it by the Icy-MetaData header.
+/// Every block follows after the same amount of audio bytes and starts with its length in 16 bytes.
+struct IcyMetadata {
+    interval: usize,
+    remaining: usize,
+    title: Option<String>,
+    on_stream_title: OnStreamTitle,
 }
 
 impl RemoteMediaSource {
-    pub async fn from_url(url: String) -> Result<Self, String> {
+    pub async fn from_url(url: String, callbacks: StreamCallbacks) -> Result<Self, String> {
         let parsed_url = url.parse::<Url>().map_err(|error| format!("Could not parse url: {}", error))?;
-        let stream = HttpStream::<Client>::create(parsed_url)
-            .await
-            .map_err(|error| format!("Could not create stream: {}", error))?;
+        let stream = Self::connect(parsed_url.clone(), callbacks.on_stream_title.is_some()).await?;
+        Self::from_stream(parsed_url, stream, callbacks).await
+    }
+
+    /// Opens the url, asking radio servers to send their metadata if wanted. The response headers tell whether it is
+    /// a stream indeed or a playlist.
+    pub async fn connect(url: Url, icy_metadata: bool) -> Result<HttpStream<Client>, String> {
+        let mut headers = HeaderMap::new();
+        if icy_metadata {
+            headers.insert("Icy-MetaData", HeaderValue::from_static("1"));
+        }
+        let client = Client::builder()
+            .default_headers(headers)
+            .read_timeout(READ_TIMEOUT)
+            .build()
+            .map_err(|error| format!("Could not create client: {}", error))?;
+        HttpStream::new(client, url).await.map_err(|error| format!("Could not create stream: {}", error))
+    }
 
+    pub async fn from_stream(url: Url, stream: HttpStream<Client>, callbacks: StreamCallbacks) -> Result<Self, String> {
         let content_length = stream.content_length();
+        let interval = stream.header("icy-metaint").and_then(|interval| interval.trim().parse::<usize>().ok());
+        let icy = match (interval, callbacks.on_stream_title) {
+            (Some(interval), Some(on_stream_title)) if interval > 0 => {
+                Some(IcyMetadata { interval, remaining: interval, title: None, on_stream_title })
+            }
+            _ => None,
+        };
 
-        let reader = StreamDownload::from_stream(stream, MemoryStorageProvider::default(), Settings::default())
+        Ok(RemoteMediaSource {
+            url,
+            reader: Self::download(stream).await?,
+            content_length,
+            position: 0,
+            icy,
+            on_stream_state: callbacks.on_stream_state,
+            runtime: Handle::current(),
+            reconnects: 0,
+        })
+    }
+
+    async fn download(stream: HttpStream<Client>) -> Result<StreamDownload<MemoryStorageProvider>, String> {
+        StreamDownload::from_stream(stream, MemoryStorageProvider::default(), Settings::default())
             .await
-            .map_err(|error| format!("Could start download: {}", error))?;
+            .map_err(|error| format!("Could start download: {}", error))
+    }
 
-        Ok(RemoteMediaSource { reader, content_length })
+    fn notify(&self, state: StreamState) {
+        if let Some(on_stream_state) = self.on_stream_state.as_ref() {
+            on_stream_state(state);
+        }
     }
+
+    /// Whether everything was read of a stream with known length. Live streams never end on their own.
+    fn is_complete(&self) -> bool {
+        self.content_length.is_some_and(|content_length| self.position >= content_length)
+    }
+
+    /// Opens the stream again after its connection failed, waiting longer with every attempt. Seekable streams
+    /// continue where they stopped by a range request, live streams continue with what is playing now.
+    fn reconnect(&mut self, error: Error) -> std::io::Result<()> {
+        loop {
+            if self.reconnects >= MAX_RECONNECTS {
+                if self.reconnects == MAX_RECONNECTS {
+                    self.reconnects += 1;
+                    self.notify(StreamState::Failed);
+                }
+                return Err(error);
+            }
+            if self.reconnects == 0 {
+                self.notify(StreamState::Buffering);
+            }
+            thread::sleep((RECONNECT_DELAY * 2u32.pow(self.reconnects)).min(MAX_RECONNECT_DELAY));
+            self.reconnects += 1;
+
+            let connect = async {
+                let stream = Self::connect(self.url.clone(), self.icy.is_some()).await?;
+                Self::download(stream).await
+            };
+            let mut reader = match self.runtime.block_on(connect) {
+                Ok(reader) => reader,
+                Err(_) => continue,
+            };
+            if self.is_seekable() && self.position > 0 && reader.seek(SeekFrom::Start(self.position)).is_err() {
+                continue;
+            }
+
+            self.reader = reader;
+            if let Some(icy) = self.icy.as_mut() {
+                // A new connection starts with audio again
+                icy.remaining = icy.interval;
+            }
+            return Ok(());
+        }
+    }
+
+    fn read_stream(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.icy.as_ref().is_some_and(|icy| icy.remaining == 0) {
+            self.read_metadata()?;
+        }
+
+        let read = match self.icy.as_mut() {
+            Some(icy) => {
+                let length = buf.len().min(icy.remaining);
+                let read = self.reader.read(&mut buf[..length])?;
+                icy.remaining -= read;
+                read
+            }
+            None => self.reader.read(buf)?,
+        };
+        self.position += read as u64;
+        Ok(read)
+    }
+
+    /// Reads the metadata block in front of the reader and passes a changed stream title on
+    fn read_metadata(&mut self) -> std::io::Result<()> {
+        let mut length = [0u8; 1];
+        self.reader.read_exact(&mut length)?;
+        let mut metadata = vec![0u8; length[0] as usize * 16];
+        self.reader.read_exact(&mut metadata)?;
+
+        let icy = self.icy.as_mut().expect("metadata is only read for icy streams");
+        icy.remaining = icy.interval;
+        // Most servers send an empty block, unless the title changed
+        if let Some(title) = get_stream_title(&String::from_utf8_lossy(&metadata)) {
+            if icy.title.as_ref() != Some(&title) {
+                icy.title = Some(title.clone());
+                (icy.on_stream_title)(title);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Extracts the title of metadata like `StreamTitle='Artist - Title';StreamUrl='';`
+fn get_stream_title(metadata: &str) -> Option<String> {
+    let start = metadata.find("StreamTitle='")? + "StreamTitle='".len();
+    let length = metadata[start..].find("';").unwrap_or(metadata[start..].trim_end_matches('\0').len());
+    Some(metadata[start..start + length].trim().to_string())
 }
 
 impl Read for RemoteMediaSource {
+    /// Reconnects if the connection fails or ends before the stream did
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        self.reader.read(buf)
+        loop {
+            let error = match self.read_stream(buf) {
+                Ok(0) if !buf.is_empty() && !self.is_complete() => {
+                    Error::new(ErrorKind::UnexpectedEof, "connection closed before the stream ended")
+                }
+                Err(error) if error.kind() != ErrorKind::Interrupted => error,
+                result => {
+                    if result.is_ok() && self.reconnects > 0 {
+                        self.reconnects = 0;
+                        self.notify(StreamState::Recovered);
+                    }
+                    return result;
+                }
+            };
+            self.reconnect(error)?;
+        }
     }
 }
 
 impl Seek for RemoteMediaSource {
     fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
-        self.reader.seek(pos)
+        // Positions in the download include the metadata blocks, so they do not match the ones of the audio
+        if self.icy.is_some() {
+            return Err(Error::new(ErrorKind::Unsupported, "streams with metadata can not be seeked"));
+        }
+        self.position = self.reader.seek(pos)?;
+        Ok(self.position)
     }
 }
 
 impl MediaSource for RemoteMediaSource {
     fn is_seekable(&self) -> bool {
-        self.content_length.is_some()
+        self.content_length.is_some() && self.icy.is_none()
     }
 
     fn byte_len(&self) -> Option<u64> {
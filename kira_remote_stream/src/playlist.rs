@@ -0,0 +1,170 @@
+use std::time::Duration;
+use stream_download::http::reqwest::{Client, Url};
+
+// Content types radio stations serve their playlists with, the ones of HLS included
+const PLAYLIST_CONTENT_TYPES: [&str; 6] = [
+    "audio/x-mpegurl",
+    "audio/mpegurl",
+    "application/x-mpegurl",
+    "application/vnd.apple.mpegurl",
+    "audio/x-scpls",
+    "application/pls+xml",
+];
+const PLAYLIST_EXTENSIONS: [&str; 3] = ["m3u", "m3u8", "pls"];
+
+pub enum Playlist {
+    /// Urls of the same stream, e.g. of several servers or bitrates, in the order they should be tried
+    Entries(Vec<Url>),
+    /// HLS playlist, whose segments are played one after another
+    Media(MediaPlaylist),
+}
+
+pub struct Segment {
+    pub sequence: u64,
+    pub url: Url,
+}
+
+pub struct MediaPlaylist {
+    // Segments are at most this long, so new ones appear in this interval
+    pub target_duration: Duration,
+    pub segments: Vec<Segment>,
+    // Initialization section of fragmented mp4 segments
+    pub map: Option<Url>,
+    // Live playlists get new segments until they are ended
+    pub ended: bool,
+}
+
+pub fn is_playlist_url(url: &Url) -> bool {
+    url.path()
+        .rsplit_once('.')
+        .is_some_and(|(_, extension)| PLAYLIST_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+}
+
+pub fn is_playlist_content_type(content_type: &str) -> bool {
+    let mime_type = content_type.split(';').next().unwrap_or_default().trim().to_lowercase();
+    PLAYLIST_CONTENT_TYPES.contains(&mime_type.as_str())
+}
+
+pub async fn fetch_playlist(client: &Client, url: &Url) -> Result<Playlist, String> {
+    let content = client
+        .get(url.clone())
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|error| format!("Could not fetch playlist: {}", error))?
+        .text()
+        .await
+        .map_err(|error| format!("Could not read playlist: {}", error))?;
+
+    parse_playlist(url, &content)
+}
+
+/// Parses PLS and M3U playlists. M3U playlists with HLS tags are either master playlists, whose variants are
+/// returned as entries, or media playlists.
+pub fn parse_playlist(url: &Url, content: &str) -> Result<Playlist, String> {
+    let content = content.trim_start_matches('\u{feff}').trim_start();
+    let playlist = if content.to_lowercase().starts_with("[playlist]") {
+        Playlist::Entries(parse_pls(url, content))
+    } else if content.contains("#EXT-X-STREAM-INF") {
+        Playlist::Entries(parse_master_playlist(url, content))
+    } else if content.contains("#EXT-X-TARGETDURATION") {
+        Playlist::Media(parse_media_playlist(url, content)?)
+    } else {
+        Playlist::Entries(get_uri_lines(content).filter_map(|line| url.join(line).ok()).collect())
+    };
+
+    match &playlist {
+        Playlist::Entries(entries) if entries.is_empty() => Err("Playlist contains no entries".to_string()),
+        _ => Ok(playlist),
+    }
+}
+
+fn get_uri_lines(content: &str) -> impl Iterator<Item = &str> {
+    content.lines().map(|line| line.trim()).filter(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+/// Returns the entries of lines like `File1=http://...` ordered by their number
+fn parse_pls(url: &Url, content: &str) -> Vec<Url> {
+    let mut entries = content
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.trim().split_once('=')?;
+            let number = key.trim().to_lowercase().strip_prefix("file")?.parse::<u32>().ok()?;
+            Some((number, url.join(value.trim()).ok()?))
+        })
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|(number, _)| *number);
+    entries.into_iter().map(|(_, entry)| entry).collect()
+}
+
+/// Returns the audio renditions followed by the variants, the ones with the highest bandwidth first
+fn parse_master_playlist(url: &Url, content: &str) -> Vec<Url> {
+    let mut renditions = vec![];
+    let mut variants = vec![];
+    let mut bandwidth = None;
+    for line in content.lines().map(|line| line.trim()).filter(|line| !line.is_empty()) {
+        if let Some(attributes) = line.strip_prefix("#EXT-X-MEDIA:") {
+            if get_attribute(attributes, "TYPE").as_deref() == Some("AUDIO") {
+                renditions.extend(get_attribute(attributes, "URI").and_then(|uri| url.join(&uri).ok()));
+            }
+        } else if let Some(attributes) = line.strip_prefix("#EXT-X-STREAM-INF:") {
+            bandwidth = Some(get_attribute(attributes, "BANDWIDTH").and_then(|value| value.parse().ok()).unwrap_or(0));
+        } else if !line.starts_with('#') {
+            if let (Some(bandwidth), Ok(variant)) = (bandwidth.take(), url.join(line)) {
+                variants.push((bandwidth, variant));
+            }
+        }
+    }
+    variants.sort_by_key(|(bandwidth, _): &(u64, Url)| std::cmp::Reverse(*bandwidth));
+
+    renditions.into_iter().chain(variants.into_iter().map(|(_, variant)| variant)).collect()
+}
+
+fn parse_media_playlist(url: &Url, content: &str) -> Result<MediaPlaylist, String> {
+    let mut playlist =
+        MediaPlaylist { target_duration: Duration::from_secs(10), segments: vec![], map: None, ended: false };
+    let mut sequence = 0;
+    for line in content.lines().map(|line| line.trim()).filter(|line| !line.is_empty()) {
+        if let Some(value) = line.strip_prefix("#EXT-X-TARGETDURATION:") {
+            let seconds = value.parse::<f64>().map_err(|_| format!("Invalid target duration {}", value))?;
+            playlist.target_duration = Duration::from_secs_f64(seconds.max(1.0));
+        } else if let Some(value) = line.strip_prefix("#EXT-X-MEDIA-SEQUENCE:") {
+            sequence = value.parse::<u64>().map_err(|_| format!("Invalid media sequence {}", value))?;
+        } else if let Some(attributes) = line.strip_prefix("#EXT-X-MAP:") {
+            playlist.map = get_attribute(attributes, "URI").and_then(|uri| url.join(&uri).ok());
+        } else if let Some(attributes) = line.strip_prefix("#EXT-X-KEY:") {
+            let method = get_attribute(attributes, "METHOD").unwrap_or_default();
+            if method != "NONE" {
+                return Err(format!("Encrypted streams ({}) are not supported", method));
+            }
+        } else if line == "#EXT-X-ENDLIST" {
+            playlist.ended = true;
+        } else if !line.starts_with('#') {
+            let url = url.join(line).map_err(|error| format!("Invalid segment url {}: {}", line, error))?;
+            playlist.segments.push(Segment { sequence, url });
+            sequence += 1;
+        }
+    }
+
+    Ok(playlist)
+}
+
+/// Returns the value of an attribute list like `BANDWIDTH=128000,CODECS="mp4a.40.2",URI="audio.m3u8"`
+fn get_attribute(attributes: &str, name: &str) -> Option<String> {
+    let mut rest = attributes;
+    while !rest.is_empty() {
+        let (key, value) = rest.split_once('=')?;
+        let (value, next) = match value.strip_prefix('"') {
+            Some(quoted) => {
+                let (value, next) = quoted.split_once('"').unwrap_or((quoted, ""));
+                (value, next.trim_start_matches(','))
+            }
+            None => value.split_once(',').unwrap_or((value, "")),
+        };
+        if key.trim() == name {
+            return Some(value.to_string());
+        }
+        rest = next;
+    }
+    None
+}
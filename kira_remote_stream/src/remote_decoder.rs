@@ -1,16 +1,83 @@
+use std::collections::VecDeque;
+use stream_download::http::reqwest::{Client, Url};
+use symphonia::core::io::MediaSource;
+
 use crate::decoder::symphonia::SymphoniaDecoder;
-use crate::media_source::RemoteMediaSource;
+use crate::hls::HlsMediaSource;
+use crate::media_source::{RemoteMediaSource, StreamCallbacks};
+use crate::playlist::{fetch_playlist, is_playlist_content_type, is_playlist_url, Playlist};
+
+// Playlists may point to further playlists, e.g. a HLS master playlist to the ones of its variants
+const MAX_PLAYLIST_DEPTH: usize = 3;
 
 pub struct RemoteStreamDecoder {}
 
+enum Source {
+    Decoder(SymphoniaDecoder),
+    Playlist(Vec<Url>),
+}
+
 impl RemoteStreamDecoder {
     pub async fn from_url(url: String) -> Result<SymphoniaDecoder, String> {
-        let stream = RemoteMediaSource::from_url(url)
-            .await
-            .map_err(|error| format!("Could not create remote media source: {}", error))?;
-        let decoder = SymphoniaDecoder::new(Box::new(stream))
-            .map_err(|error| format!("Could not create remote decoder: {}", error))?;
+        Self::create(url, StreamCallbacks::default()).await
+    }
+
+    /// Like from_url, but calls back with the title of every song an internet radio plays, if wanted, and with the
+    /// state of the connection whenever it is lost or recovered
+    pub async fn from_url_with_callbacks(url: String, callbacks: StreamCallbacks) -> Result<SymphoniaDecoder, String> {
+        Self::create(url, callbacks).await
+    }
 
-        Ok(decoder)
+    /// Resolves M3U, PLS and HLS playlists to their streams. The entries of a playlist are tried in order until one
+    /// can be played.
+    async fn create(url: String, callbacks: StreamCallbacks) -> Result<SymphoniaDecoder, String> {
+        let url = url.parse::<Url>().map_err(|error| format!("Could not parse url: {}", error))?;
+        let client = Client::new();
+
+        let mut candidates = VecDeque::from([(url, 0)]);
+        let mut errors = vec![];
+        while let Some((url, depth)) = candidates.pop_front() {
+            match Self::open(&client, &url, callbacks.clone()).await {
+                Ok(Source::Decoder(decoder)) => return Ok(decoder),
+                Ok(Source::Playlist(entries)) if depth < MAX_PLAYLIST_DEPTH => {
+                    for entry in entries.into_iter().rev() {
+                        candidates.push_front((entry, depth + 1));
+                    }
+                }
+                Ok(Source::Playlist(_)) => errors.push(format!("{}: Playlists are nested too deep", url)),
+                Err(error) => errors.push(format!("{}: {}", url, error)),
+            }
+        }
+
+        Err(format!("Could not play remote stream: {}", errors.join(", ")))
+    }
+
+    async fn open(client: &Client, url: &Url, callbacks: StreamCallbacks) -> Result<Source, String> {
+        if !is_playlist_url(url) {
+            let stream = RemoteMediaSource::connect(url.clone(), callbacks.on_stream_title.is_some()).await?;
+            // Playlists without extension are recognized by their content type
+            if !stream.header("content-type").is_some_and(is_playlist_content_type) {
+                let source = RemoteMediaSource::from_stream(url.clone(), stream, callbacks)
+                    .await
+                    .map_err(|error| format!("Could not create remote media source: {}", error))?;
+                return Self::decode(Box::new(source)).await.map(Source::Decoder);
+            }
+        }
+
+        match fetch_playlist(client, url).await? {
+            Playlist::Entries(entries) => Ok(Source::Playlist(entries)),
+            Playlist::Media(playlist) => {
+                let source = HlsMediaSource::start(client.clone(), url.clone(), playlist, callbacks.on_stream_state);
+                Self::decode(Box::new(source)).await.map(Source::Decoder)
+            }
+        }
+    }
+
+    /// Probing reads from the stream, which blocks until the data is downloaded
+    async fn decode(source: Box<dyn MediaSource>) -> Result<SymphoniaDecoder, String> {
+        tokio::task::spawn_blocking(move || SymphoniaDecoder::new(source))
+            .await
+            .map_err(|error| format!("Creating remote decoder was aborted: {}", error))?
+            .map_err(|error| format!("Could not create remote decoder: {}", error))
     }
 }
@@ -0,0 +1,79 @@
+use kira::Frame;
+use kira_remote_stream::decoder::symphonia::load_frames_from_buffer;
+use kira_remote_stream::ChannelMode;
+use symphonia::core::audio::{AudioBuffer, Channels, Layout, Signal, SignalSpec};
+
+const CENTRE_LEVEL: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+/// Buffer with a single frame, which has the samples on its channels in their order
+fn buffer(channels: Channels, samples: &[f32]) -> AudioBuffer<f32> {
+    let mut buffer = AudioBuffer::<f32>::new(1, SignalSpec::new(44100, channels));
+    buffer.render_reserved(Some(1));
+    for (index, sample) in samples.iter().enumerate() {
+        buffer.chan_mut(index)[0] = *sample;
+    }
+    buffer
+}
+
+fn load_frame(channels: Channels, samples: &[f32], channel_mode: ChannelMode) -> Frame {
+    load_frames_from_buffer(&buffer(channels, samples), channel_mode).unwrap()[0]
+}
+
+fn assert_frame(frame: Frame, left: f32, right: f32) {
+    assert!((frame.left - left).abs() < 1e-4, "left is {} instead of {}", frame.left, left);
+    assert!((frame.right - right).abs() < 1e-4, "right is {} instead of {}", frame.right, right);
+}
+
+#[test]
+fn keeps_level_of_front_channels_in_surround_downmix() {
+    // Channels of 5.1 are front left, front right, centre, low frequency, rear left and rear right
+    let channels = Layout::FivePointOne.into_channels();
+
+    assert_frame(load_frame(channels, &[0.5, 0.0, 0.0, 0.0, 0.0, 0.0], ChannelMode::Stereo), 0.5, 0.0);
+    assert_frame(load_frame(channels, &[0.0, 0.5, 0.0, 0.0, 0.0, 0.0], ChannelMode::Stereo), 0.0, 0.5);
+}
+
+#[test]
+fn lowers_centre_and_surround_channels_by_3_db() {
+    let channels = Layout::FivePointOne.into_channels();
+    let level = 0.5 * CENTRE_LEVEL;
+
+    assert_frame(load_frame(channels, &[0.0, 0.0, 0.5, 0.0, 0.0, 0.0], ChannelMode::Stereo), level, level);
+    assert_frame(load_frame(channels, &[0.0, 0.0, 0.0, 0.0, 0.5, 0.0], ChannelMode::Stereo), level, 0.0);
+    assert_frame(load_frame(channels, &[0.0, 0.0, 0.0, 0.0, 0.0, 0.5], ChannelMode::Stereo), 0.0, level);
+}
+
+#[test]
+fn leaves_out_low_frequency_channel() {
+    let channels = Layout::FivePointOne.into_channels();
+
+    assert_frame(load_frame(channels, &[0.0, 0.0, 0.0, 1.0, 0.0, 0.0], ChannelMode::Stereo), 0.0, 0.0);
+}
+
+#[test]
+fn limits_loud_surround_downmix_below_full_scale() {
+    let channels = Layout::FivePointOne.into_channels();
+
+    // Sums up to 1.2 on each side without the limiter
+    let frame = load_frame(channels, &[0.5; 6], ChannelMode::Stereo);
+    assert!(frame.left > 0.8 && frame.left < 1.0, "left is {}", frame.left);
+    assert_frame(frame, frame.left, frame.left);
+    let frame = load_frame(channels, &[-1.0; 6], ChannelMode::Stereo);
+    assert!(frame.left < -0.8 && frame.left >= -1.0, "left is {}", frame.left);
+}
+
+#[test]
+fn mixes_stereo_down_to_mono() {
+    let channels = Layout::Stereo.into_channels();
+
+    assert_frame(load_frame(channels, &[0.5, 0.0], ChannelMode::Mono), 0.25, 0.25);
+    assert_frame(load_frame(channels, &[0.5, 0.5], ChannelMode::Mono), 0.5, 0.5);
+    assert_frame(load_frame(channels, &[0.5, -0.5], ChannelMode::Mono), 0.0, 0.0);
+}
+
+#[test]
+fn keeps_stereo_in_stereo_mode() {
+    let channels = Layout::Stereo.into_channels();
+
+    assert_frame(load_frame(channels, &[0.5, -0.25], ChannelMode::Stereo), 0.5, -0.25);
+}
@@ -0,0 +1,151 @@
+use fixture_server::{fixture, serve};
+use kira::sound::streaming::Decoder;
+use kira_remote_stream::{RemoteStreamDecoder, SymphoniaDecoder};
+
+const SAMPLE_RATE: u32 = 8000;
+
+/// One second of a sine wave as 16 bit mono WAV file
+fn wav() -> Vec<u8> {
+    let samples = (0..SAMPLE_RATE)
+        .flat_map(|index| {
+            let sample = (index as f32 * 440.0 * std::f32::consts::TAU / SAMPLE_RATE as f32).sin() * 8000.0;
+            (sample as i16).to_le_bytes()
+        })
+        .collect::<Vec<u8>>();
+
+    let mut wav = b"RIFF".to_vec();
+    wav.extend((36 + samples.len() as u32).to_le_bytes());
+    wav.extend(b"WAVEfmt ");
+    wav.extend(16u32.to_le_bytes());
+    wav.extend(1u16.to_le_bytes());
+    wav.extend(1u16.to_le_bytes());
+    wav.extend(SAMPLE_RATE.to_le_bytes());
+    wav.extend((SAMPLE_RATE * 2).to_le_bytes());
+    wav.extend(2u16.to_le_bytes());
+    wav.extend(16u16.to_le_bytes());
+    wav.extend(b"data");
+    wav.extend((samples.len() as u32).to_le_bytes());
+    wav.extend(samples);
+    wav
+}
+
+/// Splits the data into packets of the pid, the last one padded by an adaptation field
+fn ts_packets(pid: u16, mut payload: &[u8]) -> Vec<u8> {
+    let mut packets = vec![];
+    let mut first = true;
+    while first || !payload.is_empty() {
+        let (chunk, rest) = payload.split_at(payload.len().min(184));
+        packets.extend([0x47, if first { 0x40 } else { 0x00 } | (pid >> 8) as u8, pid as u8]);
+        if chunk.len() < 184 {
+            let padding = 184 - chunk.len() - 1;
+            packets.push(0x30);
+            packets.push(padding as u8);
+            if padding > 0 {
+                packets.push(0x00);
+                packets.extend(vec![0xff; padding - 1]);
+            }
+        } else {
+            packets.push(0x10);
+        }
+        packets.extend(chunk);
+        payload = rest;
+        first = false;
+    }
+    packets
+}
+
+/// Wraps the data as MPEG audio stream into a transport stream segment
+fn ts_segment(data: &[u8]) -> Vec<u8> {
+    let pat = [0x00, 0x00, 0xb0, 13, 0x00, 0x01, 0xc1, 0x00, 0x00, 0x00, 0x01, 0xf0, 0x00, 0, 0, 0, 0];
+    let pmt = [
+        0x00, 0x02, 0xb0, 18, 0x00, 0x01, 0xc1, 0x00, 0x00, 0xe1, 0x01, 0xf0, 0x00, 0x04, 0xe1, 0x01, 0xf0, 0x00, 0, 0,
+        0, 0,
+    ];
+    let pes = [&[0x00, 0x00, 0x01, 0xc0, 0x00, 0x00, 0x80, 0x80, 0x05, 0x21, 0x00, 0x01, 0x00, 0x01], data].concat();
+
+    [ts_packets(0x0000, &pat), ts_packets(0x1000, &pmt), ts_packets(0x0101, &pes)].concat()
+}
+
+fn media_playlist(segments: &[&str]) -> String {
+    let segments = segments.iter().map(|segment| format!("#EXTINF:0.5,\n{}\n", segment)).collect::<String>();
+    format!("#EXTM3U\n#EXT-X-TARGETDURATION:1\n#EXT-X-MEDIA-SEQUENCE:7\n{}#EXT-X-ENDLIST\n", segments)
+}
+
+/// Decodes the whole stream and returns its sample rate and number of frames
+async fn decode(url: String) -> Result<(u32, usize), String> {
+    let mut decoder: SymphoniaDecoder = RemoteStreamDecoder::from_url(url).await?;
+    let frames = tokio::task::spawn_blocking(move || {
+        let mut frames = 0;
+        while let Ok(decoded) = decoder.decode() {
+            frames += decoded.len();
+        }
+        (decoder.sample_rate(), frames)
+    });
+    Ok(frames.await.unwrap())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn pls_falls_back_to_the_next_entry() {
+    let base_url = serve(|base_url| {
+        let pls = format!("[playlist]\nNumberOfEntries=2\nFile2=audio.wav\nFile1={}/offline.mp3\n", base_url);
+        vec![fixture("/radio.pls", "audio/x-scpls", pls), fixture("/audio.wav", "audio/wav", wav())]
+    });
+
+    assert_eq!(decode(format!("{}/radio.pls", base_url)).await, Ok((SAMPLE_RATE, SAMPLE_RATE as usize)));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn m3u_is_detected_by_its_content_type() {
+    let base_url = serve(|_| {
+        vec![
+            fixture("/listen", "audio/x-mpegurl; charset=utf-8", "#EXTM3U\n#EXTINF:-1,Radio\nstreams/audio.wav\n"),
+            fixture("/streams/audio.wav", "audio/wav", wav()),
+        ]
+    });
+
+    assert_eq!(decode(format!("{}/listen", base_url)).await, Ok((SAMPLE_RATE, SAMPLE_RATE as usize)));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn hls_segments_are_played_as_one_stream() {
+    let wav = wav();
+    let (first, second) = wav.split_at(wav.len() / 2);
+    // Packed audio segments start with an ID3 tag
+    let first = [b"ID3\x04\x00\x00\x00\x00\x00\x05hello".as_slice(), first].concat();
+    let second = second.to_vec();
+    let base_url = serve(move |_| {
+        let master = "#EXTM3U\n#EXT-X-STREAM-INF:BANDWIDTH=128000,CODECS=\"mp4a.40.2\"\nhigh/index.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=64000\nlow/index.m3u8\n";
+        vec![
+            fixture("/master.m3u8", "application/vnd.apple.mpegurl", master),
+            fixture("/low/index.m3u8", "application/vnd.apple.mpegurl", media_playlist(&["0.aac", "1.aac"])),
+            fixture("/low/0.aac", "audio/aac", first),
+            fixture("/low/1.aac", "audio/aac", second),
+        ]
+    });
+
+    assert_eq!(decode(format!("{}/master.m3u8", base_url)).await, Ok((SAMPLE_RATE, SAMPLE_RATE as usize)));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn hls_transport_stream_segments_are_demuxed() {
+    let wav = wav();
+    let (first, second) = wav.split_at(wav.len() / 2);
+    let (first, second) = (ts_segment(first), ts_segment(second));
+    let base_url = serve(move |_| {
+        vec![
+            fixture("/index.m3u8", "application/vnd.apple.mpegurl", media_playlist(&["0.ts", "1.ts"])),
+            fixture("/0.ts", "video/mp2t", first),
+            fixture("/1.ts", "video/mp2t", second),
+        ]
+    });
+
+    assert_eq!(decode(format!("{}/index.m3u8", base_url)).await, Ok((SAMPLE_RATE, SAMPLE_RATE as usize)));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn playlist_without_playable_entry_fails() {
+    let base_url = serve(|_| vec![fixture("/radio.m3u", "audio/x-mpegurl", "missing.mp3\n")]);
+
+    assert!(decode(format!("{}/radio.m3u", base_url)).await.is_err());
+}
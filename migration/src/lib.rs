@@ -6,6 +6,18 @@ mod m20240115_221627_create_default_system_config;
 mod m20240212_225127_create_root_library_entry;
 mod m20240321_123652_add_library_entry_sort_key;
 mod m20250129_230144_add_on_off_shim_pins;
+mod m20261018_091500_create_playback_position;
+mod m20261018_134500_add_library_entry_playback_mode;
+mod m20261019_101000_add_crossfade;
+mod m20261019_143000_move_track_files_to_media_store;
+mod m20261019_170000_create_card_mapping;
+mod m20261019_190000_add_button_pins;
+mod m20261020_080000_add_quiet_hours;
+mod m20261020_120000_add_loudness_gain;
+mod m20261021_090000_create_audio_config;
+mod m20261021_140000_add_podcast_fields;
+mod m20261022_090000_add_audio_cache;
+mod m20261023_090000_add_mono_output;
 
 pub struct Migrator;
 
@@ -19,6 +31,18 @@ impl MigratorTrait for Migrator {
             Box::new(m20240212_225127_create_root_library_entry::Migration),
             Box::new(m20240321_123652_add_library_entry_sort_key::Migration),
             Box::new(m20250129_230144_add_on_off_shim_pins::Migration),
+            Box::new(m20261018_091500_create_playback_position::Migration),
+            Box::new(m20261018_134500_add_library_entry_playback_mode::Migration),
+            Box::new(m20261019_101000_add_crossfade::Migration),
+            Box::new(m20261019_143000_move_track_files_to_media_store::Migration),
+            Box::new(m20261019_170000_create_card_mapping::Migration),
+            Box::new(m20261019_190000_add_button_pins::Migration),
+            Box::new(m20261020_080000_add_quiet_hours::Migration),
+            Box::new(m20261020_120000_add_loudness_gain::Migration),
+            Box::new(m20261021_090000_create_audio_config::Migration),
+            Box::new(m20261021_140000_add_podcast_fields::Migration),
+            Box::new(m20261022_090000_add_audio_cache::Migration),
+            Box::new(m20261023_090000_add_mono_output::Migration),
         ]
     }
 }
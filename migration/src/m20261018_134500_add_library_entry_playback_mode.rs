@@ -0,0 +1,23 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let conn = manager.get_connection();
+        conn.execute_unprepared("ALTER TABLE library_entry ADD COLUMN shuffle BOOLEAN NOT NULL DEFAULT false").await?;
+        conn.execute_unprepared("ALTER TABLE library_entry ADD COLUMN repeat_mode TEXT NOT NULL DEFAULT 'off'").await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let conn = manager.get_connection();
+        conn.execute_unprepared("ALTER TABLE library_entry DROP COLUMN shuffle").await?;
+        conn.execute_unprepared("ALTER TABLE library_entry DROP COLUMN repeat_mode").await?;
+
+        Ok(())
+    }
+}
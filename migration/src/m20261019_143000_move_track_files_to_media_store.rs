@@ -0,0 +1,94 @@
+use std::fs::{create_dir_all, read, write};
+use std::path::PathBuf;
+
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, Statement};
+use sha2::{Digest, Sha256};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Kept in here instead of using the media store of the database crate, so the migration does not change with it
+fn get_media_path(hash: &str) -> PathBuf {
+    let media_folder = std::env::var("MEDIA_FOLDER").unwrap_or("media".to_string());
+    PathBuf::from(media_folder).join(&hash[..2]).join(hash)
+}
+
+fn io_error(error: std::io::Error) -> DbErr {
+    DbErr::Custom(format!("Could not move track file: {}", error))
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let conn = manager.get_connection();
+        let backend = manager.get_database_backend();
+        conn.execute_unprepared("ALTER TABLE track_source ADD COLUMN file_hash TEXT").await?;
+
+        // Files are moved one by one, as loading all of them at once would not fit into memory
+        let rows = conn
+            .query_all(Statement::from_string(backend, "SELECT id FROM track_source WHERE file IS NOT NULL"))
+            .await?;
+        for row in rows {
+            let id = row.try_get::<i32>("", "id")?;
+            let file = conn
+                .query_one(Statement::from_sql_and_values(
+                    backend,
+                    "SELECT file FROM track_source WHERE id = ?",
+                    [id.into()],
+                ))
+                .await?
+                .ok_or(DbErr::RecordNotFound(format!("No track source with id {} found", id)))?
+                .try_get::<Vec<u8>>("", "file")?;
+
+            let hash = format!("{:x}", Sha256::digest(&file));
+            let path = get_media_path(&hash);
+            if !path.exists() {
+                create_dir_all(path.parent().unwrap()).map_err(io_error)?;
+                write(&path, &file).map_err(io_error)?;
+            }
+
+            conn.execute(Statement::from_sql_and_values(
+                backend,
+                "UPDATE track_source SET file_hash = ? WHERE id = ?",
+                [hash.into(), id.into()],
+            ))
+            .await?;
+        }
+
+        conn.execute_unprepared("ALTER TABLE track_source DROP COLUMN file").await?;
+        // Dropping the column only frees its pages within the database file, which would stay as large as before
+        conn.execute_unprepared("VACUUM").await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let conn = manager.get_connection();
+        let backend = manager.get_database_backend();
+        conn.execute_unprepared("ALTER TABLE track_source ADD COLUMN file BLOB").await?;
+
+        let rows = conn
+            .query_all(Statement::from_string(
+                backend,
+                "SELECT id, file_hash FROM track_source WHERE file_hash IS NOT NULL",
+            ))
+            .await?;
+        for row in rows {
+            let id = row.try_get::<i32>("", "id")?;
+            let hash = row.try_get::<String>("", "file_hash")?;
+            let file = read(get_media_path(&hash)).map_err(io_error)?;
+
+            conn.execute(Statement::from_sql_and_values(
+                backend,
+                "UPDATE track_source SET file = ? WHERE id = ?",
+                [file.into(), id.into()],
+            ))
+            .await?;
+        }
+
+        conn.execute_unprepared("ALTER TABLE track_source DROP COLUMN file_hash").await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,32 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let conn = manager.get_connection();
+
+        conn.execute_unprepared(
+            r#"
+                CREATE TABLE card_mapping (
+                    card_uid TEXT PRIMARY KEY NOT NULL,
+                    library_entry_id INTEGER,
+                    last_seen_at TEXT,
+                    FOREIGN KEY (library_entry_id) REFERENCES library_entry (id) ON DELETE SET NULL ON UPDATE CASCADE
+                )
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let conn = manager.get_connection();
+        conn.execute_unprepared("DROP TABLE card_mapping").await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const PIN_COLUMNS: [&str; 7] = [
+    "play_pause_pin",
+    "next_pin",
+    "prev_pin",
+    "volume_up_pin",
+    "volume_down_pin",
+    "rotary_clk_pin",
+    "rotary_dt_pin",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let conn = manager.get_connection();
+        // 0 means no button is connected
+        for column in PIN_COLUMNS {
+            conn.execute_unprepared(&format!("ALTER TABLE system_config ADD COLUMN {} INT NOT NULL DEFAULT 0", column))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let conn = manager.get_connection();
+        for column in PIN_COLUMNS {
+            conn.execute_unprepared(&format!("ALTER TABLE system_config DROP COLUMN {}", column)).await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const COLUMNS: [&str; 3] = [
+    "quiet_hours_start TEXT NOT NULL DEFAULT ''",
+    "quiet_hours_end TEXT NOT NULL DEFAULT ''",
+    "quiet_hours_volume INT NOT NULL DEFAULT 30",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let conn = manager.get_connection();
+        // Empty times disable the quiet hours
+        for column in COLUMNS {
+            conn.execute_unprepared(&format!("ALTER TABLE system_config ADD COLUMN {}", column)).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let conn = manager.get_connection();
+        for column in COLUMNS {
+            let name = column.split_whitespace().next().unwrap_or_default();
+            conn.execute_unprepared(&format!("ALTER TABLE system_config DROP COLUMN {}", name)).await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,34 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let conn = manager.get_connection();
+
+        conn.execute_unprepared(
+            r#"
+                CREATE TABLE audio_config (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+                    bass INTEGER NOT NULL DEFAULT 0,
+                    treble INTEGER NOT NULL DEFAULT 0,
+                    limiter BOOLEAN NOT NULL DEFAULT 0,
+                    limiter_threshold INTEGER NOT NULL DEFAULT -6
+                )
+            "#,
+        )
+        .await?;
+        conn.execute_unprepared("INSERT INTO audio_config (id) VALUES (1)").await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let conn = manager.get_connection();
+        conn.execute_unprepared("DROP TABLE audio_config").await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,29 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Publish date of an episode and number of episodes kept of a podcast feed
+const COLUMNS: [&str; 2] = ["published_at TEXT", "episode_limit INTEGER"];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let conn = manager.get_connection();
+        for column in COLUMNS {
+            conn.execute_unprepared(&format!("ALTER TABLE track_source ADD COLUMN {}", column)).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let conn = manager.get_connection();
+        for column in COLUMNS {
+            let name = column.split_whitespace().next().unwrap_or_default();
+            conn.execute_unprepared(&format!("ALTER TABLE track_source DROP COLUMN {}", name)).await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,32 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Table and definition of the added columns
+const COLUMNS: [(&str, &str); 2] = [
+    ("library_entry", "pinned BOOLEAN NOT NULL DEFAULT false"),
+    ("system_config", "audio_cache_size INTEGER NOT NULL DEFAULT 2048"),
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let conn = manager.get_connection();
+        for (table, column) in COLUMNS {
+            conn.execute_unprepared(&format!("ALTER TABLE {} ADD COLUMN {}", table, column)).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let conn = manager.get_connection();
+        for (table, column) in COLUMNS {
+            let name = column.split_whitespace().next().unwrap_or_default();
+            conn.execute_unprepared(&format!("ALTER TABLE {} DROP COLUMN {}", table, name)).await?;
+        }
+
+        Ok(())
+    }
+}
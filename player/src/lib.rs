@@ -3,5 +3,14 @@ extern crate core;
 mod player;
 
 pub use player::Player;
+pub use player::PlaybackMode;
+pub use player::PlaybackState;
+pub use player::PlayerEvent;
 pub use player::Queue;
-pub use player::Progress;
\ No newline at end of file
+pub use player::Progress;
+pub use player::SleepTimer;
+pub use player::ContextTrack;
+pub use player::resolve_context;
+pub use player::get_track_id;
+pub use player::QuietHours;
+pub use player::VolumePolicy;
\ No newline at end of file
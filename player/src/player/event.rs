@@ -0,0 +1,94 @@
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::broadcast;
+use tracing::debug;
+
+use database::model::library_entry::Model as LibraryEntry;
+
+use crate::player::play_target::Progress;
+use crate::player::sleep_timer::SleepTimer;
+
+// Events are dropped for receivers lagging behind more than this
+const EVENT_CAPACITY: usize = 64;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PlaybackState {
+    #[default]
+    Stopped,
+    // Play target prepares the track, e.g. connects to a stream or waits for spotify
+    Loading,
+    Playing,
+    Paused,
+    // Play target waits for more data of a remote track
+    Buffering,
+    // Track played until its end, the player moves on to the next one
+    Ended,
+}
+
+impl PlaybackState {
+    /// Whether the progress of the current track advances in this state
+    pub fn is_active(&self) -> bool {
+        matches!(self, PlaybackState::Playing)
+    }
+
+    /// Whether the player plays or is about to play, opposed to being paused or idle
+    pub fn is_playing(&self) -> bool {
+        matches!(self, PlaybackState::Loading | PlaybackState::Playing | PlaybackState::Buffering)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum PlayerEvent {
+    StateChanged(PlaybackState),
+    TrackChanged(Option<LibraryEntry>),
+    TrackEnded(LibraryEntry),
+    ProgressChanged(Progress),
+    // Library entry id of an internet radio and the title of the song it plays now
+    StreamTitleChanged(i32, String),
+    SleepTimerChanged(Option<SleepTimer>),
+    // Highest volume 0-1 the volume policy allows right now
+    VolumeLimitChanged(f64),
+}
+
+/// Holds the playback state and broadcasts every change of it to all subscribers
+#[derive(Clone)]
+pub struct PlayerEvents {
+    sender: broadcast::Sender<PlayerEvent>,
+    state: Arc<Mutex<PlaybackState>>,
+}
+
+impl PlayerEvents {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_CAPACITY);
+        Self {
+            sender,
+            state: Arc::new(Mutex::new(PlaybackState::default())),
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<PlayerEvent> {
+        self.sender.subscribe()
+    }
+
+    pub fn state(&self) -> PlaybackState {
+        *self.state.lock().unwrap()
+    }
+
+    /// Moves to the given state and emits it, if it differs from the current one
+    pub fn set_state(&self, state: PlaybackState) {
+        {
+            let mut current = self.state.lock().unwrap();
+            if *current == state {
+                return;
+            }
+            debug!("Playback state {:?} -> {:?}", *current, state);
+            *current = state;
+        }
+        self.emit(PlayerEvent::StateChanged(state));
+    }
+
+    pub fn emit(&self, event: PlayerEvent) {
+        // Sending only fails if nobody subscribed, which is fine
+        let _ = self.sender.send(event);
+    }
+}
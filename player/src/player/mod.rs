@@ -1,10 +1,17 @@
+pub use event::{PlaybackState, PlayerEvent};
 pub use play_target::Progress;
 pub use player::Player;
-pub use queue::Queue;
+pub use queue::{PlaybackMode, Queue};
+pub use sleep_timer::SleepTimer;
+pub use spotify_context::{get_track_id, resolve_context, ContextTrack};
+pub use volume::{QuietHours, VolumePolicy};
 
+mod event;
 mod play_target;
 mod player;
 mod queue;
+mod sleep_timer;
+mod spotify_context;
 mod spotify_manager;
 mod timer;
-
+mod volume;
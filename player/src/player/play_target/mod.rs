@@ -1,45 +1,362 @@
-use database::model::library_entry::Model as LibraryEntry;
+use database::model::library_entry::{Model as LibraryEntry, Variant};
+use database::model::system_config::NormalizationMode;
+use database::{DatabaseConnection, LibraryEntryRepository, SystemConfigRepository};
+use std::sync::{Arc, Mutex, MutexGuard};
 use std::time::Duration;
 use async_trait::async_trait;
+use kira::clock::{ClockHandle, ClockTime};
+use kira::sound::streaming::{StreamingSoundData, StreamingSoundHandle, StreamingSoundSettings};
+use kira::sound::{FromFileError, PlaybackState};
+use kira::Value::Fixed;
+use kira::{Decibels, StartTime, Tween, Value};
+use tracing::warn;
 
 mod spotify;
 mod local;
 mod remote;
+mod output;
 
 pub use spotify::SpotifyPlayTarget;
 pub use local::LocalPlayTarget;
 pub use remote::RemotePlayTarget;
+pub use output::AudioOutput;
+
+// Quiet tracks are raised at most this much, as more would clip them
+const MAX_GAIN: f32 = 6.0;
+// Resolution of the clocks which start the queued sounds
+const CLOCK_TICKS_PER_SECOND: f64 = 1000.0;
 
 #[derive(Clone, Debug)]
 pub struct Progress {
     pub position: Duration,
-    pub duration: Duration
+    // Not known for live streams, which play until they are stopped
+    pub duration: Option<Duration>
 }
 
 impl Default for Progress {
     fn default() -> Self {
         Self {
             position: Duration::from_secs(0),
-            duration: Duration::from_secs(1)
+            duration: Some(Duration::from_secs(0))
         }
     }
 }
 
 impl Progress {
+    /// Percent of the track which is played, always 0 for live streams
     pub fn as_f64(&self) -> f64 {
-        self.position.as_secs_f64() / self.duration.as_secs_f64() * 100.0
+        match self.duration {
+            Some(duration) if !duration.is_zero() => self.position.as_secs_f64() / duration.as_secs_f64() * 100.0,
+            _ => 0.0,
+        }
+    }
+
+    pub fn is_live(&self) -> bool {
+        self.duration.is_none()
+    }
+
+    /// Time until the track ends, if it ends on its own
+    pub fn remaining(&self) -> Option<Duration> {
+        self.duration.map(|duration| duration.saturating_sub(self.position))
+    }
+}
+
+/// Reads the configured crossfade on every use, so changes in the admin interface apply to the next track
+async fn get_crossfade(conn: &DatabaseConnection) -> Duration {
+    match SystemConfigRepository::get(conn).await {
+        Ok(Some(config)) => Duration::from_secs(config.crossfade.max(0) as u64),
+        Ok(None) => Duration::ZERO,
+        Err(error) => {
+            warn!("Could not load crossfade config: {}", error);
+            Duration::ZERO
+        }
+    }
+}
+
+/// Returns the decibels which normalize the loudness of the track, using the gain of the track or of its folder as
+/// configured. Tracks which are not analyzed yet play unchanged. Only local files are analyzed, so the album gain is
+/// not applied to streams and episodes in the folder.
+async fn get_gain(conn: &DatabaseConnection, track: &LibraryEntry) -> f32 {
+    let mode = match SystemConfigRepository::get(conn).await {
+        Ok(config) => config.map(|config| config.loudness_normalization).unwrap_or_default(),
+        Err(error) => {
+            warn!("Could not load loudness normalization config: {}", error);
+            NormalizationMode::default()
+        }
+    };
+    let track_gain = track.track_source.as_ref().and_then(|track_source| track_source.gain);
+
+    let album_id = track.parent_id.filter(|_| matches!(track.variant, Variant::File));
+
+    let gain = match (mode, album_id) {
+        (NormalizationMode::Off, _) => None,
+        (NormalizationMode::Album, Some(parent_id)) => match LibraryEntryRepository::get_flat(conn, parent_id).await {
+            Ok(parent) => parent.and_then(|parent| parent.album_gain).or(track_gain),
+            Err(error) => {
+                warn!("Could not load album gain of {}: {}", parent_id, error);
+                track_gain
+            }
+        },
+        _ => track_gain,
+    };
+    (gain.unwrap_or_default() as f32).min(MAX_GAIN)
+}
+
+/// Converts the volume 0-100 into decibels on a curve which sounds linear, raised or lowered by the gain
+fn percent_to_decibel(value: f64, gain: f32) -> Value<Decibels> {
+    Fixed(Decibels((30.0 * ((value / 100.0) * 0.99 + 0.01).log10()) as f32 + gain))
+}
+
+struct Sound {
+    handle: StreamingSoundHandle<FromFileError>,
+    library_entry_id: i32,
+    // Decibels normalizing the loudness
+    gain: f32,
+    // Not known for live streams
+    duration: Option<Duration>,
+    // Position of the sound at the first tick of the clock, negative if the sound started after it
+    clock_origin: f64,
+}
+
+struct Clock {
+    handle: ClockHandle,
+    first_tick: ClockTime,
+}
+
+impl Clock {
+    /// Starts a new clock. Commands to sounds sent right before are applied in the same audio frame, so the clock runs
+    /// in sync with them.
+    fn start(output: &mut AudioOutput) -> Result<Self, String> {
+        let mut handle = output.add_clock(CLOCK_TICKS_PER_SECOND)?;
+        let first_tick = handle.time();
+        handle.start();
+        Ok(Self { handle, first_tick })
+    }
+
+    /// Returns the time at which the sound reaches the position
+    fn time_at(&self, sound: &Sound, position: Duration) -> ClockTime {
+        let ticks = (position.as_secs_f64() - sound.clock_origin) * CLOCK_TICKS_PER_SECOND;
+        self.first_tick + ticks.max(0.0) as u64
+    }
+}
+
+#[derive(Default)]
+struct SoundsState {
+    playing: Option<Sound>,
+    queued: Option<Sound>,
+    clock: Option<Clock>,
+    volume: f64,
+}
+
+impl SoundsState {
+    /// Stops a queued sound which did not start yet and reverts the fade out of the playing sound
+    fn discard_queued(&mut self) {
+        if let Some(mut queued) = self.queued.take() {
+            queued.handle.stop(Tween::default());
+            if let Some(playing) = self.playing.as_mut() {
+                playing.handle.set_volume(percent_to_decibel(self.volume, playing.gain), Tween::default());
+            }
+        }
+    }
+
+    fn playing(&mut self, action: &str) -> Result<&mut Sound, String> {
+        self.playing.as_mut().ok_or(format!("No sound handle to {}", action))
+    }
+}
+
+/// Playing sound of the local or remote play target and the one queued to start when it ends. A kira clock runs along
+/// with the playing sound on the audio thread, so the queued sound starts and crossfades at the exact sample instead
+/// of when the progress was polled.
+#[derive(Clone)]
+struct Sounds {
+    output: Arc<Mutex<AudioOutput>>,
+    state: Arc<Mutex<SoundsState>>,
+}
+
+impl Sounds {
+    fn new(output: Arc<Mutex<AudioOutput>>, volume: f64) -> Self {
+        Self { output, state: Arc::new(Mutex::new(SoundsState { volume, ..Default::default() })) }
+    }
+
+    fn lock(&self) -> Result<MutexGuard<SoundsState>, String> {
+        self.state.lock().map_err(|e| format!("Could not lock sounds: {}", e))
+    }
+
+    fn lock_output(&self) -> Result<MutexGuard<AudioOutput>, String> {
+        self.output.lock().map_err(|e| format!("Could not lock audio output: {}", e))
+    }
+
+    /// Plays the sound right away instead of the queued one
+    fn play(
+        &self,
+        sound: StreamingSoundData<FromFileError>,
+        library_entry_id: i32,
+        gain: f32,
+        duration: Option<Duration>,
+    ) -> Result<(), String> {
+        let mut state = self.lock()?;
+        state.discard_queued();
+
+        let settings = StreamingSoundSettings::default().volume(percent_to_decibel(state.volume, gain));
+        let mut output = self.lock_output()?;
+        let handle = output.play(sound.with_settings(settings)).map_err(|e| format!("Could not play sound: {}", e))?;
+        state.clock = Some(Clock::start(&mut output)?);
+        state.playing = Some(Sound { handle, library_entry_id, gain, duration, clock_origin: 0.0 });
+        Ok(())
+    }
+
+    /// Schedules the sound to start when the playing one ends, which avoids any gap in between. With a crossfade, it
+    /// starts earlier while the playing one fades out.
+    fn queue(
+        &self,
+        sound: StreamingSoundData<FromFileError>,
+        library_entry_id: i32,
+        gain: f32,
+        duration: Option<Duration>,
+        crossfade: Duration,
+    ) -> Result<(), String> {
+        let mut guard = self.lock()?;
+        guard.discard_queued();
+
+        let state = &mut *guard;
+        let clock = state.clock.as_ref().ok_or("No clock to queue the sound on".to_string())?;
+        let playing = state.playing.as_mut().ok_or("No sound handle to queue after".to_string())?;
+        let end = playing.duration.ok_or("Live streams do not end, so no track can be queued after them".to_string())?;
+        let remaining = end.saturating_sub(Duration::from_secs_f64(playing.handle.position().max(0.0)));
+        let crossfade = crossfade.min(remaining);
+        let start_time = clock.time_at(playing, end - crossfade);
+
+        let mut settings = StreamingSoundSettings::default()
+            .volume(percent_to_decibel(state.volume, gain))
+            .start_time(StartTime::ClockTime(start_time));
+        if !crossfade.is_zero() {
+            settings = settings.fade_in_tween(Tween { duration: crossfade, ..Default::default() });
+            let fade = Tween {
+                start_time: StartTime::ClockTime(start_time),
+                duration: crossfade,
+                ..Default::default()
+            };
+            playing.handle.set_volume(Decibels::SILENCE, fade);
+        }
+
+        let handle = self
+            .lock_output()?
+            .play(sound.with_settings(settings))
+            .map_err(|e| format!("Could not queue sound: {}", e))?;
+        let clock_origin = -((start_time.ticks - clock.first_tick.ticks) as f64 / CLOCK_TICKS_PER_SECOND);
+        state.queued = Some(Sound { handle, library_entry_id, gain, duration, clock_origin });
+        Ok(())
+    }
+
+    fn discard_queued(&self) -> Result<(), String> {
+        self.lock()?.discard_queued();
+        Ok(())
+    }
+
+    /// Checks whether the playing sound ended. The queued sound is the playing one from then on.
+    fn is_finished(&self) -> Result<bool, String> {
+        let mut state = self.lock()?;
+        if !matches!(state.playing("get state")?.handle.state(), PlaybackState::Stopped) {
+            return Ok(false);
+        }
+
+        if let Some(queued) = state.queued.take() {
+            state.playing = Some(queued);
+        }
+        Ok(true)
+    }
+
+    /// Returns the library entry of the playing sound
+    fn playing_id(&self) -> Result<Option<i32>, String> {
+        Ok(self.lock()?.playing.as_ref().map(|sound| sound.library_entry_id))
+    }
+
+    fn pause(&self) -> Result<(), String> {
+        let mut state = self.lock()?;
+        state.discard_queued();
+        state.playing("pause")?.handle.pause(Tween::default());
+        Ok(())
+    }
+
+    /// Resumes the sound along with a new clock, as the old one kept running while the sound faded out on pause
+    fn resume(&self) -> Result<(), String> {
+        let mut state = self.lock()?;
+        let playing = state.playing("resume")?;
+        playing.handle.resume(Tween::default());
+        playing.clock_origin = playing.handle.position();
+        state.clock = Some(Clock::start(&mut *self.lock_output()?)?);
+        Ok(())
+    }
+
+    fn stop(&self) -> Result<(), String> {
+        let mut state = self.lock()?;
+        state.discard_queued();
+        state.playing("stop")?.handle.stop(Tween::default());
+        Ok(())
+    }
+
+    fn seek_to(&self, position: Duration) -> Result<(), String> {
+        let mut state = self.lock()?;
+        state.discard_queued();
+        let playing = state.playing("seek")?;
+        playing.handle.seek_to(position.as_secs_f64());
+        playing.clock_origin = position.as_secs_f64();
+        state.clock = Some(Clock::start(&mut *self.lock_output()?)?);
+        Ok(())
+    }
+
+    fn set_volume(&self, volume: f64) -> Result<(), String> {
+        let mut state = self.lock()?;
+        state.volume = volume;
+        let playing = state.playing("set value")?;
+        playing.handle.set_volume(percent_to_decibel(volume, playing.gain), Tween::default());
+        Ok(())
+    }
+
+    fn fade_out(&self, duration: Duration) -> Result<(), String> {
+        let mut state = self.lock()?;
+        state.playing("fade out")?.handle.set_volume(Decibels::SILENCE, Tween { duration, ..Default::default() });
+        Ok(())
+    }
+
+    fn cancel_fade(&self) -> Result<(), String> {
+        let mut state = self.lock()?;
+        let volume = state.volume;
+        // The sound may have ended already, then the next one starts with the set volume anyway
+        if let Some(playing) = state.playing.as_mut() {
+            playing.handle.set_volume(percent_to_decibel(volume, playing.gain), Tween::default());
+        }
+        Ok(())
+    }
+
+    fn get_progress(&self) -> Result<Progress, String> {
+        let mut state = self.lock()?;
+        let playing = state.playing("get progress")?;
+        Ok(Progress {
+            position: Duration::from_secs_f64(playing.handle.position()),
+            duration: playing.duration,
+        })
     }
 }
 
 #[async_trait]
 pub trait PlayTarget {
     async fn play(&mut self, track: &LibraryEntry) -> Result<(), String>;
+    /// Prepares the track to start right after the playing one ends. Only called if supports_queue returns true.
     async fn queue(&mut self, track: &LibraryEntry) -> Result<(), String>;
+    fn supports_queue(&self) -> bool;
+    /// Checks whether the playing track reached its end. If a track was queued, it is playing from now on.
+    async fn is_finished(&mut self, progress: &Progress) -> Result<bool, String>;
     async fn pause(&mut self) -> Result<(), String>;
     async fn resume(&mut self) -> Result<(), String>;
     async fn stop(&mut self) -> Result<(), String>;
     async fn seek_to(&mut self, position: Duration) -> Result<(), String>;
     async fn set_volume(&mut self, volume: f64) -> Result<(), String>;
+    /// Fades the volume out to silence. The set volume is kept and restored by cancel_fade.
+    async fn fade_out(&mut self, duration: Duration) -> Result<(), String>;
+    async fn cancel_fade(&mut self) -> Result<(), String>;
+    /// Removes the track handed to queue before, so the playing track is the last one
+    async fn unqueue(&mut self) -> Result<(), String>;
     async fn get_progress(&self) -> Result<Progress, String>;
     fn clone_box(&self) -> Box<dyn PlayTarget>;
 }
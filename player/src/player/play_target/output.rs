@@ -0,0 +1,119 @@
+use std::time::Duration;
+
+use kira::clock::{ClockHandle, ClockSpeed};
+use kira::effect::compressor::{CompressorBuilder, CompressorHandle};
+use kira::effect::eq_filter::{EqFilterBuilder, EqFilterHandle, EqFilterKind};
+use kira::sound::streaming::{StreamingSoundData, StreamingSoundHandle};
+use kira::sound::FromFileError;
+use kira::track::{TrackBuilder, TrackHandle};
+use kira::{AudioManager, AudioManagerSettings, Decibels, DefaultBackend, Mix, Tween};
+use tracing::info;
+
+use database::model::audio_config::Model as AudioConfig;
+use kira_remote_stream::ChannelMode;
+
+// Corner frequencies of the shelves, below and above the range small speakers reproduce well
+const BASS_FREQUENCY: f64 = 120.0;
+const TREBLE_FREQUENCY: f64 = 6000.0;
+const SHELF_Q: f64 = 0.7;
+const MAX_SHELF_GAIN: i32 = 12;
+// A compressor with a high ratio and fast attack, which only catches the peaks above the threshold
+const LIMITER_RATIO: f64 = 20.0;
+const LIMITER_ATTACK: Duration = Duration::from_millis(1);
+const LIMITER_RELEASE: Duration = Duration::from_millis(100);
+// Changes are faded in, so moving a slider in the admin interface does not crackle
+const CHANGE_DURATION: Duration = Duration::from_millis(200);
+
+/// Audio manager shared by the local and remote play target. Their sounds play on one mixer track, whose effects
+/// apply to every track and can be changed while playing.
+pub struct AudioOutput {
+    // Keeps the audio thread running as long as the output exists
+    manager: AudioManager<DefaultBackend>,
+    track: TrackHandle,
+    bass: EqFilterHandle,
+    treble: EqFilterHandle,
+    limiter: CompressorHandle,
+    config: AudioConfig,
+}
+
+impl AudioOutput {
+    pub fn new(config: AudioConfig) -> Result<Self, String> {
+        let mut manager = AudioManager::<DefaultBackend>::new(AudioManagerSettings::default())
+            .map_err(|e| format!("Could not create audio manager: {}", e))?;
+
+        let mut builder = TrackBuilder::new();
+        let bass = builder.add_effect(EqFilterBuilder::new(
+            EqFilterKind::LowShelf,
+            BASS_FREQUENCY,
+            get_shelf_gain(config.bass),
+            SHELF_Q,
+        ));
+        let treble = builder.add_effect(EqFilterBuilder::new(
+            EqFilterKind::HighShelf,
+            TREBLE_FREQUENCY,
+            get_shelf_gain(config.treble),
+            SHELF_Q,
+        ));
+        let limiter = builder.add_effect(
+            CompressorBuilder::new()
+                .threshold(config.limiter_threshold.min(0) as f64)
+                .ratio(LIMITER_RATIO)
+                .attack_duration(LIMITER_ATTACK)
+                .release_duration(LIMITER_RELEASE)
+                .mix(get_limiter_mix(config.limiter)),
+        );
+        let track = manager.add_sub_track(builder).map_err(|e| format!("Could not create mixer track: {}", e))?;
+        info!("Using audio effects {:?}", config);
+
+        Ok(Self { manager, track, bass, treble, limiter, config })
+    }
+
+    pub fn play(
+        &mut self,
+        sound: StreamingSoundData<FromFileError>,
+    ) -> Result<StreamingSoundHandle<FromFileError>, String> {
+        self.track.play(sound).map_err(|e| e.to_string())
+    }
+
+    /// Adds a stopped clock, which is removed again when its handle is dropped
+    pub fn add_clock(&mut self, ticks_per_second: f64) -> Result<ClockHandle, String> {
+        self.manager
+            .add_clock(ClockSpeed::TicksPerSecond(ticks_per_second))
+            .map_err(|e| format!("Could not add clock: {}", e))
+    }
+
+    /// Returns how the channels of the next loaded sound are mixed, which can not change while a sound plays
+    pub fn channel_mode(&self) -> ChannelMode {
+        match self.config.mono {
+            true => ChannelMode::Mono,
+            false => ChannelMode::Stereo,
+        }
+    }
+
+    /// Fades the effects to the new config without interrupting the playing sounds
+    pub fn set_config(&mut self, config: AudioConfig) {
+        if config == self.config {
+            return;
+        }
+        info!("Audio effects changed to {:?}", config);
+
+        let tween = Tween { duration: CHANGE_DURATION, ..Default::default() };
+        self.bass.set_gain(get_shelf_gain(config.bass), tween);
+        self.treble.set_gain(get_shelf_gain(config.treble), tween);
+        self.limiter.set_threshold(config.limiter_threshold.min(0) as f64, tween);
+        self.limiter.set_mix(get_limiter_mix(config.limiter), tween);
+        self.config = config;
+    }
+}
+
+fn get_shelf_gain(gain: i32) -> Decibels {
+    Decibels(gain.clamp(-MAX_SHELF_GAIN, MAX_SHELF_GAIN) as f32)
+}
+
+// A disabled limiter stays in the chain with a dry mix, so it can be enabled while playing
+fn get_limiter_mix(enabled: bool) -> Mix {
+    match enabled {
+        true => Mix::WET,
+        false => Mix::DRY,
+    }
+}
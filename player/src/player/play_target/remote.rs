@@ -1,43 +1,47 @@
-use crate::player::play_target::{PlayTarget, Progress};
+use crate::player::event::{self, PlayerEvent, PlayerEvents};
+use crate::player::play_target::{get_crossfade, get_gain, AudioOutput, PlayTarget, Progress, Sounds};
 use async_trait::async_trait;
-use database::model::library_entry::Model as LibraryEntry;
-use database::DatabaseConnection;
-use kira::sound::streaming::{StreamingSoundData, StreamingSoundHandle, StreamingSoundSettings};
+use database::model::library_entry::{Model as LibraryEntry, Variant};
+use database::{AudioCache, DatabaseConnection, SystemConfigRepository};
+use kira::sound::streaming::StreamingSoundData;
 use kira::sound::FromFileError;
-use kira::Value::Fixed;
-use kira::{AudioManager, AudioManagerSettings, Decibels, DefaultBackend, Tween, Value};
-use kira_remote_stream::RemoteStreamDecoder;
+use kira_remote_stream::{OnStreamState, RemoteStreamDecoder, StreamCallbacks, StreamState, SymphoniaDecoder};
+use std::collections::HashSet;
+use std::fs::File;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
+use tracing::{error, warn};
 
 #[derive(Clone)]
 pub struct RemotePlayTarget {
-    manager: Arc<Mutex<AudioManager<DefaultBackend>>>,
-    sound_handle: Arc<Mutex<Option<StreamingSoundHandle<FromFileError>>>>,
-    volume: f64,
-    duration: Duration,
+    conn: DatabaseConnection,
+    output: Arc<Mutex<AudioOutput>>,
+    // Passes the song titles of internet radios and interruptions of the connection on
+    events: PlayerEvents,
+    // Library entry of the sound which plays right now, only its stream changes the playback state
+    playing_id: Arc<Mutex<Option<i32>>>,
+    // Urls which are downloaded into the audio cache right now
+    caching_urls: Arc<Mutex<HashSet<String>>>,
+    sounds: Sounds,
 }
 
 impl RemotePlayTarget {
-    pub fn new(_conn: DatabaseConnection, volume: f64) -> Self {
+    pub fn new(conn: DatabaseConnection, output: Arc<Mutex<AudioOutput>>, events: PlayerEvents, volume: f64) -> Self {
         Self {
-            manager: Arc::new(Mutex::new(
-                AudioManager::<DefaultBackend>::new(AudioManagerSettings::default()).expect("manager to be created"),
-            )),
-            sound_handle: Arc::new(Mutex::new(None)),
-            volume,
-            duration: Duration::default(),
+            conn,
+            sounds: Sounds::new(output.clone(), volume),
+            output,
+            events,
+            playing_id: Arc::new(Mutex::new(None)),
+            caching_urls: Arc::new(Mutex::new(HashSet::new())),
         }
     }
-}
-
-fn percent_to_decibel(value: f64) -> Value<Decibels> {
-    Fixed(Decibels((30.0 * ((value / 100.0) * 0.99 + 0.01).log10()) as f32))
-}
 
-#[async_trait]
-impl PlayTarget for RemotePlayTarget {
-    async fn play(&mut self, track: &LibraryEntry) -> Result<(), String> {
+    /// Returns the sound with its duration, which is not known for live streams
+    async fn load_sound(
+        &self,
+        track: &LibraryEntry,
+    ) -> Result<(StreamingSoundData<FromFileError>, Option<Duration>), String> {
         let url = track
             .track_source
             .as_ref()
@@ -46,90 +50,155 @@ impl PlayTarget for RemotePlayTarget {
             .as_ref()
             .ok_or("The url is not set on track source".to_string())?;
 
-        let decoder = RemoteStreamDecoder::from_url(url.to_string()).await?;
-        let settings = StreamingSoundSettings::default().volume(percent_to_decibel(self.volume));
-        let sound = StreamingSoundData::from_decoder(decoder).with_settings(settings);
-        self.duration = sound.duration();
+        let channel_mode =
+            self.output.lock().map_err(|e| format!("Could not lock audio output: {}", e))?.channel_mode();
+        if let Some(path) = AudioCache::get(url) {
+            let file = File::open(&path).map_err(|error| format!("Could not open cached file: {}", error))?;
+            let decoder = SymphoniaDecoder::new(Box::new(file))
+                .map_err(|error| format!("Could not load cached file: {}", error))?
+                .with_channel_mode(channel_mode);
+            let duration = decoder.duration();
+            return Ok((StreamingSoundData::from_decoder(decoder), duration));
+        }
+        // Radios have no end, so they are neither cached nor fetched a second time
+        if !matches!(track.variant, Variant::Stream) {
+            self.cache(url.to_string());
+        }
 
-        let handle = self
-            .manager
-            .lock()
-            .map_err(|e| format!("Could not lock audio manager: {}", e))?
-            .play(sound)
-            .map_err(|e| format!("Could not play sound: {}", e))?;
-        self.sound_handle = Arc::new(Mutex::new(Some(handle)));
+        let (events, library_entry_id) = (self.events.clone(), track.id);
+        let callbacks = StreamCallbacks {
+            on_stream_title: match track.variant {
+                Variant::Stream => {
+                    Some(Arc::new(move |title| events.emit(PlayerEvent::StreamTitleChanged(library_entry_id, title))))
+                }
+                _ => None,
+            },
+            on_stream_state: Some(self.on_stream_state(track.id)),
+        };
+        let decoder = RemoteStreamDecoder::from_url_with_callbacks(url.to_string(), callbacks).await?;
+        let duration = decoder.duration();
+        Ok((StreamingSoundData::from_decoder(decoder.with_channel_mode(channel_mode)), duration))
+    }
 
-        Ok(())
+    /// Downloads the url into the audio cache in the background, so it plays from disk next time. The offline
+    /// downloader removes the least recently used files once the cache grew beyond its size.
+    fn cache(&self, url: String) {
+        match self.caching_urls.lock() {
+            Ok(mut caching_urls) if caching_urls.insert(url.clone()) => {}
+            _ => return,
+        }
+
+        let (conn, caching_urls) = (self.conn.clone(), self.caching_urls.clone());
+        tokio::spawn(async move {
+            match SystemConfigRepository::get_audio_cache_size(&conn).await {
+                Ok(size_limit) => {
+                    let task_url = url.clone();
+                    match tokio::task::spawn_blocking(move || AudioCache::store(&task_url, size_limit)).await {
+                        Ok(Err(error)) => warn!("Could not cache remote track: {}", error),
+                        Err(error) => error!("Audio cache task failed: {}", error),
+                        _ => {}
+                    }
+                }
+                Err(error) => error!("Could not get audio cache size: {}", error),
+            }
+            if let Ok(mut caching_urls) = caching_urls.lock() {
+                caching_urls.remove(&url);
+            }
+        });
+    }
+
+    /// Pauses the progress while the playing stream reconnects. A failed stream stops its sound, so the player
+    /// moves on to the next track once it is playing again.
+    fn on_stream_state(&self, library_entry_id: i32) -> OnStreamState {
+        let (events, playing_id) = (self.events.clone(), self.playing_id.clone());
+        Arc::new(move |stream_state| {
+            if *playing_id.lock().unwrap() != Some(library_entry_id) {
+                return;
+            }
+            match (stream_state, events.state()) {
+                (StreamState::Buffering, event::PlaybackState::Playing) => {
+                    events.set_state(event::PlaybackState::Buffering)
+                }
+                (StreamState::Recovered | StreamState::Failed, event::PlaybackState::Buffering) => {
+                    events.set_state(event::PlaybackState::Playing)
+                }
+                _ => {}
+            }
+        })
     }
 
-    async fn queue(&mut self, _track: &LibraryEntry) -> Result<(), String> {
-        // Do nothing. Local playing through Kira does not support queueing, and is fast enough.
+    fn set_playing_id(&self, library_entry_id: Option<i32>) -> Result<(), String> {
+        *self.playing_id.lock().map_err(|e| format!("Could not lock playing id: {}", e))? = library_entry_id;
         Ok(())
     }
+}
+
+#[async_trait]
+impl PlayTarget for RemotePlayTarget {
+    async fn play(&mut self, track: &LibraryEntry) -> Result<(), String> {
+        let gain = get_gain(&self.conn, track).await;
+        self.set_playing_id(Some(track.id))?;
+        let (sound, duration) = self.load_sound(track).await?;
+        self.sounds.play(sound, track.id, gain, duration)
+    }
+
+    /// Schedules the track to start on the audio thread when the playing one ends, which avoids any gap in between.
+    /// With a crossfade configured, the next track starts earlier while the playing one fades out.
+    async fn queue(&mut self, track: &LibraryEntry) -> Result<(), String> {
+        self.sounds.discard_queued()?;
+
+        let (sound, duration) = self.load_sound(track).await?;
+        let gain = get_gain(&self.conn, track).await;
+        let crossfade = get_crossfade(&self.conn).await;
+        self.sounds.queue(sound, track.id, gain, duration, crossfade)
+    }
+
+    fn supports_queue(&self) -> bool {
+        true
+    }
+
+    async fn is_finished(&mut self, _progress: &Progress) -> Result<bool, String> {
+        let finished = self.sounds.is_finished()?;
+        if finished {
+            self.set_playing_id(self.sounds.playing_id()?)?;
+        }
+        Ok(finished)
+    }
 
     async fn pause(&mut self) -> Result<(), String> {
-        self.sound_handle
-            .lock()
-            .map_err(|e| format!("Could not lock sound handle: {}", e))?
-            .as_mut()
-            .ok_or("No sound handle to pause".to_string())?
-            .pause(Tween::default());
-        Ok(())
+        self.sounds.pause()
     }
 
     async fn resume(&mut self) -> Result<(), String> {
-        self.sound_handle
-            .lock()
-            .map_err(|e| format!("Could not lock sound handle: {}", e))?
-            .as_mut()
-            .ok_or("No sound handle to resume".to_string())?
-            .resume(Tween::default());
-        Ok(())
+        self.sounds.resume()
     }
 
     async fn stop(&mut self) -> Result<(), String> {
-        self.sound_handle
-            .lock()
-            .map_err(|e| format!("Could not lock sound handle: {}", e))?
-            .as_mut()
-            .ok_or("No sound handle to stop".to_string())?
-            .stop(Tween::default());
-        Ok(())
+        self.sounds.stop()
     }
 
     async fn seek_to(&mut self, position: Duration) -> Result<(), String> {
-        self.sound_handle
-            .lock()
-            .map_err(|e| format!("Could not lock sound handle: {}", e))?
-            .as_mut()
-            .ok_or("No sound handle to pause".to_string())?
-            .seek_to(position.as_secs_f64());
-        Ok(())
+        self.sounds.seek_to(position)
     }
 
     async fn set_volume(&mut self, volume: f64) -> Result<(), String> {
-        self.sound_handle
-            .lock()
-            .map_err(|e| format!("Could not lock sound handle: {}", e))?
-            .as_mut()
-            .ok_or("No sound handle to set value".to_string())?
-            .set_volume(percent_to_decibel(volume), Tween::default());
-        Ok(())
+        self.sounds.set_volume(volume)
     }
 
-    async fn get_progress(&self) -> Result<Progress, String> {
-        let progress = self
-            .sound_handle
-            .lock()
-            .map_err(|e| format!("Could not lock sound handle: {}", e))?
-            .as_ref()
-            .ok_or("No sound handle to get progress".to_string())?
-            .position();
+    async fn fade_out(&mut self, duration: Duration) -> Result<(), String> {
+        self.sounds.fade_out(duration)
+    }
 
-        Ok(Progress {
-            position: Duration::from_secs_f64(progress),
-            duration: self.duration,
-        })
+    async fn cancel_fade(&mut self) -> Result<(), String> {
+        self.sounds.cancel_fade()
+    }
+
+    async fn unqueue(&mut self) -> Result<(), String> {
+        self.sounds.discard_queued()
+    }
+
+    async fn get_progress(&self) -> Result<Progress, String> {
+        self.sounds.get_progress()
     }
 
     fn clone_box(&self) -> Box<dyn PlayTarget> {
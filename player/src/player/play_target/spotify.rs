@@ -1,29 +1,106 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use async_trait::async_trait;
 use rspotify::model::{AdditionalType, AlbumId, ArtistId, EpisodeId, IdError, PlayableItem, PlayContextId, PlaylistId, ShowId, TrackId};
 use rspotify::prelude::{OAuthClient, PlayableId};
-use tracing::{debug, error};
+use tracing::{debug, error, warn};
 
+use database::fetch_image;
 use database::model::library_entry::{Model as LibraryEntry, Variant};
 
 use crate::player::play_target::{PlayTarget, Progress};
+use crate::player::spotify_context::resolve_context;
 use crate::player::spotify_manager::SpotifyManager;
 
+// Spotify has no volume transitions, so fading sets the volume in steps of this length
+const FADE_STEP: Duration = Duration::from_secs(5);
+
 #[derive(Clone)]
 pub struct SpotifyPlayTarget {
     manager: SpotifyManager,
     device_id: Option<String>,
+    volume: f64,
+    // Increased on every volume change, which stops a running fade
+    fade_id: Arc<AtomicU64>,
+    // Covers of the resolved context tracks by their spotify id, shared with the clones resolving them
+    cover_urls: Arc<Mutex<HashMap<String, String>>>,
 }
 
 impl SpotifyPlayTarget {
-    pub async fn new(manager: SpotifyManager, _volume: f64) -> Self {
+    pub async fn new(manager: SpotifyManager, volume: f64) -> Self {
         Self {
             manager,
             device_id: None,
+            volume,
+            fade_id: Arc::new(AtomicU64::new(0)),
+            cover_urls: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
+    /// Returns the tracks of an album, playlist or show, which are played one by one instead of it.
+    /// Returns none for other entries. Blocks on the web API, so it is meant to run on a blocking thread.
+    pub fn get_context_tracks(&self, context: &LibraryEntry) -> Result<Option<Vec<LibraryEntry>>, String> {
+        let tracks = match resolve_context(&self.manager.client, context)? {
+            Some(tracks) => tracks,
+            None => return Ok(None),
+        };
+
+        let mut cover_urls = self.cover_urls.lock().map_err(|e| format!("Could not lock cover urls: {}", e))?;
+        let tracks = tracks.into_iter().map(|track| {
+            let spotify_id = track.library_entry.track_source.as_ref().and_then(|source| source.spotify_id.clone());
+            if let (Some(spotify_id), Some(cover_url)) = (spotify_id, track.cover_url) {
+                cover_urls.insert(spotify_id, cover_url);
+            }
+            track.library_entry
+        });
+        Ok(Some(tracks.collect()))
+    }
+
+    /// Downloads the cover of a context track, if it has another one than its context. Blocks like
+    /// get_context_tracks.
+    pub fn get_cover(&self, track: &LibraryEntry) -> Option<Vec<u8>> {
+        let spotify_id = track.track_source.as_ref()?.spotify_id.as_ref()?;
+        let cover_url = self.cover_urls.lock().ok()?.get(spotify_id)?.clone();
+        fetch_image(&cover_url)
+    }
+
+    fn fetch_progress(&self) -> Result<Progress, String> {
+        let playback = self.manager.client.current_playback(None, None::<Vec<&AdditionalType>>)
+            .map_err(|e| format!("Failed to get current playback position: {}", e))?
+            .ok_or("No current playback returned".to_string())?;
+
+        let progress = playback
+            .progress
+            .ok_or("No progress reported".to_string())?
+            .to_std()
+            .map_err(|e| format!("Failed to convert chrono duration: {}", e))?;
+
+        let duration = playback.item
+            .ok_or("No item reported".to_string())
+            .map(|item| match item {
+                PlayableItem::Track(track) => track.duration,
+                PlayableItem::Episode(episode) => episode.duration,
+            })?
+            .to_std()
+            .map_err(|e| format!("Failed to convert chrono duration: {}", e))?;
+
+        Ok(Progress {
+            position: progress,
+            duration: Some(duration),
+        })
+    }
+
+    fn send_volume(&mut self, volume: f64) -> Result<(), String> {
+        let device_id = self.get_device_id()?;
+        let device_id = Some(device_id.as_str());
+
+        self.manager.client.volume((volume * 100.0) as u8, device_id)
+            .map_err(|e| format!("Failed to set volume: {}", e))
+    }
+
     fn get_play_id(&self, track: &LibraryEntry) -> Result<SpotifyId, String> {
         if !matches!(track.variant, Variant::Spotify) {
             error!("Attempted to play non-Spotify track on Spotify play target: {}", track.id);
@@ -86,6 +163,15 @@ impl PlayTarget for SpotifyPlayTarget {
         result.map_err(|e| format!("Failed to play track: {}", e))
     }
 
+    // Queued items can not be removed from the spotify queue again, so skipping would play outdated tracks
+    fn supports_queue(&self) -> bool {
+        false
+    }
+
+    async fn is_finished(&mut self, progress: &Progress) -> Result<bool, String> {
+        Ok(progress.remaining().is_some_and(|remaining| remaining.is_zero()))
+    }
+
     async fn pause(&mut self) -> Result<(), String> {
         let device_id = self.get_device_id()?;
         let device_id = Some(device_id.as_str());
@@ -113,37 +199,48 @@ impl PlayTarget for SpotifyPlayTarget {
     }
 
     async fn set_volume(&mut self, volume: f64) -> Result<(), String> {
-        let device_id = self.get_device_id()?;
-        let device_id = Some(device_id.as_str());
-
-        self.manager.client.volume((volume * 100.0) as u8, device_id)
-            .map_err(|e| format!("Failed to set volume: {}", e))
+        self.volume = volume;
+        self.fade_id.fetch_add(1, Ordering::SeqCst);
+        self.send_volume(volume)
     }
 
-    async fn get_progress(&self) -> Result<Progress, String> {
-        let playback = self.manager.client.current_playback(None, None::<Vec<&AdditionalType>>)
-            .map_err(|e| format!("Failed to get current playback position: {}", e))?
-            .ok_or("No current playback returned".to_string())?;
+    async fn fade_out(&mut self, duration: Duration) -> Result<(), String> {
+        let fade_id = self.fade_id.fetch_add(1, Ordering::SeqCst) + 1;
+        let steps = (duration.as_secs() / FADE_STEP.as_secs()).max(1);
+        let mut target = self.clone();
+
+        tokio::spawn(async move {
+            for step in 1..=steps {
+                tokio::time::sleep(duration / steps as u32).await;
+                if target.fade_id.load(Ordering::SeqCst) != fade_id {
+                    return;
+                }
+                let volume = target.volume * (1.0 - step as f64 / steps as f64);
+                if let Err(error) = target.send_volume(volume) {
+                    warn!("Could not fade out spotify: {}", error);
+                }
+            }
+        });
+        Ok(())
+    }
 
-        let progress = playback
-            .progress
-            .ok_or("No progress reported".to_string())?
-            .to_std()
-            .map_err(|e| format!("Failed to convert chrono duration: {}", e))?;
+    async fn cancel_fade(&mut self) -> Result<(), String> {
+        self.fade_id.fetch_add(1, Ordering::SeqCst);
+        let volume = self.volume;
+        self.send_volume(volume)
+    }
 
-        let duration = playback.item
-            .ok_or("No item reported".to_string())
-            .map(|item| match item {
-                PlayableItem::Track(track) => track.duration,
-                PlayableItem::Episode(episode) => episode.duration,
-            })?
-            .to_std()
-            .map_err(|e| format!("Failed to convert chrono duration: {}", e))?;
+    // Nothing is ever queued, see supports_queue
+    async fn unqueue(&mut self) -> Result<(), String> {
+        Ok(())
+    }
 
-        Ok(Progress {
-            position: progress,
-            duration,
-        })
+    async fn get_progress(&self) -> Result<Progress, String> {
+        // The web API is called blocking, so it must not hold up an async worker
+        let target = self.clone();
+        tokio::task::spawn_blocking(move || target.fetch_progress())
+            .await
+            .map_err(|e| format!("Progress task failed: {}", e))?
     }
 
     fn clone_box(&self) -> Box<dyn PlayTarget> {
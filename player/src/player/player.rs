@@ -1,79 +1,133 @@
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use tokio::sync::Mutex;
+use chrono::Local;
+use tokio::sync::{broadcast, Mutex};
 use tokio::time::sleep;
 use tracing::log::info;
+use tracing::warn;
 
-use database::model::library_entry::Variant;
-use database::{model::library_entry::Model as LibraryEntry, DatabaseConnection};
+use database::model::library_entry::{RepeatMode, Variant};
+use database::{
+    model::library_entry::Model as LibraryEntry, AudioConfigRepository, DatabaseConnection, PlaybackPositionRepository,
+};
 
-use crate::player::play_target::{LocalPlayTarget, PlayTarget, Progress, RemotePlayTarget, SpotifyPlayTarget};
-use crate::player::queue::Queue;
+use crate::player::event::{PlaybackState, PlayerEvent, PlayerEvents};
+use crate::player::play_target::{
+    AudioOutput, LocalPlayTarget, PlayTarget, Progress, RemotePlayTarget, SpotifyPlayTarget,
+};
+use crate::player::queue::{PlaybackMode, Queue};
+use crate::player::sleep_timer::SleepTimerState;
 use crate::player::spotify_manager::SpotifyManager;
 use crate::player::timer::PlayerTimer;
+use crate::player::volume::VolumePolicy;
+
+// Positions this close to the start or end of a track are not worth resuming
+const RESUME_THRESHOLD: Duration = Duration::from_secs(5);
+
+/// Streams may be live radios which can not be seeked, so their position is not worth to remember.
+/// The tracks of a Spotify album, playlist or show share its id, so their position would resume the wrong one.
+pub(super) fn is_resumable(library_entry: &LibraryEntry) -> bool {
+    !matches!(library_entry.variant, Variant::Stream) && library_entry.duration_ms.is_none()
+}
 
 #[derive(Clone)]
 pub(super) struct Track {
     pub(super) library_entry: LibraryEntry,
     pub(super) target: Arc<Mutex<dyn PlayTarget + Send + 'static>>,
-    pub(super) playing: bool,
     pub(super) progress: Progress,
+    // Time at which the position of the progress was current
+    pub(super) progress_at: Instant,
+    // Track which was handed to the play target to start right after this one
+    pub(super) queued: Option<LibraryEntry>,
+}
+
+impl Track {
+    pub(super) fn set_progress(&mut self, progress: Progress) {
+        self.progress = progress;
+        self.progress_at = Instant::now();
+    }
+
+    /// Advances the position by the time passed since it was current, as the play targets are only asked for it every
+    /// few seconds. The position stays while the track does not play.
+    pub(super) fn advance_progress(&mut self, playing: bool) {
+        let now = Instant::now();
+        if playing {
+            let position = self.progress.position + (now - self.progress_at);
+            self.progress.position = self.progress.duration.map_or(position, |duration| position.min(duration));
+        }
+        self.progress_at = now;
+    }
 }
 
 #[derive(Clone)]
-pub struct Player<P, T, E>
-where
-    P: Fn(Progress) + 'static + Sync + Send,
-    T: Fn(Option<LibraryEntry>) + 'static + Sync + Send,
-    E: Fn(LibraryEntry) + 'static + Sync + Send,
-{
+pub struct Player {
+    pub(super) conn: DatabaseConnection,
     spotify: Arc<Mutex<SpotifyPlayTarget>>,
-    local: Arc<Mutex<LocalPlayTarget>>,
-    remote: Arc<Mutex<RemotePlayTarget>>,
+    pub(super) local: Arc<Mutex<LocalPlayTarget>>,
+    pub(super) remote: Arc<Mutex<RemotePlayTarget>>,
+    // Shared by the local and remote play target, which run on the audio thread of kira
+    output: Arc<std::sync::Mutex<AudioOutput>>,
     queue: Queue,
+    // Only mutated in place, as the timers keep a reference to it
     pub(super) current_track: Arc<Mutex<Option<Track>>>,
-    pub(super) notify_progress: Option<P>,
-    pub(super) notify_track_change: Option<T>,
-    pub(super) notify_track_end: Option<E>,
+    pub(super) events: PlayerEvents,
+    pub(super) sleep_timer: SleepTimerState,
+    // Set volume 0-1, the play targets use it up to the limit of the volume policy
+    pub(super) volume: f64,
+    pub(super) volume_policy: VolumePolicy,
+    pub(super) volume_limit: f64,
 }
 
-impl<P, T, E> Player<P, T, E>
-where
-    P: Fn(Progress) + 'static + Sync + Send,
-    T: Fn(Option<LibraryEntry>) + 'static + Sync + Send,
-    E: Fn(LibraryEntry) + 'static + Sync + Send,
-{
+impl Player {
     pub async fn new(conn: DatabaseConnection, volume: f64) -> Arc<Mutex<Self>> {
         let spotify_manager = SpotifyManager::new(&conn).await;
+        let volume_policy = VolumePolicy::load(&conn).await.unwrap_or_default();
+        let volume_limit = volume_policy.get_limit(Local::now().time());
+        let applied_volume = volume.min(volume_limit);
+        let audio_config = AudioConfigRepository::get(&conn).await.unwrap_or_else(|error| {
+            warn!("Could not load audio config: {}", error);
+            Default::default()
+        });
+        let output = AudioOutput::new(audio_config).expect("audio output to be created");
+        let output = Arc::new(std::sync::Mutex::new(output));
+        let events = PlayerEvents::new();
 
         let player = Arc::new(Mutex::new(Self {
-            spotify: Arc::new(Mutex::new(SpotifyPlayTarget::new(spotify_manager, volume).await)),
-            local: Arc::new(Mutex::new(LocalPlayTarget::new(conn.clone(), volume).await)),
-            remote: Arc::new(Mutex::new(RemotePlayTarget::new(conn.clone(), volume))),
+            conn: conn.clone(),
+            spotify: Arc::new(Mutex::new(SpotifyPlayTarget::new(spotify_manager, applied_volume).await)),
+            local: Arc::new(Mutex::new(LocalPlayTarget::new(conn.clone(), output.clone(), applied_volume).await)),
+            remote: Arc::new(Mutex::new(RemotePlayTarget::new(
+                conn.clone(),
+                output.clone(),
+                events.clone(),
+                applied_volume,
+            ))),
+            output,
             queue: Queue::new(),
             current_track: Arc::new(Mutex::new(None)),
-            notify_progress: Default::default(),
-            notify_track_change: Default::default(),
-            notify_track_end: Default::default(),
+            events,
+            sleep_timer: SleepTimerState::default(),
+            volume,
+            volume_policy,
+            volume_limit,
         }));
 
         PlayerTimer::start_progress_timer(player.clone());
         PlayerTimer::start_correct_progress_timer(player.clone());
+        PlayerTimer::start_sleep_timer(player.clone());
+        PlayerTimer::start_volume_timer(player.clone());
 
         player
     }
 
-    pub fn connect_progress_changed(&mut self, notify_progress: P) {
-        self.notify_progress = Some(notify_progress);
+    /// Receives all changes of the playback state, the playing track and its progress
+    pub fn subscribe(&self) -> broadcast::Receiver<PlayerEvent> {
+        self.events.subscribe()
     }
 
-    pub fn connect_track_changed(&mut self, notify_track_change: T) {
-        self.notify_track_change = Some(notify_track_change);
-    }
-
-    pub fn connect_track_ended(&mut self, notify_track_end: E) {
-        self.notify_track_end = Some(notify_track_end);
+    pub fn get_state(&self) -> PlaybackState {
+        self.events.state()
     }
 
     pub async fn play_queue(&mut self, queue: Queue) -> Result<Option<LibraryEntry>, String> {
@@ -81,40 +135,126 @@ where
         self.play_next_track().await
     }
 
+    /// Returns the tracks of the queue in the order they are played
+    pub fn get_queue(&self) -> Vec<LibraryEntry> {
+        self.queue.get_tracks()
+    }
+
+    /// Returns the progress of the current track, if any is playing
+    pub async fn get_progress(&self) -> Option<Progress> {
+        self.current_track.lock().await.as_ref().map(|track| track.progress.clone())
+    }
+
+    pub fn get_playback_mode(&self) -> PlaybackMode {
+        self.queue.mode()
+    }
+
+    pub fn set_playback_mode(&mut self, mode: PlaybackMode) {
+        self.queue.set_mode(mode);
+    }
+
     fn get_play_target(&mut self, track: &LibraryEntry) -> Option<Arc<Mutex<dyn PlayTarget + Send>>> {
         match track.variant {
-            Variant::Folder => None,
-            Variant::Stream => Some(self.remote.clone()),
+            Variant::Folder | Variant::Podcast => None,
+            Variant::Stream | Variant::Episode => Some(self.remote.clone()),
             Variant::File => Some(self.local.clone()),
             Variant::Spotify => Some(self.spotify.clone()),
         }
     }
 
     async fn play_track(&mut self, library_entry: LibraryEntry) -> Result<Option<LibraryEntry>, String> {
+        // Spotify keeps the faded volume otherwise. The sleep timer fades the new track again if needed.
+        if let Err(error) = self.cancel_fade().await {
+            warn!("Could not restore volume: {}", error);
+        }
+        *self.current_track.lock().await = None;
+        self.events.set_state(PlaybackState::Loading);
+
+        let library_entry = match library_entry.variant {
+            Variant::Spotify => self.resolve_spotify_track(library_entry).await,
+            _ => library_entry,
+        };
         let mut new_track = self.get_play_target(&library_entry).map(|target| Track {
             library_entry: library_entry.clone(),
             target,
-            playing: true,
             progress: Progress::default(),
+            progress_at: Instant::now(),
+            queued: None,
         });
 
         if let Some(new_track) = new_track.as_mut() {
-            new_track.target.lock().await.play(&new_track.library_entry).await?;
-            sleep(Duration::from_secs(1)).await; // Let spotify api catch up with playing
-            new_track.progress = new_track.target.lock().await.get_progress().await?;
-            new_track.progress.position = Duration::from_secs(0); // Spotify returns weird position
+            if let Err(error) = self.start_track(new_track).await {
+                self.events.set_state(PlaybackState::Stopped);
+                return Err(error);
+            }
         }
 
-        self.current_track = Arc::new(Mutex::new(new_track));
+        let progress = new_track.as_ref().map(|track| track.progress.clone()).unwrap_or_default();
+        let state = match new_track {
+            Some(_) => PlaybackState::Playing,
+            None => PlaybackState::Stopped,
+        };
+        *self.current_track.lock().await = new_track;
+
+        self.events.set_state(state);
+        self.events.emit(PlayerEvent::TrackChanged(Some(library_entry.clone())));
+        self.events.emit(PlayerEvent::ProgressChanged(progress));
 
-        if let Some(on_track_change) = self.notify_track_change.as_ref() {
-            on_track_change(Some(library_entry.clone()));
+        Ok(Some(library_entry.clone()))
+    }
+
+    /// Replaces a Spotify album, playlist or show in the queue by its tracks, so they are skipped and marked as played
+    /// one by one. Spotify plays it as a whole if its tracks can not be resolved. The given entry has to be the
+    /// current one of the queue.
+    async fn resolve_spotify_track(&mut self, library_entry: LibraryEntry) -> LibraryEntry {
+        // The web API is called on a blocking thread with a clone, so the Spotify play target stays usable meanwhile
+        let spotify = self.spotify.lock().await.clone();
+        let (task_spotify, context) = (spotify.clone(), library_entry.clone());
+        let tracks = tokio::task::spawn_blocking(move || task_spotify.get_context_tracks(&context))
+            .await
+            .unwrap_or_else(|error| Err(format!("Resolving was aborted: {}", error)));
+        let mut library_entry = match tracks {
+            Ok(Some(tracks)) if !tracks.is_empty() => {
+                info!("Resolved {} tracks of {}", tracks.len(), library_entry.id);
+                self.queue.expand_current(tracks).unwrap_or(library_entry)
+            }
+            Ok(_) => library_entry,
+            Err(error) => {
+                warn!("Could not resolve tracks of {}: {}", library_entry.id, error);
+                library_entry
+            }
+        };
+
+        if library_entry.image.is_none() {
+            let track = library_entry.clone();
+            library_entry.image =
+                tokio::task::spawn_blocking(move || spotify.get_cover(&track)).await.unwrap_or_default();
         }
-        if let Some(on_progress) = self.notify_progress.as_ref() {
-            on_progress(Progress::default());
+        library_entry
+    }
+
+    async fn start_track(&self, track: &mut Track) -> Result<(), String> {
+        track.target.lock().await.play(&track.library_entry).await?;
+        // Spotify keeps the volume of its device, which may be above the limit of the volume policy by now
+        if let Err(error) = track.target.lock().await.set_volume(self.get_applied_volume()).await {
+            warn!("Could not set volume of {}: {}", track.library_entry.id, error);
         }
+        sleep(Duration::from_secs(1)).await; // Let spotify api catch up with playing
+        let mut progress = track.target.lock().await.get_progress().await?;
+        progress.position = Duration::from_secs(0); // Spotify returns weird position
 
-        Ok(Some(library_entry.clone()))
+        // Spotify may still report the previous track, while the tracks of its contexts are known already
+        if let Some(duration_ms) = track.library_entry.duration_ms {
+            progress.duration = Some(Duration::from_millis(duration_ms as u64));
+        }
+
+        if let Some(position) = self.get_resume_position(&track.library_entry, &progress).await {
+            info!("Resuming {} at {:?}", track.library_entry.id, position);
+            track.target.lock().await.seek_to(position).await?;
+            progress.position = position;
+        }
+        track.set_progress(progress);
+        Ok(())
     }
 
     pub async fn play_prev_track(&mut self) -> Result<Option<LibraryEntry>, String> {
@@ -131,18 +271,54 @@ where
         }
     }
 
-    pub async fn queue_next_track(&mut self) -> Result<Option<LibraryEntry>, String> {
-        match self.queue.next() {
+    /// Plays the track at the position of the queue, as returned by get_queue
+    pub async fn play_queued_track(&mut self, position: usize) -> Result<Option<LibraryEntry>, String> {
+        match self.queue.skip_to(position) {
             Some(library_entry) => self.play_track(library_entry).await,
             None => Ok(None),
         }
     }
 
+    /// Returns the track which plays after the current one ended on its own
+    fn get_upcoming_track(&self) -> Option<LibraryEntry> {
+        match self.queue.mode().repeat {
+            RepeatMode::One => self.queue.get_current(),
+            _ => self.queue.peek_next(),
+        }
+    }
+
+    /// Hands the upcoming track to the play target ahead of time, so it can start it without a gap or crossfade into
+    /// it. Only done if the upcoming track plays on the same target as the current one.
+    pub(super) async fn queue_next_track(&mut self) -> Result<(), String> {
+        let upcoming = match self.get_upcoming_track() {
+            Some(upcoming) if !self.stops_after_current_track() => upcoming,
+            _ => return Ok(()),
+        };
+
+        let mut current_track = self.current_track.lock().await;
+        let track = match current_track.as_mut() {
+            Some(track) if track.queued.is_none() && track.library_entry.variant == upcoming.variant => track,
+            _ => return Ok(()),
+        };
+
+        let mut target = track.target.lock().await;
+        if !target.supports_queue() {
+            return Ok(());
+        }
+
+        info!("Queueing {}", upcoming.id);
+        target.queue(&upcoming).await?;
+        track.queued = Some(upcoming);
+        Ok(())
+    }
+
     pub async fn pause(&mut self) -> Result<(), String> {
         if let Some(track) = self.current_track.lock().await.as_mut() {
-            if track.playing {
+            if matches!(self.events.state(), PlaybackState::Playing | PlaybackState::Buffering) {
                 track.target.lock().await.pause().await?;
-                track.playing = false;
+                track.advance_progress(self.events.state().is_active());
+                track.queued = None;
+                self.events.set_state(PlaybackState::Paused);
             }
         }
         Ok(())
@@ -150,9 +326,10 @@ where
 
     pub async fn resume(&mut self) -> Result<(), String> {
         if let Some(track) = self.current_track.lock().await.as_mut() {
-            if !track.playing {
+            if matches!(self.events.state(), PlaybackState::Paused) {
                 track.target.lock().await.resume().await?;
-                track.playing = true;
+                track.advance_progress(false);
+                self.events.set_state(PlaybackState::Playing);
             }
         }
         Ok(())
@@ -160,11 +337,14 @@ where
 
     pub async fn seek_to(&mut self, percent: f64) -> Result<Option<Progress>, String> {
         if let Some(track) = self.current_track.lock().await.as_mut() {
-            let position = Duration::from_secs_f64(track.progress.duration.as_secs_f64() * percent / 100.0);
+            let duration = track.progress.duration.ok_or("Live streams can not be seeked".to_string())?;
+            let position = Duration::from_secs_f64(duration.as_secs_f64() * percent / 100.0);
             let mut target_lock = track.target.lock().await;
             target_lock.seek_to(position).await?;
 
-            track.progress.position = position;
+            track.set_progress(Progress { position, duration: Some(duration) });
+            track.queued = None;
+            self.events.emit(PlayerEvent::ProgressChanged(track.progress.clone()));
 
             return Ok(Some(track.progress.clone()));
         }
@@ -172,27 +352,118 @@ where
         Ok(None)
     }
 
-    pub async fn set_volume(&mut self, volume: f64) -> Result<(), String> {
-        if let Some(track) = self.current_track.lock().await.as_mut() {
-            track.target.lock().await.set_volume(volume).await?;
+    /// Sets the volume 0-1, the play targets use it up to the limit of the volume policy. Returns the volume which was
+    /// set, it is kept when the limit rises again after the quiet hours.
+    pub async fn set_volume(&mut self, volume: f64) -> Result<f64, String> {
+        self.volume = volume.clamp(0.0, 1.0);
+        self.apply_volume().await?;
+        Ok(self.volume)
+    }
+
+    /// Reads the audio effects again and applies them to the playing track, e.g. after they changed in the admin
+    /// interface
+    pub async fn reload_audio_config(&self) -> Result<(), String> {
+        let config = AudioConfigRepository::get(&self.conn)
+            .await
+            .map_err(|error| format!("Could not load audio config: {}", error))?;
+        self.output.lock().map_err(|error| format!("Could not lock audio output: {}", error))?.set_config(config);
+        Ok(())
+    }
+
+    /// Returns the stored position of the track if it is worth to continue from there
+    async fn get_resume_position(&self, library_entry: &LibraryEntry, progress: &Progress) -> Option<Duration> {
+        if !is_resumable(library_entry) {
+            return None;
+        }
+
+        let position = match PlaybackPositionRepository::get(&self.conn, library_entry.id).await {
+            Ok(position) => position?,
+            Err(error) => {
+                warn!("Could not load playback position of {}: {}", library_entry.id, error);
+                return None;
+            }
+        };
+
+        if position < RESUME_THRESHOLD
+            || progress.duration.is_none_or(|duration| position + RESUME_THRESHOLD > duration)
+        {
+            return None;
+        }
+
+        Some(position)
+    }
+
+    /// Makes the queued track, which was started by the play target on its own, the current one
+    async fn continue_with_queued(
+        &mut self,
+        library_entry: LibraryEntry,
+        target: Arc<Mutex<dyn PlayTarget + Send + 'static>>,
+    ) -> Result<(), String> {
+        if !matches!(self.queue.mode().repeat, RepeatMode::One) {
+            self.queue.next();
         }
+
+        let progress = target.lock().await.get_progress().await?;
+        *self.current_track.lock().await = Some(Track {
+            library_entry: library_entry.clone(),
+            target,
+            progress: progress.clone(),
+            progress_at: Instant::now(),
+            queued: None,
+        });
+
+        self.events.set_state(PlaybackState::Playing);
+        self.events.emit(PlayerEvent::TrackChanged(Some(library_entry)));
+        self.events.emit(PlayerEvent::ProgressChanged(progress));
         Ok(())
     }
 
     pub(super) async fn on_track_end(&mut self) -> Result<(), String> {
         info!("Track ended");
+        let mut queued = None;
         if let Some(track) = self.current_track.lock().await.as_mut() {
-            if let Some(notify_track_end) = self.notify_track_end.as_mut() {
-                notify_track_end(track.library_entry.clone())
+            queued = track.queued.take().map(|library_entry| (library_entry, track.target.clone()));
+            // Track was heard completely, next time it should start from the beginning
+            if let Err(error) = PlaybackPositionRepository::delete(&self.conn, track.library_entry.id).await {
+                warn!("Could not reset playback position of {}: {}", track.library_entry.id, error);
             }
+            self.events.emit(PlayerEvent::TrackEnded(track.library_entry.clone()));
         }
 
-        self.current_track = Arc::new(Mutex::new(None));
-        // If no next track notify about that. Else notification happens in play track
-        if self.play_next_track().await?.is_none() {
-            if let Some(on_track_change) = self.notify_track_change.as_ref() {
-                on_track_change(None);
+        *self.current_track.lock().await = None;
+        self.events.set_state(PlaybackState::Ended);
+
+        if self.count_ended_track() {
+            info!("Sleep timer ended with the track");
+            if let Some((_, target)) = queued {
+                target.lock().await.stop().await?;
+            }
+            self.cancel_fade().await?;
+            self.events.set_state(PlaybackState::Stopped);
+            self.events.emit(PlayerEvent::TrackChanged(None));
+            return Ok(());
+        }
+
+        // The queued track is already playing, unless the queue changed since. Then it is replaced by the right one.
+        if let Some((library_entry, target)) = queued {
+            if self.get_upcoming_track().is_some_and(|upcoming| upcoming.id == library_entry.id) {
+                return self.continue_with_queued(library_entry, target).await;
             }
+            target.lock().await.stop().await?;
+        }
+
+        let next_track = match self.queue.mode().repeat {
+            RepeatMode::One => match self.queue.get_current() {
+                Some(library_entry) => self.play_track(library_entry).await?,
+                None => None,
+            },
+            _ => self.play_next_track().await?,
+        };
+
+        // If no next track notify about that. Else notification happens in play track
+        if next_track.is_none() {
+            self.events.set_state(PlaybackState::Stopped);
+            self.events.emit(PlayerEvent::TrackChanged(None));
         }
         Ok(())
     }
@@ -1,45 +1,202 @@
 use std::fmt::Display;
-use database::model::library_entry::Model as LibraryEntry;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use database::model::library_entry::{Model as LibraryEntry, RepeatMode};
+
+/// Defines in which order tracks are played and whether they are repeated
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PlaybackMode {
+    pub shuffle: bool,
+    pub repeat: RepeatMode,
+}
+
+impl From<&LibraryEntry> for PlaybackMode {
+    fn from(library_entry: &LibraryEntry) -> Self {
+        Self {
+            shuffle: library_entry.shuffle,
+            repeat: library_entry.repeat_mode,
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct Queue {
     queue: Vec<LibraryEntry>,
-    current: i32
+    // Indices into queue in the order they are played
+    order: Vec<usize>,
+    current: i32,
+    mode: PlaybackMode,
 }
 
 impl Queue {
     pub fn new() -> Self {
         Self {
             queue: vec![],
-            current: -1
+            order: vec![],
+            current: -1,
+            mode: PlaybackMode::default(),
+        }
+    }
+
+    /// Creates a queue of all tracks in the given mode, which continues with the track of start_id.
+    /// If no start track is given, the queue starts with the first track, or a random one when shuffling.
+    pub fn from_tracks(tracks: Vec<LibraryEntry>, start_id: Option<i32>, mode: PlaybackMode) -> Self {
+        let start_index = start_id.and_then(|start_id| tracks.iter().position(|track| track.id == start_id));
+        let mut queue = Self {
+            order: (0..tracks.len()).collect(),
+            queue: tracks,
+            current: -1,
+            mode,
+        };
+
+        if mode.shuffle {
+            queue.order.shuffle(&mut rand::thread_rng());
+            if let Some(start_index) = start_index {
+                queue.order.retain(|index| *index != start_index);
+                queue.order.insert(0, start_index);
+            }
+        } else if let Some(start_index) = start_index {
+            queue.current = start_index as i32 - 1;
         }
+
+        queue
     }
 
     pub fn add(&mut self, track: LibraryEntry) {
+        self.order.push(self.queue.len());
         self.queue.push(track);
     }
 
+    pub fn mode(&self) -> PlaybackMode {
+        self.mode
+    }
+
+    /// Changes the playback mode. Toggling shuffle keeps the current track and only reorders the upcoming ones.
+    pub fn set_mode(&mut self, mode: PlaybackMode) {
+        if mode.shuffle != self.mode.shuffle {
+            let current_index = self.current_index();
+            self.order = (0..self.queue.len()).collect();
+
+            match current_index {
+                Some(index) if mode.shuffle => {
+                    self.order.retain(|i| *i != index);
+                    self.order.shuffle(&mut rand::thread_rng());
+                    self.order.insert(0, index);
+                    self.current = 0;
+                }
+                Some(index) => self.current = index as i32,
+                None if mode.shuffle => self.order.shuffle(&mut rand::thread_rng()),
+                None => {}
+            }
+        }
+        self.mode = mode;
+    }
+
+    /// Moves to the next track. At the end of the queue it starts over again if repeating is enabled, in a new order
+    /// when shuffling. Repeating a single track is up to the player, as skipping should still move on to the next
+    /// track.
     pub fn next(&mut self) -> Option<LibraryEntry> {
-        if self.queue.len() <= (self.current + 1) as usize {
-            None
+        if self.order.len() <= (self.current + 1) as usize {
+            if matches!(self.mode.repeat, RepeatMode::Off) || self.order.is_empty() {
+                return None;
+            }
+            if self.mode.shuffle {
+                let finished = self.current_index();
+                self.order.shuffle(&mut rand::thread_rng());
+                // The track which just finished would otherwise play twice in a row
+                if self.order.len() > 1 && self.order.first() == finished.as_ref() {
+                    let other = rand::thread_rng().gen_range(1..self.order.len());
+                    self.order.swap(0, other);
+                }
+            }
+            self.current = 0;
         } else {
             self.current += 1;
-            self.queue.get(self.current as usize).map(|track| track.clone())
         }
+        self.get_current()
+    }
+
+    /// Returns the track next() would move to, without moving there. None at the end of a shuffled queue, as the order
+    /// of the next round is only decided once it starts.
+    pub fn peek_next(&self) -> Option<LibraryEntry> {
+        let next = if self.order.len() <= (self.current + 1) as usize {
+            if matches!(self.mode.repeat, RepeatMode::Off) || self.mode.shuffle {
+                return None;
+            }
+            0
+        } else {
+            (self.current + 1) as usize
+        };
+        self.order.get(next).and_then(|index| self.queue.get(*index)).cloned()
     }
 
     pub fn prev(&mut self) -> Option<LibraryEntry> {
         if self.current < 1 {
-            None
+            if matches!(self.mode.repeat, RepeatMode::Off) || self.order.is_empty() {
+                return None;
+            }
+            self.current = self.order.len() as i32 - 1;
         } else {
             self.current -= 1;
-            self.queue.get(self.current as usize).map(|track| track.clone())
         }
+        self.get_current()
+    }
+
+    /// Moves to the track at the position of the order get_tracks returns
+    pub fn skip_to(&mut self, position: usize) -> Option<LibraryEntry> {
+        if position >= self.order.len() {
+            return None;
+        }
+        self.current = position as i32;
+        self.get_current()
+    }
+
+    /// Returns how many tracks are left to play until the end of the queue, including the current one
+    pub fn count_remaining(&self) -> u32 {
+        (self.order.len() as i32 - self.current.max(0)).max(0) as u32
+    }
+
+    /// Returns all tracks in the order they are played
+    pub fn get_tracks(&self) -> Vec<LibraryEntry> {
+        self.order.iter().filter_map(|index| self.queue.get(*index)).cloned().collect()
+    }
+
+    pub fn get_current(&self) -> Option<LibraryEntry> {
+        self.current_index().and_then(|index| self.queue.get(index)).cloned()
+    }
+
+    /// Replaces the current track by the given ones, e.g. a Spotify album by its tracks. They are played in their
+    /// order even when shuffling, as they belong together. Returns the first of them, which is the current track then.
+    pub fn expand_current(&mut self, tracks: Vec<LibraryEntry>) -> Option<LibraryEntry> {
+        let index = self.current_index()?;
+        if tracks.is_empty() {
+            return self.get_current();
+        }
+
+        let added = tracks.len() - 1;
+        self.queue.splice(index..=index, tracks);
+        for queue_index in self.order.iter_mut().filter(|queue_index| **queue_index > index) {
+            *queue_index += added;
+        }
+        let position = self.current as usize + 1;
+        self.order.splice(position..position, index + 1..=index + added);
+
+        self.get_current()
     }
 
     pub fn clear(&mut self) {
         self.current = -1;
         self.queue.clear();
+        self.order.clear();
+    }
+
+    fn current_index(&self) -> Option<usize> {
+        if self.current < 0 {
+            return None;
+        }
+        self.order.get(self.current as usize).copied()
     }
 }
 
@@ -53,24 +210,155 @@ impl FromIterator<LibraryEntry> for Queue {
     }
 }
 
-impl Iterator for Queue {
-    type Item = LibraryEntry;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        self.next()
-    }
-}
-
 impl Display for Queue {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "Queue([")?;
-        for (i, track) in self.queue.iter().enumerate() {
+        for (i, index) in self.order.iter().enumerate() {
             if i != 0 {
                 write!(f, " ,")?;
             }
-            write!(f, "{}", track.id)?;
+            write!(f, "{}", self.queue[*index].id)?;
         }
         write!(f, "])")?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use database::model::library_entry::Variant;
+
+    use super::*;
+
+    fn tracks(count: i32) -> Vec<LibraryEntry> {
+        (1..=count)
+            .map(|id| LibraryEntry {
+                id,
+                parent_id: Some(0),
+                variant: Variant::File,
+                name: format!("Track {}", id),
+                image: None,
+                played_at: None,
+                sort_key: id,
+                shuffle: false,
+                repeat_mode: RepeatMode::Off,
+                album_gain: None,
+                pinned: false,
+                children: None,
+                track_source: None,
+                parent_name: None,
+                parent_image: None,
+                duration_ms: None,
+            })
+            .collect()
+    }
+
+    fn mode(shuffle: bool, repeat: RepeatMode) -> PlaybackMode {
+        PlaybackMode { shuffle, repeat }
+    }
+
+    fn next_id(queue: &mut Queue) -> Option<i32> {
+        queue.next().map(|track| track.id)
+    }
+
+    #[test]
+    fn starts_at_start_track() {
+        let mut queue = Queue::from_tracks(tracks(3), Some(2), mode(false, RepeatMode::Off));
+
+        assert_eq!(next_id(&mut queue), Some(2));
+        assert_eq!(next_id(&mut queue), Some(3));
+        assert_eq!(next_id(&mut queue), None);
+    }
+
+    #[test]
+    fn wraps_around_on_repeat_all() {
+        let mut queue = Queue::from_tracks(tracks(3), None, mode(false, RepeatMode::All));
+
+        let ids = (0..7).map(|_| next_id(&mut queue)).collect::<Vec<_>>();
+        assert_eq!(ids, [1, 2, 3, 1, 2, 3, 1].map(Some));
+    }
+
+    #[test]
+    fn plays_every_track_once_per_round_when_shuffling() {
+        let mut queue = Queue::from_tracks(tracks(5), Some(3), mode(true, RepeatMode::All));
+
+        for round in 0..3 {
+            let mut ids = (0..5).map(|_| next_id(&mut queue).unwrap()).collect::<Vec<_>>();
+            if round == 0 {
+                assert_eq!(ids[0], 3);
+            }
+            ids.sort();
+            assert_eq!(ids, vec![1, 2, 3, 4, 5]);
+        }
+    }
+
+    #[test]
+    fn does_not_repeat_last_track_after_reshuffling() {
+        for _ in 0..50 {
+            let mut queue = Queue::from_tracks(tracks(2), None, mode(true, RepeatMode::All));
+            next_id(&mut queue);
+            let last = next_id(&mut queue);
+
+            assert_ne!(next_id(&mut queue), last);
+        }
+    }
+
+    #[test]
+    fn stays_at_start_on_prev_without_repeat() {
+        let mut queue = Queue::from_tracks(tracks(3), None, mode(false, RepeatMode::Off));
+        assert_eq!(queue.prev(), None);
+
+        next_id(&mut queue);
+        assert_eq!(queue.prev(), None);
+        assert_eq!(queue.get_current().map(|track| track.id), Some(1));
+    }
+
+    #[test]
+    fn goes_to_last_track_on_prev_at_start_with_repeat_all() {
+        let mut queue = Queue::from_tracks(tracks(3), None, mode(false, RepeatMode::All));
+        next_id(&mut queue);
+
+        assert_eq!(queue.prev().map(|track| track.id), Some(3));
+        assert_eq!(queue.prev().map(|track| track.id), Some(2));
+    }
+
+    #[test]
+    fn keeps_current_track_when_toggling_shuffle() {
+        let mut queue = Queue::from_tracks(tracks(5), None, mode(false, RepeatMode::Off));
+        next_id(&mut queue);
+        next_id(&mut queue);
+
+        queue.set_mode(mode(true, RepeatMode::Off));
+        assert_eq!(queue.get_current().map(|track| track.id), Some(2));
+        assert_eq!(queue.count_remaining(), 5);
+        let mut upcoming = (0..4).map(|_| next_id(&mut queue).unwrap()).collect::<Vec<_>>();
+        upcoming.sort();
+        assert_eq!(upcoming, vec![1, 3, 4, 5]);
+
+        queue.set_mode(mode(false, RepeatMode::Off));
+        let current = queue.get_current().unwrap().id;
+        assert_eq!(next_id(&mut queue), if current < 5 { Some(current + 1) } else { None });
+    }
+
+    #[test]
+    fn peeks_track_next_moves_to() {
+        for playback_mode in [mode(false, RepeatMode::Off), mode(false, RepeatMode::All), mode(true, RepeatMode::Off)] {
+            let mut queue = Queue::from_tracks(tracks(4), None, playback_mode);
+            for _ in 0..6 {
+                let peeked = queue.peek_next();
+                assert_eq!(queue.next(), peeked);
+            }
+        }
+    }
+
+    #[test]
+    fn peeks_nothing_at_end_of_shuffled_round() {
+        let mut queue = Queue::from_tracks(tracks(3), None, mode(true, RepeatMode::All));
+        for _ in 0..3 {
+            next_id(&mut queue);
+        }
+
+        assert_eq!(queue.peek_next(), None);
+        assert!(queue.next().is_some());
+    }
+}
@@ -0,0 +1,124 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::sync::Mutex as AsyncMutex;
+use tracing::info;
+
+use crate::player::event::{PlaybackState, PlayerEvent};
+use crate::player::play_target::{PlayTarget, Progress};
+use crate::Player;
+
+// The volume fades out during this time before the sleep timer stops the playback
+pub(super) const FADE_DURATION: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SleepTimer {
+    // Stops the playback at the given time
+    Until(Instant),
+    // Stops the playback at the end of this many tracks, including the playing one
+    Tracks(u32),
+}
+
+impl SleepTimer {
+    /// Time until the playback stops, if it is known already
+    pub fn get_remaining(&self, progress: &Progress) -> Option<Duration> {
+        match self {
+            SleepTimer::Until(end) => Some(end.saturating_duration_since(Instant::now())),
+            SleepTimer::Tracks(1) => progress.remaining(),
+            SleepTimer::Tracks(_) => None,
+        }
+    }
+}
+
+/// Track whose volume is fading out
+pub(super) struct Fade {
+    pub(super) library_entry_id: i32,
+    pub(super) target: Arc<AsyncMutex<dyn PlayTarget + Send + 'static>>,
+}
+
+/// Sleep timer and its fade, shared with the timers
+#[derive(Clone, Default)]
+pub(super) struct SleepTimerState {
+    pub(super) timer: Arc<Mutex<Option<SleepTimer>>>,
+    pub(super) fade: Arc<Mutex<Option<Fade>>>,
+}
+
+impl Player {
+    pub fn get_sleep_timer(&self) -> Option<SleepTimer> {
+        *self.sleep_timer.timer.lock().unwrap()
+    }
+
+    /// Starts, replaces or cancels the sleep timer. A running fade is reverted.
+    pub async fn set_sleep_timer(&mut self, timer: Option<SleepTimer>) -> Result<(), String> {
+        info!("Setting sleep timer to {:?}", timer);
+        // No track would ever count it down, e.g. when sleeping at the end of an empty queue
+        if matches!(timer, Some(SleepTimer::Tracks(0))) {
+            return Err("The sleep timer needs at least one track to play".to_string());
+        }
+        *self.sleep_timer.timer.lock().unwrap() = timer;
+        self.cancel_fade().await?;
+
+        // A queued track would start on its own, even though the timer ends with the playing one
+        if matches!(timer, Some(SleepTimer::Tracks(1))) {
+            if let Some(track) = self.current_track.lock().await.as_mut() {
+                if track.queued.take().is_some() {
+                    track.target.lock().await.unqueue().await?;
+                }
+            }
+        }
+
+        self.events.emit(PlayerEvent::SleepTimerChanged(timer));
+        Ok(())
+    }
+
+    /// Returns how many tracks are left until the end of the playing folder, including the current one
+    pub fn count_remaining_tracks(&self) -> u32 {
+        self.queue.count_remaining()
+    }
+
+    /// Whether the sleep timer stops the playback at the end of the current track
+    pub(super) fn stops_after_current_track(&self) -> bool {
+        matches!(self.get_sleep_timer(), Some(SleepTimer::Tracks(count)) if count <= 1)
+    }
+
+    /// Counts down the tracks of the sleep timer. Returns whether the playback has to stop.
+    pub(super) fn count_ended_track(&self) -> bool {
+        let timer = {
+            let mut timer = self.sleep_timer.timer.lock().unwrap();
+            match *timer {
+                Some(SleepTimer::Tracks(count)) if count <= 1 => *timer = None,
+                Some(SleepTimer::Tracks(count)) => *timer = Some(SleepTimer::Tracks(count - 1)),
+                _ => return false,
+            }
+            *timer
+        };
+
+        self.events.emit(PlayerEvent::SleepTimerChanged(timer));
+        timer.is_none()
+    }
+
+    /// Stops the playback after the time of the sleep timer passed, the same way as when it ends with a track
+    pub(super) async fn expire_sleep_timer(&mut self) -> Result<(), String> {
+        info!("Sleep timer expired");
+        *self.sleep_timer.timer.lock().unwrap() = None;
+        let track = self.current_track.lock().await.take();
+        if let Some(track) = track {
+            track.target.lock().await.stop().await?;
+        }
+        self.cancel_fade().await?;
+        self.events.set_state(PlaybackState::Stopped);
+        self.events.emit(PlayerEvent::TrackChanged(None));
+        self.events.emit(PlayerEvent::SleepTimerChanged(None));
+        Ok(())
+    }
+
+    /// Restores the volume of a track which is fading out. The limit of the volume policy may have changed meanwhile.
+    pub(super) async fn cancel_fade(&self) -> Result<(), String> {
+        let fade = self.sleep_timer.fade.lock().unwrap().take();
+        if let Some(fade) = fade {
+            fade.target.lock().await.cancel_fade().await?;
+            self.apply_volume().await?;
+        }
+        Ok(())
+    }
+}
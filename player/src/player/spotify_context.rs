@@ -0,0 +1,145 @@
+use rspotify::clients::BaseClient;
+use rspotify::model::{AlbumId, Image, Market, PlayableItem, PlaylistId, ShowId};
+use rspotify::prelude::Id;
+use rspotify::AuthCodeSpotify;
+
+use database::model::library_entry::{Model as LibraryEntry, Variant};
+
+// The player bar shows covers small, so the smallest one of at least this width is used
+const COVER_WIDTH: u32 = 300;
+
+/// Track of a Spotify album, playlist or show, which is queued in place of it
+pub struct ContextTrack {
+    pub library_entry: LibraryEntry,
+    // Playlists may have hundreds of tracks, so covers are only fetched once a track plays
+    pub cover_url: Option<String>,
+}
+
+/// Fetches the tracks of a Spotify album, playlist or show through the Web API. Returns none for other entries, which
+/// play on their own.
+pub fn resolve_context(client: &AuthCodeSpotify, context: &LibraryEntry) -> Result<Option<Vec<ContextTrack>>, String> {
+    if !matches!(context.variant, Variant::Spotify) {
+        return Ok(None);
+    }
+    let track_source = match context.track_source.as_ref() {
+        Some(track_source) => track_source,
+        None => return Ok(None),
+    };
+    let (spotify_type, spotify_id) = match (track_source.spotify_type.as_deref(), track_source.spotify_id.as_deref()) {
+        (Some(spotify_type), Some(spotify_id)) => (spotify_type, spotify_id),
+        _ => return Ok(None),
+    };
+
+    // Lets spotify leave out tracks which are not available in the country of the user
+    let market = Some(Market::FromToken);
+    let mut tracks = vec![];
+    match spotify_type {
+        "album" => {
+            let album_id = AlbumId::from_id(spotify_id).map_err(|e| format!("Invalid album ID: {}", e))?;
+            for track in client.album_track(album_id, market) {
+                let track = track.map_err(|e| format!("Failed to fetch album tracks: {}", e))?;
+                if track.is_playable == Some(false) {
+                    continue;
+                }
+                if let Some(id) = track.id {
+                    // The album cover is the one of the context already
+                    tracks.push(to_context_track(context, "track", id.id(), track.name, track.duration, &[]));
+                }
+            }
+        }
+        "playlist" => {
+            let playlist_id = PlaylistId::from_id(spotify_id).map_err(|e| format!("Invalid playlist ID: {}", e))?;
+            for item in client.playlist_items(playlist_id, None, market) {
+                let item = item.map_err(|e| format!("Failed to fetch playlist items: {}", e))?;
+                match item.track {
+                    Some(PlayableItem::Track(track)) if track.is_playable != Some(false) => {
+                        // Local files of the playlist owner have no id and can not be played
+                        if let Some(id) = track.id {
+                            let images = &track.album.images;
+                            tracks.push(to_context_track(
+                                context,
+                                "track",
+                                id.id(),
+                                track.name,
+                                track.duration,
+                                images,
+                            ));
+                        }
+                    }
+                    Some(PlayableItem::Episode(episode)) => {
+                        let id = episode.id.id();
+                        tracks.push(to_context_track(
+                            context,
+                            "episode",
+                            id,
+                            episode.name,
+                            episode.duration,
+                            &episode.images,
+                        ));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        "show" => {
+            let show_id = ShowId::from_id(spotify_id).map_err(|e| format!("Invalid show ID: {}", e))?;
+            for episode in client.get_shows_episodes(show_id, market) {
+                let episode = episode.map_err(|e| format!("Failed to fetch show episodes: {}", e))?;
+                if episode.is_playable {
+                    let id = episode.id.id();
+                    tracks.push(to_context_track(
+                        context,
+                        "episode",
+                        id,
+                        episode.name,
+                        episode.duration,
+                        &episode.images,
+                    ));
+                }
+            }
+        }
+        // Artists play their top tracks on spotify, which change over time
+        _ => return Ok(None),
+    }
+
+    Ok(Some(tracks))
+}
+
+/// Returns an id which tells the tracks of a context apart, as they share the library entry of the context
+pub fn get_track_id(library_entry: &LibraryEntry) -> String {
+    let spotify_id = library_entry.track_source.as_ref().and_then(|track_source| track_source.spotify_id.as_deref());
+    match (library_entry.variant, spotify_id) {
+        (Variant::Spotify, Some(spotify_id)) => format!("{}_{}", library_entry.id, spotify_id),
+        _ => library_entry.id.to_string(),
+    }
+}
+
+fn to_context_track(
+    context: &LibraryEntry,
+    spotify_type: &str,
+    spotify_id: &str,
+    name: String,
+    duration: chrono::Duration,
+    images: &[Image],
+) -> ContextTrack {
+    let mut library_entry = context.clone();
+    library_entry.name = name;
+    library_entry.image = None;
+    library_entry.children = None;
+    library_entry.parent_name = Some(context.name.clone());
+    library_entry.parent_image = context.image.clone().or(context.parent_image.clone());
+    library_entry.duration_ms = Some(duration.num_milliseconds() as i32);
+    if let Some(track_source) = library_entry.track_source.as_mut() {
+        track_source.spotify_type = Some(spotify_type.to_string());
+        track_source.spotify_id = Some(spotify_id.to_string());
+    }
+
+    let cover_url = images
+        .iter()
+        .filter(|image| image.width.unwrap_or_default() >= COVER_WIDTH)
+        .min_by_key(|image| image.width)
+        .or(images.first())
+        .map(|image| image.url.clone());
+
+    ContextTrack { library_entry, cover_url }
+}
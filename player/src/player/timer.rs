@@ -1,84 +1,104 @@
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use tokio::sync::Mutex;
 use tracing::error;
 
-use database::model::library_entry::{Model as LibraryEntry, Variant};
+use database::PlaybackPositionRepository;
 
-use crate::{Player, Progress};
+use crate::player::event::{PlaybackState, PlayerEvent};
+use crate::player::player::is_resumable;
+use crate::player::sleep_timer::{Fade, SleepTimer, FADE_DURATION};
+use crate::player::spotify_context::get_track_id;
+use crate::player::volume::VolumePolicy;
+use crate::Player;
 
-// Update progress position every second optimistically. FetchProgressTimer is used to correct the optimistic progress position
+// Upcoming tracks are queued this long before the current one ends. Leaves enough time to load them and to crossfade.
+const QUEUE_AHEAD: Duration = Duration::from_secs(15);
+// The volume limit changes gradually when quiet hours start, so it is checked often enough for a smooth ramp
+const VOLUME_POLICY_INTERVAL: Duration = Duration::from_secs(10);
+
+// Advances the progress position every second by the time passed. The play targets are asked for the actual position
+// every few seconds only, as Spotify has to be asked through its web API. The timers only work on the shared current
+// track and lock the player only if the track needs to change, so user actions are not blocked.
 pub struct PlayerTimer;
 impl PlayerTimer {
-    pub fn start_progress_timer<P, T, E>(player: Arc<Mutex<Player<P, T, E>>>)
-    where
-        P: Fn(Progress) + 'static + Sync + Send,
-        T: Fn(Option<LibraryEntry>) + 'static + Sync + Send,
-        E: Fn(LibraryEntry) + 'static + Sync + Send,
-    {
+    pub fn start_progress_timer(player: Arc<Mutex<Player>>) {
         tokio::spawn(async move {
+            let (current_track, events) = {
+                let player = player.lock().await;
+                (player.current_track.clone(), player.events.clone())
+            };
+
             let mut interval = tokio::time::interval(Duration::from_millis(1000));
             loop {
                 interval.tick().await;
 
-                let mut player = player.lock().await;
-                let (variant, progress) = {
-                    let mut current_track = player.current_track.lock().await;
+                let (target, progress) = {
+                    let mut current_track = current_track.lock().await;
                     let track = match current_track.as_mut() {
+                        Some(track) => track,
                         None => continue,
-                        Some(current_track) if !current_track.playing => continue,
-                        Some(current_track) => current_track,
                     };
 
-                    track.progress.position += Duration::from_millis(1000);
-
-                    if let Some(on_progress) = player.notify_progress.as_ref() {
-                        on_progress(track.progress.clone())
+                    // Keeps the time of a paused or buffering track current, so its position does not jump afterward
+                    track.advance_progress(events.state().is_active());
+                    if !events.state().is_active() {
+                        continue;
                     }
+                    events.emit(PlayerEvent::ProgressChanged(track.progress.clone()));
 
-                    (track.library_entry.variant, track.progress.clone())
+                    (track.target.clone(), track.progress.clone())
                 };
 
-                // For spotify we want to add tracks to queue before they end to ensure seamless playing
-                if matches!(variant, Variant::Spotify) {
-                    if progress.position >= progress.duration {
-                        if let Err(err) = player.on_track_end().await {
-                            error!("Failed to play next track: {}", err);
-                        }
+                if progress.remaining().is_some_and(|remaining| remaining <= QUEUE_AHEAD) {
+                    if let Err(err) = player.lock().await.queue_next_track().await {
+                        error!("Failed to queue next track: {}", err);
                     }
-                } else {
-                    if progress.position >= progress.duration {
-                        if let Err(err) = player.on_track_end().await {
-                            error!("Failed to end track: {}", err);
-                        }
+                }
+
+                let finished = match target.lock().await.is_finished(&progress).await {
+                    Ok(finished) => finished,
+                    Err(err) => {
+                        error!("Could not check if track finished: {}", err);
+                        continue;
+                    }
+                };
+                if finished {
+                    if let Err(err) = player.lock().await.on_track_end().await {
+                        error!("Failed to end track: {}", err);
+                        events.set_state(PlaybackState::Stopped);
                     }
                 }
             }
         });
     }
 
-    // Fetching progress is done in separate thread to not block progress update
-    pub fn start_correct_progress_timer<P, T, E>(player: Arc<Mutex<Player<P, T, E>>>)
-    where
-        P: Fn(Progress) + 'static + Sync + Send,
-        T: Fn(Option<LibraryEntry>) + 'static + Sync + Send,
-        E: Fn(LibraryEntry) + 'static + Sync + Send,
-    {
+    // Fetching progress is done in separate thread to not block progress update.
+    // The fetched position is also persisted to be able to resume the track after a restart.
+    pub fn start_correct_progress_timer(player: Arc<Mutex<Player>>) {
         tokio::spawn(async move {
+            let (current_track, events, conn) = {
+                let player = player.lock().await;
+                (player.current_track.clone(), player.events.clone(), player.conn.clone())
+            };
+
             let mut interval = tokio::time::interval(Duration::from_millis(5000));
             loop {
                 interval.tick().await;
-                let player = player.lock().await;
 
-                let mut current_track = player.current_track.lock().await;
-                let current_track = match current_track.as_mut() {
-                    None => continue,
-                    Some(track) if !track.playing => continue,
-                    Some(track) => track,
+                let (target, track_id) = {
+                    let current_track = current_track.lock().await;
+                    match current_track.as_ref() {
+                        Some(track) if events.state().is_active() => {
+                            (track.target.clone(), get_track_id(&track.library_entry))
+                        }
+                        _ => continue,
+                    }
                 };
 
-                let progress = match current_track.target.lock().await.get_progress().await {
+                // The track is not locked meanwhile, as Spotify is asked through its web API
+                let progress = match target.lock().await.get_progress().await {
                     Ok(progress) => progress,
                     Err(error) => {
                         error!("Could not fetch progress: {}", error);
@@ -86,10 +106,95 @@ impl PlayerTimer {
                     }
                 };
 
-                current_track.progress = progress;
+                let library_entry = {
+                    let mut current_track = current_track.lock().await;
+                    match current_track.as_mut() {
+                        // The progress of a track which ended meanwhile would be the one of the next track
+                        Some(track) if get_track_id(&track.library_entry) == track_id => {
+                            track.set_progress(progress.clone());
+                            track.library_entry.clone()
+                        }
+                        _ => continue,
+                    }
+                };
+
+                events.emit(PlayerEvent::ProgressChanged(progress.clone()));
 
-                if let Some(on_progress) = player.notify_progress.as_ref() {
-                    on_progress(current_track.progress.clone())
+                // The track is not locked meanwhile, so the database does not hold up the other timers
+                if is_resumable(&library_entry) {
+                    let position = progress.position;
+                    if let Err(error) = PlaybackPositionRepository::set(&conn, library_entry.id, position).await {
+                        error!("Could not save playback position of {}: {}", library_entry.id, error);
+                    }
+                }
+            }
+        });
+    }
+
+    // Fades out the volume during the last minute of the sleep timer and stops the playback when it expired
+    pub fn start_sleep_timer(player: Arc<Mutex<Player>>) {
+        tokio::spawn(async move {
+            let (current_track, events, sleep_timer) = {
+                let player = player.lock().await;
+                (player.current_track.clone(), player.events.clone(), player.sleep_timer.clone())
+            };
+
+            let mut interval = tokio::time::interval(Duration::from_millis(1000));
+            loop {
+                interval.tick().await;
+
+                let timer = match *sleep_timer.timer.lock().unwrap() {
+                    Some(timer) => timer,
+                    None => continue,
+                };
+                if matches!(timer, SleepTimer::Until(end) if end <= Instant::now()) {
+                    if let Err(err) = player.lock().await.expire_sleep_timer().await {
+                        error!("Failed to stop playback for sleep timer: {}", err);
+                    }
+                    continue;
+                }
+
+                let (target, remaining) = {
+                    let current_track = current_track.lock().await;
+                    let track = match current_track.as_ref() {
+                        Some(track) if events.state().is_active() => track,
+                        _ => continue,
+                    };
+                    let remaining = match timer.get_remaining(&track.progress) {
+                        Some(remaining) if remaining <= FADE_DURATION => remaining,
+                        _ => continue,
+                    };
+
+                    let mut fade = sleep_timer.fade.lock().unwrap();
+                    if fade.as_ref().is_some_and(|fade| fade.library_entry_id == track.library_entry.id) {
+                        continue;
+                    }
+                    *fade = Some(Fade { library_entry_id: track.library_entry.id, target: track.target.clone() });
+                    (track.target.clone(), remaining)
+                };
+
+                if let Err(err) = target.lock().await.fade_out(remaining).await {
+                    error!("Failed to fade out for sleep timer: {}", err);
+                }
+            }
+        });
+    }
+
+    // Reloads the volume policy and lowers the volume step by step when quiet hours start
+    pub fn start_volume_timer(player: Arc<Mutex<Player>>) {
+        tokio::spawn(async move {
+            let conn = player.lock().await.conn.clone();
+
+            let mut interval = tokio::time::interval(VOLUME_POLICY_INTERVAL);
+            loop {
+                interval.tick().await;
+
+                let policy = match VolumePolicy::load(&conn).await {
+                    Some(policy) => policy,
+                    None => continue,
+                };
+                if let Err(err) = player.lock().await.update_volume_policy(policy).await {
+                    error!("Failed to apply volume policy: {}", err);
                 }
             }
         });
@@ -0,0 +1,234 @@
+use std::time::Duration;
+
+use chrono::{Local, NaiveTime};
+use tracing::{info, warn};
+
+use database::model::system_config::Model as SystemConfig;
+use database::{DatabaseConnection, SystemConfigRepository};
+
+use crate::player::event::PlayerEvent;
+use crate::Player;
+
+// The limit lowers gradually to the one of the quiet hours within this time after they start
+const RAMP_DURATION: Duration = Duration::from_secs(10 * 60);
+
+/// Time of day window, which may span midnight, e.g. 19:00-07:00
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    // 0-1
+    pub max_volume: f64,
+}
+
+impl QuietHours {
+    fn from_config(config: &SystemConfig) -> Option<Self> {
+        if config.quiet_hours_start.is_empty() || config.quiet_hours_end.is_empty() {
+            return None;
+        }
+
+        let parse = |time: &str| match NaiveTime::parse_from_str(time, "%H:%M") {
+            Ok(time) => Some(time),
+            Err(error) => {
+                warn!("Invalid quiet hours time '{}': {}", time, error);
+                None
+            }
+        };
+        Some(Self {
+            start: parse(&config.quiet_hours_start)?,
+            end: parse(&config.quiet_hours_end)?,
+            max_volume: config.quiet_hours_volume.min(100) as f64 / 100.0,
+        })
+    }
+
+    /// Returns how long the quiet hours are active already, if they are active at the time
+    fn get_elapsed(&self, time: NaiveTime) -> Option<Duration> {
+        let active = match self.start <= self.end {
+            true => self.start <= time && time < self.end,
+            false => self.start <= time || time < self.end,
+        };
+        if !active {
+            return None;
+        }
+
+        // Spanning midnight makes the difference negative, a day is added then
+        let elapsed = (time - self.start).num_seconds().rem_euclid(24 * 60 * 60);
+        Some(Duration::from_secs(elapsed as u64))
+    }
+}
+
+/// Limits the volume of all play targets to the configured maximum, which is lowered during quiet hours
+#[derive(Clone, Debug, PartialEq)]
+pub struct VolumePolicy {
+    // 0-1
+    pub max_volume: f64,
+    pub quiet_hours: Option<QuietHours>,
+}
+
+impl Default for VolumePolicy {
+    fn default() -> Self {
+        Self { max_volume: 1.0, quiet_hours: None }
+    }
+}
+
+impl VolumePolicy {
+    /// Reads the policy from the system config, so changes in the admin interface apply without restart
+    pub async fn load(conn: &DatabaseConnection) -> Option<Self> {
+        match SystemConfigRepository::get(conn).await {
+            Ok(Some(config)) => Some(Self {
+                max_volume: config.max_volume.min(100) as f64 / 100.0,
+                quiet_hours: QuietHours::from_config(&config),
+            }),
+            Ok(None) => Some(Self::default()),
+            Err(error) => {
+                warn!("Could not load volume policy: {}", error);
+                None
+            }
+        }
+    }
+
+    /// Returns the highest volume allowed at the time of day
+    pub fn get_limit(&self, time: NaiveTime) -> f64 {
+        let quiet_hours = match self.quiet_hours {
+            Some(quiet_hours) => quiet_hours,
+            None => return self.max_volume,
+        };
+        let quiet_volume = quiet_hours.max_volume.min(self.max_volume);
+
+        match quiet_hours.get_elapsed(time) {
+            Some(elapsed) if elapsed < RAMP_DURATION => {
+                let ramp = elapsed.as_secs_f64() / RAMP_DURATION.as_secs_f64();
+                self.max_volume - (self.max_volume - quiet_volume) * ramp
+            }
+            Some(_) => quiet_volume,
+            None => self.max_volume,
+        }
+    }
+}
+
+impl Player {
+    /// Highest volume currently allowed by the volume policy
+    pub fn get_volume_limit(&self) -> f64 {
+        self.volume_limit
+    }
+
+    /// Volume the play targets actually use, the set one may be above the limit of the quiet hours
+    pub(super) fn get_applied_volume(&self) -> f64 {
+        self.volume.min(self.volume_limit)
+    }
+
+    /// Replaces the volume policy and applies its limit of the current time of day
+    pub(super) async fn update_volume_policy(&mut self, policy: VolumePolicy) -> Result<(), String> {
+        let limit = policy.get_limit(Local::now().time());
+        if policy != self.volume_policy {
+            info!("Using volume policy {:?}", policy);
+            self.volume_policy = policy;
+        }
+        if (limit - self.volume_limit).abs() < 0.005 {
+            return Ok(());
+        }
+
+        let applied_volume = self.get_applied_volume();
+        self.volume_limit = limit;
+        self.events.emit(PlayerEvent::VolumeLimitChanged(limit));
+        // Setting the volume would cancel the fade of the sleep timer, the limit applies once the fade is cancelled
+        let fading = self.sleep_timer.fade.lock().unwrap().is_some();
+        if self.get_applied_volume() != applied_volume && !fading {
+            self.apply_volume().await?;
+        }
+        Ok(())
+    }
+
+    /// Hands the applied volume to all play targets, so a track of another target starts with it as well
+    pub(super) async fn apply_volume(&self) -> Result<(), String> {
+        let volume = self.get_applied_volume();
+        let current_target = self.current_track.lock().await.as_ref().map(|track| track.target.clone());
+
+        if let Some(target) = current_target.as_ref() {
+            target.lock().await.set_volume(volume).await?;
+        }
+        // Spotify needs a device for every volume change, so it is only told while it plays
+        if let Err(error) = self.local.lock().await.set_volume(volume).await {
+            warn!("Could not set volume of local play target: {}", error);
+        }
+        if let Err(error) = self.remote.lock().await.set_volume(volume).await {
+            warn!("Could not set volume of remote play target: {}", error);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    fn policy(max_volume: f64, start: NaiveTime, end: NaiveTime, quiet_volume: f64) -> VolumePolicy {
+        VolumePolicy { max_volume, quiet_hours: Some(QuietHours { start, end, max_volume: quiet_volume }) }
+    }
+
+    fn assert_limit(policy: &VolumePolicy, time: NaiveTime, limit: f64) {
+        let actual = policy.get_limit(time);
+        assert!((actual - limit).abs() < 1e-9, "limit at {} is {} instead of {}", time, actual, limit);
+    }
+
+    #[test]
+    fn uses_max_volume_without_quiet_hours() {
+        let policy = VolumePolicy { max_volume: 0.8, quiet_hours: None };
+
+        assert_limit(&policy, time(3, 0), 0.8);
+        assert_limit(&policy, time(21, 0), 0.8);
+    }
+
+    #[test]
+    fn limits_volume_within_quiet_hours_of_a_day() {
+        let policy = policy(0.8, time(13, 0), time(15, 0), 0.3);
+
+        assert_limit(&policy, time(12, 59), 0.8);
+        assert_limit(&policy, time(14, 0), 0.3);
+        assert_limit(&policy, time(14, 59), 0.3);
+        assert_limit(&policy, time(15, 0), 0.8);
+    }
+
+    #[test]
+    fn limits_volume_within_quiet_hours_over_midnight() {
+        let policy = policy(0.8, time(19, 0), time(7, 0), 0.3);
+
+        assert_limit(&policy, time(18, 59), 0.8);
+        assert_limit(&policy, time(23, 0), 0.3);
+        assert_limit(&policy, time(0, 0), 0.3);
+        assert_limit(&policy, time(6, 59), 0.3);
+        assert_limit(&policy, time(7, 0), 0.8);
+        assert_limit(&policy, time(12, 0), 0.8);
+    }
+
+    #[test]
+    fn lowers_limit_gradually_when_quiet_hours_start() {
+        let policy = policy(0.8, time(19, 0), time(7, 0), 0.3);
+
+        assert_limit(&policy, time(19, 0), 0.8);
+        assert_limit(&policy, time(19, 5), 0.55);
+        assert_limit(&policy, time(19, 10), 0.3);
+    }
+
+    #[test]
+    fn lowers_limit_gradually_over_midnight() {
+        let policy = policy(0.8, time(23, 55), time(7, 0), 0.3);
+
+        assert_limit(&policy, time(23, 55), 0.8);
+        assert_limit(&policy, time(0, 0), 0.55);
+        assert_limit(&policy, time(0, 5), 0.3);
+    }
+
+    #[test]
+    fn keeps_limit_of_quiet_hours_below_max_volume() {
+        let policy = policy(0.5, time(19, 0), time(7, 0), 0.8);
+
+        assert_limit(&policy, time(19, 5), 0.5);
+        assert_limit(&policy, time(22, 0), 0.5);
+        assert_limit(&policy, time(12, 0), 0.5);
+    }
+}
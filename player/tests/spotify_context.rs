@@ -0,0 +1,239 @@
+use chrono::Utc;
+use fixture_server::{fixture, serve, Fixture};
+use rspotify::{AuthCodeSpotify, Config, Credentials, OAuth, Token};
+
+use database::model::library_entry::{Model as LibraryEntry, RepeatMode, Variant};
+use database::model::track_source::Model as TrackSource;
+use player::{get_track_id, resolve_context, PlaybackMode, Queue};
+
+const ALBUM_ID: &str = "4aawyAB9vmqN3uQ7FjRGTy";
+const PLAYLIST_ID: &str = "37i9dQZF1DXcBWIGoYBM5M";
+const SHOW_ID: &str = "5CfCWKI5pZ28U0uOzXkDHe";
+
+/// Response of the Spotify Web API
+fn json(path: &str, body: String) -> Fixture {
+    fixture(path, "application/json", body)
+}
+
+fn client(base_url: &str) -> AuthCodeSpotify {
+    let token = Token {
+        access_token: "token".to_string(),
+        expires_at: Some(Utc::now() + chrono::Duration::hours(1)),
+        ..Default::default()
+    };
+    let config = Config { api_base_url: format!("{}/v1/", base_url), token_refreshing: false, ..Default::default() };
+    AuthCodeSpotify::from_token_with_config(token, Credentials::new_pkce("client"), OAuth::default(), config)
+}
+
+fn page(items: &[String]) -> String {
+    format!(
+        r#"{{"href": "", "items": [{}], "limit": 50, "next": null, "offset": 0, "previous": null, "total": {}}}"#,
+        items.join(","),
+        items.len()
+    )
+}
+
+fn simplified_track(id: &str, name: &str, duration_ms: u32, is_playable: bool) -> String {
+    format!(
+        r#"{{"artists": [], "disc_number": 1, "duration_ms": {duration_ms}, "explicit": false, "external_urls": {{}},
+            "href": null, "id": "{id}", "is_local": false, "is_playable": {is_playable}, "name": "{name}",
+            "preview_url": null, "track_number": 1, "type": "track"}}"#
+    )
+}
+
+fn playlist_track(id: Option<&str>, name: &str, duration_ms: u32) -> String {
+    let id = id.map(|id| format!(r#""{}""#, id)).unwrap_or("null".to_string());
+    format!(
+        r#"{{"added_at": null, "is_local": {is_local}, "track": {{
+            "album": {{"album_type": "album", "artists": [], "available_markets": [], "external_urls": {{}},
+                "href": null, "id": null, "images": [
+                    {{"height": 640, "url": "https://i.scdn.co/image/large", "width": 640}},
+                    {{"height": 300, "url": "https://i.scdn.co/image/medium", "width": 300}},
+                    {{"height": 64, "url": "https://i.scdn.co/image/small", "width": 64}}
+                ], "name": "Album", "release_date": "2020", "release_date_precision": "year"}},
+            "artists": [], "available_markets": [], "disc_number": 1, "duration_ms": {duration_ms}, "explicit": false,
+            "external_ids": {{}}, "external_urls": {{}}, "href": null, "id": {id}, "is_local": {is_local},
+            "is_playable": true, "name": "{name}", "popularity": 0, "preview_url": null, "track_number": 1,
+            "type": "track"}}}}"#,
+        is_local = id == "null",
+    )
+}
+
+fn episode(id: &str, name: &str, duration_ms: u32) -> String {
+    format!(
+        r#"{{"audio_preview_url": null, "description": "", "duration_ms": {duration_ms}, "explicit": false,
+            "external_urls": {{}}, "href": "", "id": "{id}",
+            "images": [{{"height": 640, "url": "https://i.scdn.co/image/episode", "width": 640}}],
+            "is_externally_hosted": false, "is_playable": true, "language": "de", "languages": ["de"],
+            "name": "{name}", "release_date": "2024-01-01", "release_date_precision": "day", "type": "episode"}}"#
+    )
+}
+
+fn library_entry(id: i32, name: &str, spotify_type: &str, spotify_id: &str) -> LibraryEntry {
+    LibraryEntry {
+        id,
+        parent_id: Some(1),
+        variant: Variant::Spotify,
+        name: name.to_string(),
+        image: Some(vec![1, 2, 3]),
+        played_at: None,
+        sort_key: 0,
+        shuffle: false,
+        repeat_mode: RepeatMode::Off,
+        album_gain: None,
+        pinned: false,
+        children: None,
+        track_source: Some(TrackSource {
+            id,
+            library_entry_id: id,
+            title: name.to_string(),
+            url: None,
+            file_hash: None,
+            spotify_id: Some(spotify_id.to_string()),
+            spotify_type: Some(spotify_type.to_string()),
+            gain: None,
+            published_at: None,
+            episode_limit: None,
+        }),
+        parent_name: None,
+        parent_image: None,
+        duration_ms: None,
+    }
+}
+
+fn spotify_source(library_entry: &LibraryEntry) -> (String, String) {
+    let track_source = library_entry.track_source.as_ref().unwrap();
+    (track_source.spotify_type.clone().unwrap(), track_source.spotify_id.clone().unwrap())
+}
+
+#[test]
+fn resolves_playable_album_tracks() {
+    let base_url = serve(|_| {
+        vec![json(
+            &format!("/v1/albums/{}/tracks", ALBUM_ID),
+            page(&[
+                simplified_track("6rqhFgbbKwnb9MLmUQDhG6", "First", 215000, true),
+                simplified_track("1301WleyT98MSxVHPZCA6M", "Unavailable", 180000, false),
+                simplified_track("3n3Ppam7vgaVa1iaRUc9Lp", "Second", 192500, true),
+            ]),
+        )]
+    });
+    let album = library_entry(7, "Album", "album", ALBUM_ID);
+
+    let tracks = resolve_context(&client(&base_url), &album).unwrap().unwrap();
+
+    let names = tracks.iter().map(|track| track.library_entry.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, vec!["First", "Second"]);
+    let first = &tracks[0].library_entry;
+    assert_eq!(first.id, album.id);
+    assert_eq!(first.duration_ms, Some(215000));
+    assert_eq!(first.parent_name.as_deref(), Some("Album"));
+    assert_eq!(first.parent_image, album.image);
+    assert_eq!(first.image, None);
+    assert_eq!(spotify_source(first), ("track".to_string(), "6rqhFgbbKwnb9MLmUQDhG6".to_string()));
+    assert_eq!(tracks[0].cover_url, None);
+}
+
+#[test]
+fn resolves_playlist_tracks_with_covers() {
+    let base_url = serve(|_| {
+        vec![json(
+            &format!("/v1/playlists/{}/tracks", PLAYLIST_ID),
+            page(&[
+                playlist_track(Some("6rqhFgbbKwnb9MLmUQDhG6"), "First", 215000),
+                playlist_track(None, "Local file", 100000),
+                playlist_track(Some("3n3Ppam7vgaVa1iaRUc9Lp"), "Second", 192500),
+            ]),
+        )]
+    });
+    let playlist = library_entry(8, "Playlist", "playlist", PLAYLIST_ID);
+
+    let tracks = resolve_context(&client(&base_url), &playlist).unwrap().unwrap();
+
+    let names = tracks.iter().map(|track| track.library_entry.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, vec!["First", "Second"]);
+    assert_eq!(tracks[1].library_entry.duration_ms, Some(192500));
+    assert_eq!(tracks[1].cover_url.as_deref(), Some("https://i.scdn.co/image/medium"));
+}
+
+#[test]
+fn resolves_show_episodes() {
+    let base_url = serve(|_| {
+        vec![json(
+            &format!("/v1/shows/{}/episodes", SHOW_ID),
+            page(&[
+                episode("512ojhOuo1ktJprKbVcKyQ", "Newest", 1800000),
+                episode("0Q86acNRm6V9GYx55SXKwf", "Older", 1200000),
+            ]),
+        )]
+    });
+    let show = library_entry(9, "Show", "show", SHOW_ID);
+
+    let tracks = resolve_context(&client(&base_url), &show).unwrap().unwrap();
+
+    assert_eq!(tracks.len(), 2);
+    assert_eq!(spotify_source(&tracks[0].library_entry), ("episode".to_string(), "512ojhOuo1ktJprKbVcKyQ".to_string()));
+    assert_eq!(tracks[1].library_entry.duration_ms, Some(1200000));
+    assert_eq!(tracks[1].cover_url.as_deref(), Some("https://i.scdn.co/image/episode"));
+}
+
+#[test]
+fn tells_resolved_tracks_apart() {
+    let base_url = serve(|_| {
+        vec![json(
+            &format!("/v1/albums/{}/tracks", ALBUM_ID),
+            page(&[
+                simplified_track("6rqhFgbbKwnb9MLmUQDhG6", "First", 215000, true),
+                simplified_track("3n3Ppam7vgaVa1iaRUc9Lp", "Second", 192500, true),
+            ]),
+        )]
+    });
+    let album = library_entry(7, "Album", "album", ALBUM_ID);
+
+    let tracks = resolve_context(&client(&base_url), &album).unwrap().unwrap();
+
+    assert_eq!(get_track_id(&tracks[0].library_entry), "7_6rqhFgbbKwnb9MLmUQDhG6");
+    assert_eq!(get_track_id(&tracks[1].library_entry), "7_3n3Ppam7vgaVa1iaRUc9Lp");
+}
+
+#[test]
+fn leaves_single_tracks_alone() {
+    // Nothing is served, so any request would fail
+    let base_url = serve(|_| vec![]);
+    let track = library_entry(10, "Track", "track", "6rqhFgbbKwnb9MLmUQDhG6");
+
+    assert!(resolve_context(&client(&base_url), &track).unwrap().is_none());
+}
+
+#[test]
+fn fails_if_the_api_fails() {
+    let base_url = serve(|_| vec![]);
+    let album = library_entry(7, "Album", "album", ALBUM_ID);
+
+    assert!(resolve_context(&client(&base_url), &album).is_err());
+}
+
+#[test]
+fn skips_through_resolved_tracks() {
+    let before = library_entry(1, "Before", "track", "6rqhFgbbKwnb9MLmUQDhG6");
+    let album = library_entry(2, "Album", "album", ALBUM_ID);
+    let after = library_entry(3, "After", "track", "3n3Ppam7vgaVa1iaRUc9Lp");
+    let mut first = album.clone();
+    first.name = "First".to_string();
+    let mut second = album.clone();
+    second.name = "Second".to_string();
+
+    let mut queue = Queue::from_tracks(vec![before, album, after], Some(2), PlaybackMode::default());
+    assert_eq!(queue.next().unwrap().name, "Album");
+    assert_eq!(queue.expand_current(vec![first, second]).unwrap().name, "First");
+
+    let names = queue.get_tracks().into_iter().map(|track| track.name).collect::<Vec<_>>();
+    assert_eq!(names, vec!["Before", "First", "Second", "After"]);
+    assert_eq!(queue.next().unwrap().name, "Second");
+    assert_eq!(queue.next().unwrap().name, "After");
+    assert_eq!(queue.prev().unwrap().name, "Second");
+    assert_eq!(queue.prev().unwrap().name, "First");
+    assert_eq!(queue.prev().unwrap().name, "Before");
+    assert_eq!(queue.skip_to(2).unwrap().name, "Second");
+    assert_eq!(queue.next().unwrap().name, "After");
+}
@@ -0,0 +1,66 @@
+use evdev::{Device, EventSummary, KeyCode};
+
+use crate::card_reader::CardInput;
+
+/// USB-HID reader, e.g. a RC522 acting as keyboard, which types the uid of a card followed by enter
+pub struct EvdevInput {
+    device: Device,
+    typed: String,
+}
+
+impl EvdevInput {
+    pub fn open(path: &str) -> Result<Self, String> {
+        let mut device = Device::open(path).map_err(|error| format!("could not open {}: {}", path, error))?;
+        // Otherwise the typed uids end up in the user interface as key presses
+        device.grab().map_err(|error| format!("could not grab {}: {}", path, error))?;
+
+        Ok(Self { device, typed: String::new() })
+    }
+}
+
+impl CardInput for EvdevInput {
+    fn read_card(&mut self) -> Result<Option<String>, String> {
+        loop {
+            let events = self.device.fetch_events().map_err(|error| error.to_string())?;
+            for event in events {
+                // Only key presses are relevant, not their release or repetition
+                let key = match event.destructure() {
+                    EventSummary::Key(_, key, 1) => key,
+                    _ => continue,
+                };
+                if key == KeyCode::KEY_ENTER || key == KeyCode::KEY_KPENTER {
+                    let card_uid = std::mem::take(&mut self.typed);
+                    if !card_uid.is_empty() {
+                        return Ok(Some(card_uid));
+                    }
+                } else if let Some(char) = to_char(key) {
+                    self.typed.push(char);
+                }
+            }
+        }
+    }
+}
+
+fn to_char(key: KeyCode) -> Option<char> {
+    let char = match key {
+        KeyCode::KEY_0 | KeyCode::KEY_KP0 => '0',
+        KeyCode::KEY_1 | KeyCode::KEY_KP1 => '1',
+        KeyCode::KEY_2 | KeyCode::KEY_KP2 => '2',
+        KeyCode::KEY_3 | KeyCode::KEY_KP3 => '3',
+        KeyCode::KEY_4 | KeyCode::KEY_KP4 => '4',
+        KeyCode::KEY_5 | KeyCode::KEY_KP5 => '5',
+        KeyCode::KEY_6 | KeyCode::KEY_KP6 => '6',
+        KeyCode::KEY_7 | KeyCode::KEY_KP7 => '7',
+        KeyCode::KEY_8 | KeyCode::KEY_KP8 => '8',
+        KeyCode::KEY_9 | KeyCode::KEY_KP9 => '9',
+        // Some readers type the uid in hex
+        KeyCode::KEY_A => 'A',
+        KeyCode::KEY_B => 'B',
+        KeyCode::KEY_C => 'C',
+        KeyCode::KEY_D => 'D',
+        KeyCode::KEY_E => 'E',
+        KeyCode::KEY_F => 'F',
+        _ => return None,
+    };
+    Some(char)
+}
@@ -0,0 +1,62 @@
+use std::fs::File;
+use std::io::{stdin, BufRead, BufReader, Read};
+use std::os::unix::fs::FileTypeExt;
+use std::path::PathBuf;
+
+use crate::card_reader::CardInput;
+
+/// Reads one card uid per line. Stands in for a real reader during development, e.g. by writing uids into a named
+/// pipe created by `mkfifo`.
+pub struct FileInput {
+    path: Option<PathBuf>,
+    reader: BufReader<Box<dyn Read + Send>>,
+}
+
+impl FileInput {
+    pub fn open(path: &str) -> Result<Self, String> {
+        let path = PathBuf::from(path);
+        let file = File::open(&path).map_err(|error| format!("could not open {:?}: {}", path, error))?;
+
+        Ok(Self { path: Some(path), reader: BufReader::new(Box::new(file)) })
+    }
+
+    pub fn stdin() -> Self {
+        Self { path: None, reader: BufReader::new(Box::new(stdin())) }
+    }
+
+    // A named pipe reaches its end whenever the writer closes it, so it is opened again to wait for the next one
+    fn reopen(&mut self) -> Result<bool, String> {
+        let path = match self.path.as_ref() {
+            Some(path) => path,
+            None => return Ok(false),
+        };
+        let is_fifo = path.metadata().map(|metadata| metadata.file_type().is_fifo()).unwrap_or(false);
+        if !is_fifo {
+            return Ok(false);
+        }
+
+        let file = File::open(path).map_err(|error| format!("could not open {:?}: {}", path, error))?;
+        self.reader = BufReader::new(Box::new(file));
+        Ok(true)
+    }
+}
+
+impl CardInput for FileInput {
+    fn read_card(&mut self) -> Result<Option<String>, String> {
+        loop {
+            let mut line = String::new();
+            let read = self.reader.read_line(&mut line).map_err(|error| error.to_string())?;
+            if read == 0 {
+                if self.reopen()? {
+                    continue;
+                }
+                return Ok(None);
+            }
+
+            let card_uid = line.trim();
+            if !card_uid.is_empty() {
+                return Ok(Some(card_uid.to_string()));
+            }
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc::unbounded_channel;
+use tracing::{debug, error, info, warn};
+
+use database::{CardMappingRepository, DatabaseConnection};
+
+use crate::card_reader::evdev::EvdevInput;
+use crate::card_reader::file::FileInput;
+use crate::card_reader::serial::SerialInput;
+use crate::state::{Action, Dispatcher};
+
+mod evdev;
+mod file;
+mod serial;
+
+// Readers report a card again and again while it lies on them
+const REPEAT_DELAY: Duration = Duration::from_secs(3);
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Source of card uids. Reading blocks until the next card is placed on the reader.
+pub trait CardInput: Send {
+    /// Returns the uid of the next card or None, if the input is closed
+    fn read_card(&mut self) -> Result<Option<String>, String>;
+}
+
+/// Opens the input configured by CARD_READER, e.g. "evdev:/dev/input/event0", "serial:/dev/ttyUSB0:9600",
+/// "file:/tmp/cards" or "stdin"
+fn open_input() -> Result<Option<Box<dyn CardInput>>, String> {
+    let config = match std::env::var("CARD_READER") {
+        Ok(config) if !config.is_empty() => config,
+        _ => return Ok(None),
+    };
+
+    let (kind, target) = config.split_once(':').unwrap_or((config.as_str(), ""));
+    let input: Box<dyn CardInput> = match kind {
+        "evdev" => Box::new(EvdevInput::open(target)?),
+        "serial" => Box::new(SerialInput::open(target)?),
+        "file" => Box::new(FileInput::open(target)?),
+        "stdin" => Box::new(FileInput::stdin()),
+        _ => return Err(format!("unknown card reader '{}'", kind)),
+    };
+
+    Ok(Some(input))
+}
+
+/// Reads cards from the configured input and plays the library entries assigned to them
+pub fn start(connection: DatabaseConnection, dispatcher: Arc<Mutex<Dispatcher>>) {
+    let mut input = match open_input() {
+        Ok(Some(input)) => input,
+        Ok(None) => {
+            info!("No card reader configured");
+            return;
+        }
+        Err(error) => {
+            error!("Could not open card reader: {}", error);
+            return;
+        }
+    };
+
+    let (sender, mut receiver) = unbounded_channel::<String>();
+    std::thread::spawn(move || loop {
+        match input.read_card() {
+            Ok(Some(card_uid)) => {
+                if sender.send(card_uid).is_err() {
+                    break;
+                }
+            }
+            Ok(None) => {
+                info!("Card reader closed");
+                break;
+            }
+            Err(error) => {
+                error!("Could not read card: {}", error);
+                std::thread::sleep(RETRY_DELAY);
+            }
+        }
+    });
+
+    tokio::spawn(async move {
+        let mut last_card: Option<(String, Instant)> = None;
+        while let Some(card_uid) = receiver.recv().await {
+            if let Some((last_uid, read_at)) = last_card.as_ref() {
+                if *last_uid == card_uid && read_at.elapsed() < REPEAT_DELAY {
+                    last_card = Some((card_uid, Instant::now()));
+                    continue;
+                }
+            }
+            last_card = Some((card_uid.clone(), Instant::now()));
+
+            if let Some(action) = get_play_action(&connection, &card_uid).await {
+                let dispatcher = dispatcher.lock().unwrap();
+                dispatcher.dispatch_action(Action::TrackActivity);
+                dispatcher.dispatch_action(action);
+            }
+        }
+    });
+}
+
+async fn get_play_action(connection: &DatabaseConnection, card_uid: &str) -> Option<Action> {
+    info!("Card {} placed on reader", card_uid);
+    let card = CardMappingRepository::mark_seen(connection, card_uid)
+        .await
+        .map_err(|error| error!("Could not save card {}: {}", card_uid, error))
+        .ok()?;
+    let library_entry_id = match card.library_entry_id {
+        Some(library_entry_id) => library_entry_id,
+        None => {
+            debug!("Card {} is not assigned yet", card_uid);
+            return None;
+        }
+    };
+
+    Action::play_library_entry(connection, library_entry_id)
+        .await
+        .map_err(|error| warn!("Could not play card {}: {}", card_uid, error))
+        .ok()
+}
@@ -0,0 +1,51 @@
+use std::io::{BufRead, BufReader, ErrorKind};
+use std::time::Duration;
+
+use serialport::SerialPort;
+
+use crate::card_reader::CardInput;
+
+const DEFAULT_BAUD_RATE: u32 = 9600;
+
+/// Reader connected via a serial port, e.g. a RC522 behind a USB serial adapter, sending one uid per line
+pub struct SerialInput {
+    reader: BufReader<Box<dyn SerialPort>>,
+}
+
+impl SerialInput {
+    /// Opens the port given as "<path>" or "<path>:<baud rate>"
+    pub fn open(target: &str) -> Result<Self, String> {
+        let (path, baud_rate) = match target.rsplit_once(':') {
+            Some((path, baud_rate)) => {
+                (path, baud_rate.parse().map_err(|_| format!("invalid baud rate '{}'", baud_rate))?)
+            }
+            None => (target, DEFAULT_BAUD_RATE),
+        };
+        let port = serialport::new(path, baud_rate)
+            .timeout(Duration::from_secs(60))
+            .open()
+            .map_err(|error| format!("could not open {}: {}", path, error))?;
+
+        Ok(Self { reader: BufReader::new(port) })
+    }
+}
+
+impl CardInput for SerialInput {
+    fn read_card(&mut self) -> Result<Option<String>, String> {
+        loop {
+            let mut line = String::new();
+            match self.reader.read_line(&mut line) {
+                Ok(0) => return Ok(None),
+                Ok(_) => {}
+                // Nobody placed a card within the timeout, which is fine
+                Err(error) if error.kind() == ErrorKind::TimedOut => continue,
+                Err(error) => return Err(error.to_string()),
+            }
+
+            let card_uid = line.trim();
+            if !card_uid.is_empty() {
+                return Ok(Some(card_uid.to_string()));
+            }
+        }
+    }
+}
@@ -2,6 +2,8 @@ use crate::components::player_bar::widget::PlayerBarWidget;
 use crate::components::{Children, Component};
 use crate::state::{Action, Dispatcher, Event, EventHandler, State};
 use crate::util::debouncer::Debouncer;
+use gtk4::glib;
+use player::SleepTimer;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
@@ -18,9 +20,12 @@ impl EventHandler for PlayerBarComponent {
                 self.update_play_state();
                 self.update_track();
             }
+            Event::StreamTitleChanged => self.update_track(),
             Event::PlayStateChanged => self.update_play_state(),
             Event::ProgressChanged => self.update_progress(),
             Event::VolumeChanged => self.update_volume(),
+            Event::PlaybackModeChanged => self.update_playback_mode(),
+            Event::SleepTimerChanged => self.update_sleep_timer(),
             _ => {}
         }
     }
@@ -44,7 +49,7 @@ impl Component<Option<()>> for PlayerBarComponent {
 
     #[allow(refining_impl_trait)]
     fn render(
-        _state: Arc<Mutex<State>>,
+        state: Arc<Mutex<State>>,
         dispatcher: Arc<Mutex<Dispatcher>>,
         _params: Option<()>,
     ) -> (PlayerBarWidget, Children) {
@@ -68,6 +73,18 @@ impl Component<Option<()>> for PlayerBarComponent {
                 dispatcher.lock().unwrap().dispatch_action(Action::NextTrack);
             });
         }
+        {
+            let dispatcher = dispatcher.clone();
+            widget.connect_shuffle_clicked(move || {
+                dispatcher.lock().unwrap().dispatch_action(Action::ToggleShuffle);
+            });
+        }
+        {
+            let dispatcher = dispatcher.clone();
+            widget.connect_repeat_clicked(move || {
+                dispatcher.lock().unwrap().dispatch_action(Action::ToggleRepeat);
+            });
+        }
         {
             let dispatcher = dispatcher.clone();
             let debouncer = Debouncer::new(Duration::from_millis(500), move |progress| {
@@ -83,6 +100,42 @@ impl Component<Option<()>> for PlayerBarComponent {
             widget.connect_volume_change(move |volume: f64| debouncer.add(volume));
         }
 
+        {
+            let dispatcher = dispatcher.clone();
+            widget.connect_sleep_extend_clicked(move || {
+                dispatcher.lock().unwrap().dispatch_action(Action::ExtendSleepTimer);
+            });
+        }
+        {
+            let dispatcher = dispatcher.clone();
+            widget.connect_sleep_track_clicked(move || {
+                dispatcher.lock().unwrap().dispatch_action(Action::SleepAfterTrack);
+            });
+        }
+        {
+            let dispatcher = dispatcher.clone();
+            widget.connect_sleep_folder_clicked(move || {
+                dispatcher.lock().unwrap().dispatch_action(Action::SleepAtEndOfFolder);
+            });
+        }
+        {
+            let dispatcher = dispatcher.clone();
+            widget.connect_sleep_off_clicked(move || {
+                dispatcher.lock().unwrap().dispatch_action(Action::CancelSleepTimer);
+            });
+        }
+        {
+            // Counts down the remaining time of the sleep timer
+            let widget = widget.clone();
+            glib::timeout_add_local(Duration::from_secs(1), move || {
+                let sleep_timer = state.lock().unwrap().sleep_timer;
+                if matches!(sleep_timer, Some(SleepTimer::Until(_))) {
+                    widget.set_sleep_timer(sleep_timer);
+                }
+                glib::ControlFlow::Continue
+            });
+        }
+
         (widget, vec![])
     }
 
@@ -91,6 +144,8 @@ impl Component<Option<()>> for PlayerBarComponent {
         self.update_track();
         self.update_play_state();
         self.update_volume();
+        self.update_playback_mode();
+        self.update_sleep_timer();
     }
 
     #[allow(refining_impl_trait)]
@@ -102,7 +157,7 @@ impl Component<Option<()>> for PlayerBarComponent {
 impl PlayerBarComponent {
     pub fn update_progress(&self) {
         let state = self.state.lock().unwrap();
-        self.widget.set_progress(state.progress);
+        self.widget.set_progress(state.progress, state.live_position);
     }
 
     pub fn update_track(&self) {
@@ -111,7 +166,15 @@ impl PlayerBarComponent {
             self.widget.set_visibility(true);
             self.widget.set_image(playing_library_entry.image.clone().or(playing_library_entry.parent_image.clone()));
             self.widget.set_track_name(playing_library_entry.name.clone());
-            self.widget.set_folder_name(playing_library_entry.parent_name.clone().unwrap_or("".to_string()));
+            // Internet radios show the song they play instead of their folder
+            let subtitle = state
+                .stream_title
+                .as_ref()
+                .filter(|(library_entry_id, title)| *library_entry_id == playing_library_entry.id && !title.is_empty())
+                .map(|(_, title)| title.clone())
+                .or(playing_library_entry.parent_name.clone())
+                .unwrap_or("".to_string());
+            self.widget.set_folder_name(subtitle);
         } else {
             self.widget.set_visibility(false);
         }
@@ -120,10 +183,22 @@ impl PlayerBarComponent {
     pub fn update_play_state(&self) {
         let state = self.state.lock().unwrap();
         self.widget.set_paused(state.paused);
+        self.widget.set_buffering(state.buffering);
     }
 
     pub fn update_volume(&self) {
         let state = self.state.lock().unwrap();
-        self.widget.set_volume(state.volume);
+        self.widget.set_volume(state.volume, state.volume_limit);
+    }
+
+    pub fn update_playback_mode(&self) {
+        let state = self.state.lock().unwrap();
+        self.widget.set_shuffle(state.playback_mode.shuffle);
+        self.widget.set_repeat(state.playback_mode.repeat);
+    }
+
+    pub fn update_sleep_timer(&self) {
+        let state = self.state.lock().unwrap();
+        self.widget.set_sleep_timer(state.sleep_timer);
     }
 }
@@ -4,8 +4,12 @@ use gtk4::glib::{object_subclass, Bytes, Propagation};
 use gtk4::prelude::*;
 use gtk4::subclass::prelude::*;
 use gtk4::{gio, glib, CompositeTemplate};
+use std::time::{Duration, Instant};
 use tracing::warn;
 
+use database::model::library_entry::RepeatMode;
+use player::SleepTimer;
+
 #[derive(Default, CompositeTemplate)]
 #[template(file = "./player_bar.ui")]
 pub struct PlayerBarWidgetImp {
@@ -14,18 +18,38 @@ pub struct PlayerBarWidgetImp {
     #[template_child]
     progress_bar: TemplateChild<gtk4::Scale>,
     #[template_child]
+    elapsed_label: TemplateChild<gtk4::Label>,
+    #[template_child]
     image: TemplateChild<gtk4::Picture>,
     #[template_child]
     track_name: TemplateChild<gtk4::Label>,
     #[template_child]
     folder_name: TemplateChild<gtk4::Label>,
     #[template_child]
+    buffering_spinner: TemplateChild<gtk4::Spinner>,
+    #[template_child]
+    shuffle_button: TemplateChild<gtk4::Button>,
+    #[template_child]
     back_button: TemplateChild<gtk4::Button>,
     #[template_child]
     play_toggle_button: TemplateChild<gtk4::Button>,
     #[template_child]
     forward_button: TemplateChild<gtk4::Button>,
     #[template_child]
+    repeat_button: TemplateChild<gtk4::Button>,
+    #[template_child]
+    sleep_button: TemplateChild<gtk4::MenuButton>,
+    #[template_child]
+    sleep_label: TemplateChild<gtk4::Label>,
+    #[template_child]
+    sleep_extend_button: TemplateChild<gtk4::Button>,
+    #[template_child]
+    sleep_track_button: TemplateChild<gtk4::Button>,
+    #[template_child]
+    sleep_folder_button: TemplateChild<gtk4::Button>,
+    #[template_child]
+    sleep_off_button: TemplateChild<gtk4::Button>,
+    #[template_child]
     volume_button: TemplateChild<gtk4::ScaleButton>,
 }
 
@@ -63,8 +87,21 @@ impl PlayerBarWidget {
         self.imp().wrapper.set_visible(visible);
     }
 
-    pub fn set_progress(&self, progress: f64) {
-        self.imp().progress_bar.adjustment().set_value(progress);
+    /// Shows the progress in percent. Live streams can not be seeked, so they show the time they are playing instead.
+    pub fn set_progress(&self, progress: f64, live_position: Option<Duration>) {
+        self.imp().progress_bar.set_visible(live_position.is_none());
+        self.imp().elapsed_label.set_visible(live_position.is_some());
+        match live_position {
+            Some(position) => {
+                let seconds = position.as_secs();
+                let label = match seconds / 3600 {
+                    0 => format!("Live · {}:{:02}", seconds / 60, seconds % 60),
+                    hours => format!("Live · {}:{:02}:{:02}", hours, seconds / 60 % 60, seconds % 60),
+                };
+                self.imp().elapsed_label.set_label(&label);
+            }
+            None => self.imp().progress_bar.adjustment().set_value(progress),
+        }
     }
 
     pub fn set_image(&self, buffer: Option<Vec<u8>>) {
@@ -95,12 +132,58 @@ impl PlayerBarWidget {
         self.imp().play_toggle_button.set_icon_name(icon_name);
     }
 
-    pub fn set_volume(&self, volume: f64) {
+    /// Spins while a remote track waits for its connection to come back
+    pub fn set_buffering(&self, buffering: bool) {
+        self.imp().buffering_spinner.set_visible(buffering);
+        self.imp().buffering_spinner.set_spinning(buffering);
+    }
+
+    pub fn set_shuffle(&self, shuffle: bool) {
+        if shuffle {
+            self.imp().shuffle_button.add_css_class("active");
+        } else {
+            self.imp().shuffle_button.remove_css_class("active");
+        }
+    }
+
+    pub fn set_repeat(&self, repeat: RepeatMode) {
+        let icon_name = if matches!(repeat, RepeatMode::One) { "repeat-one" } else { "repeat" };
+        self.imp().repeat_button.set_icon_name(icon_name);
+        if matches!(repeat, RepeatMode::Off) {
+            self.imp().repeat_button.remove_css_class("active");
+        } else {
+            self.imp().repeat_button.add_css_class("active");
+        }
+    }
+
+    /// Sets the volume and limits the slider to the highest volume currently allowed
+    pub fn set_volume(&self, volume: f64, limit: f64) {
         let adjustment = self.imp().volume_button.adjustment();
-        adjustment.set_value(volume);
+        adjustment.set_upper(limit);
+        adjustment.set_value(volume.min(limit));
         self.imp().volume_button.set_adjustment(&adjustment);
     }
 
+    /// Shows the remaining time or tracks of the sleep timer
+    pub fn set_sleep_timer(&self, sleep_timer: Option<SleepTimer>) {
+        let label = sleep_timer.map(|sleep_timer| match sleep_timer {
+            SleepTimer::Until(end) => {
+                let seconds = end.saturating_duration_since(Instant::now()).as_secs();
+                format!("{}:{:02}", seconds / 60, seconds % 60)
+            }
+            SleepTimer::Tracks(1) => "1 track".to_string(),
+            SleepTimer::Tracks(count) => format!("{} tracks", count),
+        });
+
+        self.imp().sleep_label.set_visible(label.is_some());
+        self.imp().sleep_label.set_label(label.as_deref().unwrap_or_default());
+        if sleep_timer.is_some() {
+            self.imp().sleep_button.add_css_class("active");
+        } else {
+            self.imp().sleep_button.remove_css_class("active");
+        }
+    }
+
     pub fn connect_seek(&self, callback: impl Fn(f64) + 'static) {
         self.imp().progress_bar.connect_change_value(move |_scale, _scroll_type, new_value| {
             callback(new_value);
@@ -120,6 +203,38 @@ impl PlayerBarWidget {
         self.imp().forward_button.connect_clicked(move |_| callback());
     }
 
+    pub fn connect_shuffle_clicked(&self, callback: impl Fn() + 'static) {
+        self.imp().shuffle_button.connect_clicked(move |_| callback());
+    }
+
+    pub fn connect_repeat_clicked(&self, callback: impl Fn() + 'static) {
+        self.imp().repeat_button.connect_clicked(move |_| callback());
+    }
+
+    pub fn connect_sleep_extend_clicked(&self, callback: impl Fn() + 'static) {
+        self.connect_sleep_option(&self.imp().sleep_extend_button, callback);
+    }
+
+    pub fn connect_sleep_track_clicked(&self, callback: impl Fn() + 'static) {
+        self.connect_sleep_option(&self.imp().sleep_track_button, callback);
+    }
+
+    pub fn connect_sleep_folder_clicked(&self, callback: impl Fn() + 'static) {
+        self.connect_sleep_option(&self.imp().sleep_folder_button, callback);
+    }
+
+    pub fn connect_sleep_off_clicked(&self, callback: impl Fn() + 'static) {
+        self.connect_sleep_option(&self.imp().sleep_off_button, callback);
+    }
+
+    fn connect_sleep_option(&self, button: &gtk4::Button, callback: impl Fn() + 'static) {
+        let sleep_button = self.imp().sleep_button.clone();
+        button.connect_clicked(move |_| {
+            sleep_button.popdown();
+            callback();
+        });
+    }
+
     pub fn connect_volume_change(&self, callback: impl Fn(f64) + 'static) {
         self.imp().volume_button.connect_value_changed(move |_scale, value| callback(value));
     }
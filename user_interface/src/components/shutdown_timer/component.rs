@@ -1,14 +1,9 @@
 use super::widget::ShutdownTimerWidget;
 use crate::components::{Children, Component};
 use crate::state::{Action, Dispatcher, Event, EventHandler, State};
-use chrono::Utc;
 use gtk4::prelude::IsA;
-use gtk4::{glib, Widget};
+use gtk4::Widget;
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
-
-const DISPLAY_OFF_TIME: i64 = 1 * 60;
-const SHUTDOWN_OFF_TIME: i64 = 60 * 60;
 
 pub struct ShutdownTimerComponent {
     children: Vec<Arc<Mutex<Box<dyn EventHandler>>>>,
@@ -49,30 +44,13 @@ impl Component<Option<()>> for ShutdownTimerComponent {
     ) -> (ShutdownTimerWidget, Children) {
         let widget = ShutdownTimerWidget::new();
 
-        {
-            let state = state.clone();
-            let dispatcher = dispatcher.clone();
-            widget.connect_clicked(move || {
-                let monitor_active = state.lock().expect("could not lock").monitor_active;
-                if !monitor_active {
-                    dispatcher.lock().expect("could not lock").dispatch_action(Action::ToggleMonitor(true));
-                }
-                dispatcher.lock().expect("could not lock").dispatch_action(Action::TrackActivity);
-            });
-        }
-
-        glib::timeout_add_local(Duration::from_secs(1), move || {
-            let last_activity = state.lock().expect("could not lock").last_activity;
+        // The timers themselves are handled by the power module, touching the display wakes it up
+        widget.connect_clicked(move || {
             let monitor_active = state.lock().expect("could not lock").monitor_active;
-            let now = Utc::now().timestamp();
-            if last_activity + DISPLAY_OFF_TIME < now && monitor_active {
-                dispatcher.lock().expect("could not lock").dispatch_action(Action::ToggleMonitor(false));
+            if !monitor_active {
+                dispatcher.lock().expect("could not lock").dispatch_action(Action::ToggleMonitor(true));
             }
-            if last_activity + SHUTDOWN_OFF_TIME < now {
-                dispatcher.lock().expect("could not lock").dispatch_action(Action::Shutdown);
-            }
-
-            glib::ControlFlow::Continue
+            dispatcher.lock().expect("could not lock").dispatch_action(Action::TrackActivity);
         });
 
         (widget, vec![])
@@ -0,0 +1,52 @@
+use std::sync::mpsc::Sender;
+
+use gpio_cdev::{Chip, EventRequestFlags, EventType, LineRequestFlags};
+use tracing::error;
+
+use crate::gpio_input::{GpioBackend, PinEvent};
+
+const CONSUMER: &str = "tinyghettobox";
+
+/// Watches pins through the linux gpio character device. The pins need a pull up, either by wiring or by
+/// `gpio=<pin>=ip,pu` in the config.txt of the Raspberry Pi.
+pub struct CdevBackend {
+    chip: Chip,
+}
+
+impl CdevBackend {
+    pub fn open(path: &str) -> Result<Self, String> {
+        let chip = Chip::new(path).map_err(|error| format!("could not open {}: {}", path, error))?;
+
+        Ok(Self { chip })
+    }
+}
+
+impl GpioBackend for CdevBackend {
+    fn watch(&mut self, pins: &[u32], sender: Sender<PinEvent>) -> Result<(), String> {
+        for &pin in pins {
+            let events = self
+                .chip
+                .get_line(pin)
+                .and_then(|line| line.events(LineRequestFlags::INPUT, EventRequestFlags::BOTH_EDGES, CONSUMER))
+                .map_err(|error| format!("could not watch pin {}: {}", pin, error))?;
+
+            let sender = sender.clone();
+            std::thread::spawn(move || {
+                for event in events {
+                    let active = match event {
+                        Ok(event) => event.event_type() == EventType::FallingEdge,
+                        Err(error) => {
+                            error!("Could not read pin {}: {}", pin, error);
+                            break;
+                        }
+                    };
+                    if sender.send(PinEvent { pin, active }).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,58 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::os::unix::fs::FileTypeExt;
+use std::path::PathBuf;
+use std::sync::mpsc::Sender;
+
+use tracing::{error, warn};
+
+use crate::gpio_input::{GpioBackend, PinEvent};
+
+/// Stands in for the gpio character device on machines without buttons. Reads lines like "17 1" to press or
+/// "17 0" to release the button on pin 17 from a file, e.g. a named pipe created by `mkfifo`.
+pub struct MockBackend {
+    path: PathBuf,
+}
+
+impl MockBackend {
+    pub fn new(path: &str) -> Self {
+        Self { path: PathBuf::from(path) }
+    }
+}
+
+impl GpioBackend for MockBackend {
+    fn watch(&mut self, pins: &[u32], sender: Sender<PinEvent>) -> Result<(), String> {
+        let path = self.path.clone();
+        let pins = pins.to_vec();
+
+        std::thread::spawn(move || loop {
+            // A named pipe ends whenever its writer closes it, so it is opened again to wait for the next one
+            let file = match File::open(&path) {
+                Ok(file) => file,
+                Err(error) => {
+                    error!("Could not open {:?}: {}", path, error);
+                    break;
+                }
+            };
+            for line in BufReader::new(file).lines().map_while(Result::ok) {
+                let event = line.split_once(' ').and_then(|(pin, level)| {
+                    Some(PinEvent { pin: pin.trim().parse().ok()?, active: level.trim() == "1" })
+                });
+                match event {
+                    Some(event) if pins.contains(&event.pin) => {
+                        if sender.send(event).is_err() {
+                            return;
+                        }
+                    }
+                    _ => warn!("Ignoring mocked pin change '{}'", line),
+                }
+            }
+            let is_fifo = path.metadata().map(|metadata| metadata.file_type().is_fifo()).unwrap_or(false);
+            if !is_fifo {
+                break;
+            }
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,452 @@
+use std::collections::HashMap;
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tracing::{debug, error, info};
+
+use database::model::system_config::Model as SystemConfig;
+use database::{DatabaseConnection, SystemConfigRepository};
+
+use crate::gpio_input::cdev::CdevBackend;
+use crate::gpio_input::mock::MockBackend;
+use crate::state::{Action, Dispatcher, State};
+
+mod cdev;
+mod mock;
+
+// Contacts of buttons bounce for a few milliseconds, so a level only counts once it stayed this long
+const DEBOUNCE_TIME: Duration = Duration::from_millis(50);
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+const VOLUME_STEP: f64 = 0.05;
+
+/// Level change of a pin. Buttons pull their pin low, so a low pin is active.
+#[derive(Clone, Copy, Debug)]
+pub struct PinEvent {
+    pub pin: u32,
+    pub active: bool,
+}
+
+pub trait GpioBackend {
+    /// Starts watching the pins in background, sending every level change to the sender
+    fn watch(&mut self, pins: &[u32], sender: Sender<PinEvent>) -> Result<(), String>;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Button {
+    PlayPause,
+    Next,
+    Prev,
+    VolumeUp,
+    VolumeDown,
+    PowerOff,
+}
+
+impl Button {
+    /// Returns the action of a short press, the power off button only acts when held
+    fn get_action(&self) -> Option<Action> {
+        match self {
+            Button::PlayPause => Some(Action::TogglePlay),
+            Button::Next => Some(Action::NextTrack),
+            Button::Prev => Some(Action::PrevTrack),
+            Button::VolumeUp => Some(Action::ChangeVolume(VOLUME_STEP)),
+            Button::VolumeDown => Some(Action::ChangeVolume(-VOLUME_STEP)),
+            Button::PowerOff => None,
+        }
+    }
+}
+
+struct PinConfig {
+    buttons: HashMap<u32, Button>,
+    // Clock and data pin
+    rotary_encoder: Option<(u32, u32)>,
+    // Pin of the button which shuts the box down when held this long
+    long_press: Option<(u32, Duration)>,
+}
+
+impl PinConfig {
+    fn new(config: &SystemConfig) -> Self {
+        let buttons = [
+            (config.play_pause_pin, Button::PlayPause),
+            (config.next_pin, Button::Next),
+            (config.prev_pin, Button::Prev),
+            (config.volume_up_pin, Button::VolumeUp),
+            (config.volume_down_pin, Button::VolumeDown),
+            (config.power_off_pin, Button::PowerOff),
+        ]
+        .into_iter()
+        .filter(|(pin, _)| *pin > 0)
+        .map(|(pin, button)| (pin as u32, button))
+        .collect();
+        let rotary_encoder = match (config.rotary_clk_pin, config.rotary_dt_pin) {
+            (clk, dt) if clk > 0 && dt > 0 => Some((clk as u32, dt as u32)),
+            _ => None,
+        };
+        let delay = Duration::from_secs(config.power_off_btn_delay.max(0) as u64);
+        // Without a power off button, holding play/pause shuts the box down instead
+        let long_press = match (config.power_off_pin, config.play_pause_pin) {
+            (power_off_pin, _) if power_off_pin > 0 => Some((power_off_pin as u32, delay)),
+            (_, play_pause_pin) if play_pause_pin > 0 && config.power_off_btn_delay > 0 => {
+                Some((play_pause_pin as u32, delay))
+            }
+            _ => None,
+        };
+
+        Self { buttons, rotary_encoder, long_press }
+    }
+
+    fn get_pins(&self) -> Vec<u32> {
+        let mut pins = self.buttons.keys().cloned().collect::<Vec<u32>>();
+        if let Some((clk, dt)) = self.rotary_encoder {
+            pins.push(clk);
+            pins.push(dt);
+        }
+        pins
+    }
+}
+
+/// Opens the backend configured by GPIO_CHIP, which is either the path of a gpio character device or
+/// "mock:<path>" to read pin changes from a file instead
+fn open_backend() -> Result<Box<dyn GpioBackend>, String> {
+    let chip = std::env::var("GPIO_CHIP").unwrap_or("/dev/gpiochip0".to_string());
+
+    match chip.strip_prefix("mock:") {
+        Some(path) => Ok(Box::new(MockBackend::new(path))),
+        None => Ok(Box::new(CdevBackend::open(&chip)?)),
+    }
+}
+
+/// Watches the buttons and the rotary encoder configured in the system config and dispatches their actions. Pin
+/// changes in the config take effect after a restart.
+pub async fn start(connection: DatabaseConnection, state: Arc<Mutex<State>>, dispatcher: Arc<Mutex<Dispatcher>>) {
+    let config = match SystemConfigRepository::get(&connection).await {
+        Ok(Some(config)) => PinConfig::new(&config),
+        Ok(None) => return,
+        Err(error) => {
+            error!("Could not load button config: {}", error);
+            return;
+        }
+    };
+    let pins = config.get_pins();
+    if pins.is_empty() {
+        info!("No buttons configured");
+        return;
+    }
+
+    let (sender, receiver) = channel::<PinEvent>();
+    if let Err(error) = open_backend().and_then(|mut backend| backend.watch(&pins, sender)) {
+        error!("Could not watch buttons: {}", error);
+        return;
+    }
+    info!("Watching buttons on pins {:?}", pins);
+
+    let on_action = move |action| {
+        let monitor_active = state.lock().expect("could not lock").monitor_active;
+        let dispatcher = dispatcher.lock().expect("could not lock");
+        if !monitor_active {
+            dispatcher.dispatch_action(Action::ToggleMonitor(true));
+        }
+        dispatcher.dispatch_action(Action::TrackActivity);
+        dispatcher.dispatch_action(action);
+    };
+    std::thread::spawn(move || InputHandler::new(config, Box::new(on_action)).run(receiver));
+}
+
+struct InputHandler {
+    config: PinConfig,
+    on_action: Box<dyn Fn(Action) + Send>,
+    // Last level reported for each pin and when it changed
+    levels: HashMap<u32, (bool, Instant)>,
+    // Levels of the buttons once they settled
+    settled_levels: HashMap<u32, bool>,
+    long_pressed_at: Option<Instant>,
+    long_press_handled: bool,
+}
+
+impl InputHandler {
+    fn new(config: PinConfig, on_action: Box<dyn Fn(Action) + Send>) -> Self {
+        Self {
+            config,
+            on_action,
+            levels: HashMap::new(),
+            settled_levels: HashMap::new(),
+            long_pressed_at: None,
+            long_press_handled: false,
+        }
+    }
+
+    fn run(mut self, receiver: Receiver<PinEvent>) {
+        loop {
+            match receiver.recv_timeout(POLL_INTERVAL) {
+                Ok(event) => self.on_event(event, Instant::now()),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            self.update(Instant::now());
+        }
+        info!("Stopped watching buttons");
+    }
+
+    fn on_event(&mut self, PinEvent { pin, active }: PinEvent, now: Instant) {
+        let previous = self.levels.get(&pin).is_some_and(|(level, _)| *level);
+        if previous == active {
+            return;
+        }
+        self.levels.insert(pin, (active, now));
+
+        if let Some((clk, dt)) = self.config.rotary_encoder {
+            // Each notch pulls the clock pin low once, the data pin tells the direction it was turned
+            if pin == clk && active {
+                let clockwise = !self.levels.get(&dt).is_some_and(|(level, _)| *level);
+                self.dispatch(Action::ChangeVolume(if clockwise { VOLUME_STEP } else { -VOLUME_STEP }));
+            }
+        }
+    }
+
+    /// Presses and releases the buttons whose level settled, so a bouncing contact counts once in both directions
+    fn update(&mut self, now: Instant) {
+        let settled = self
+            .config
+            .buttons
+            .iter()
+            .filter_map(|(pin, button)| {
+                let (active, changed_at) = *self.levels.get(pin)?;
+                let settled = self.settled_levels.get(pin).cloned().unwrap_or(false);
+                let is_settled = active != settled && now.duration_since(changed_at) >= DEBOUNCE_TIME;
+                is_settled.then_some((*pin, *button, active, changed_at))
+            })
+            .collect::<Vec<_>>();
+
+        for (pin, button, active, changed_at) in settled {
+            self.settled_levels.insert(pin, active);
+            let is_long_press = self.config.long_press.is_some_and(|(long_press_pin, _)| long_press_pin == pin);
+            if active {
+                debug!("Button {:?} pressed", button);
+            }
+            match (is_long_press, active) {
+                // The button waits for the release to tell a short from a long press
+                (true, true) => {
+                    self.long_pressed_at = Some(changed_at);
+                    self.long_press_handled = false;
+                }
+                (true, false) => {
+                    if self.long_pressed_at.take().is_some() && !self.long_press_handled {
+                        button.get_action().into_iter().for_each(|action| self.dispatch(action));
+                    }
+                }
+                (false, true) => button.get_action().into_iter().for_each(|action| self.dispatch(action)),
+                (false, false) => {}
+            }
+        }
+
+        self.check_long_press(now);
+    }
+
+    fn check_long_press(&mut self, now: Instant) {
+        let (pin, delay) = match self.config.long_press {
+            Some(long_press) if !self.long_press_handled => long_press,
+            _ => return,
+        };
+
+        if self.long_pressed_at.is_some_and(|pressed_at| now.duration_since(pressed_at) >= delay) {
+            info!("Button {:?} held for {:?}, shutting down", self.config.buttons.get(&pin), delay);
+            self.long_press_handled = true;
+            self.dispatch(Action::Shutdown);
+        }
+    }
+
+    fn dispatch(&self, action: Action) {
+        (self.on_action)(action);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::Write;
+
+    use super::*;
+
+    const PLAY_PAUSE_PIN: u32 = 5;
+    const POWER_OFF_PIN: u32 = 4;
+    const CLK_PIN: u32 = 20;
+    const DT_PIN: u32 = 21;
+    const HOLD_TIME: Duration = Duration::from_secs(2);
+
+    fn config(power_off_pin: Option<u32>) -> PinConfig {
+        let mut buttons = HashMap::from([(PLAY_PAUSE_PIN, Button::PlayPause)]);
+        if let Some(power_off_pin) = power_off_pin {
+            buttons.insert(power_off_pin, Button::PowerOff);
+        }
+        let long_press = Some((power_off_pin.unwrap_or(PLAY_PAUSE_PIN), HOLD_TIME));
+        PinConfig { buttons, rotary_encoder: Some((CLK_PIN, DT_PIN)), long_press }
+    }
+
+    /// Returns the handler and the dispatched actions, which are formatted as they have no equality
+    fn handler(config: PinConfig) -> (InputHandler, Arc<Mutex<Vec<String>>>) {
+        let actions = Arc::new(Mutex::new(vec![]));
+        let dispatched = actions.clone();
+        let on_action = move |action: Action| dispatched.lock().unwrap().push(format!("{:?}", action));
+        (InputHandler::new(config, Box::new(on_action)), actions)
+    }
+
+    /// Feeds the level changes, each given with its milliseconds after the start, and updates the handler in between
+    fn feed(handler: &mut InputHandler, start: Instant, events: &[(u64, u32, bool)]) {
+        for (millis, pin, active) in events {
+            let now = start + Duration::from_millis(*millis);
+            handler.update(now);
+            handler.on_event(PinEvent { pin: *pin, active: *active }, now);
+        }
+    }
+
+    fn update_at(handler: &mut InputHandler, start: Instant, millis: u64) {
+        handler.update(start + Duration::from_millis(millis));
+    }
+
+    fn take(actions: &Arc<Mutex<Vec<String>>>) -> Vec<String> {
+        std::mem::take(&mut *actions.lock().unwrap())
+    }
+
+    #[test]
+    fn toggles_play_on_short_press() {
+        let (mut handler, actions) = handler(config(Some(POWER_OFF_PIN)));
+        let start = Instant::now();
+
+        feed(&mut handler, start, &[(0, PLAY_PAUSE_PIN, true), (200, PLAY_PAUSE_PIN, false)]);
+        update_at(&mut handler, start, 300);
+
+        assert_eq!(take(&actions), vec!["TogglePlay"]);
+    }
+
+    #[test]
+    fn shuts_down_when_power_off_button_is_held() {
+        let (mut handler, actions) = handler(config(Some(POWER_OFF_PIN)));
+        let start = Instant::now();
+
+        feed(&mut handler, start, &[(0, POWER_OFF_PIN, true)]);
+        update_at(&mut handler, start, 1000);
+        assert!(take(&actions).is_empty());
+        update_at(&mut handler, start, 2100);
+        assert_eq!(take(&actions), vec!["Shutdown"]);
+
+        feed(&mut handler, start, &[(3000, POWER_OFF_PIN, false)]);
+        update_at(&mut handler, start, 3100);
+        assert!(take(&actions).is_empty());
+    }
+
+    #[test]
+    fn ignores_short_press_of_power_off_button() {
+        let (mut handler, actions) = handler(config(Some(POWER_OFF_PIN)));
+        let start = Instant::now();
+
+        feed(&mut handler, start, &[(0, POWER_OFF_PIN, true), (500, POWER_OFF_PIN, false)]);
+        update_at(&mut handler, start, 3000);
+
+        assert!(take(&actions).is_empty());
+    }
+
+    #[test]
+    fn holds_play_pause_to_shut_down_without_power_off_button() {
+        let (mut handler, actions) = handler(config(None));
+        let start = Instant::now();
+
+        feed(&mut handler, start, &[(0, PLAY_PAUSE_PIN, true)]);
+        update_at(&mut handler, start, 2100);
+        feed(&mut handler, start, &[(2500, PLAY_PAUSE_PIN, false)]);
+        update_at(&mut handler, start, 2600);
+
+        assert_eq!(take(&actions), vec!["Shutdown"]);
+    }
+
+    #[test]
+    fn counts_bouncing_press_and_release_once() {
+        let (mut handler, actions) = handler(config(None));
+        let start = Instant::now();
+
+        feed(
+            &mut handler,
+            start,
+            &[
+                (0, PLAY_PAUSE_PIN, true),
+                (3, PLAY_PAUSE_PIN, false),
+                (6, PLAY_PAUSE_PIN, true),
+                (300, PLAY_PAUSE_PIN, false),
+                (303, PLAY_PAUSE_PIN, true),
+                (306, PLAY_PAUSE_PIN, false),
+            ],
+        );
+        update_at(&mut handler, start, 400);
+
+        assert_eq!(take(&actions), vec!["TogglePlay"]);
+    }
+
+    #[test]
+    fn keeps_button_held_through_bouncing_contact() {
+        let (mut handler, actions) = handler(config(None));
+        let start = Instant::now();
+
+        // The contact opens briefly while the button is held
+        feed(
+            &mut handler,
+            start,
+            &[(0, PLAY_PAUSE_PIN, true), (1000, PLAY_PAUSE_PIN, false), (1010, PLAY_PAUSE_PIN, true)],
+        );
+        update_at(&mut handler, start, 2100);
+
+        assert_eq!(take(&actions), vec!["Shutdown"]);
+    }
+
+    #[test]
+    fn changes_volume_in_direction_of_rotary_encoder() {
+        let (mut handler, actions) = handler(config(None));
+        let start = Instant::now();
+
+        // Clockwise the clock pin changes first, counterclockwise the data pin does
+        feed(
+            &mut handler,
+            start,
+            &[
+                (0, CLK_PIN, true),
+                (5, DT_PIN, true),
+                (10, CLK_PIN, false),
+                (15, DT_PIN, false),
+                (100, DT_PIN, true),
+                (105, CLK_PIN, true),
+                (110, DT_PIN, false),
+                (115, CLK_PIN, false),
+            ],
+        );
+
+        assert_eq!(
+            take(&actions),
+            vec![format!("ChangeVolume({:?})", VOLUME_STEP), format!("ChangeVolume({:?})", -VOLUME_STEP)]
+        );
+    }
+
+    #[test]
+    fn reads_pin_changes_from_mock_file() {
+        let path = std::env::temp_dir().join(format!("gpio_mock_{}", std::process::id()));
+        let mut file = File::create(&path).unwrap();
+        writeln!(file, "{} 1\n99 1\n{} 0", PLAY_PAUSE_PIN, PLAY_PAUSE_PIN).unwrap();
+        drop(file);
+
+        let (sender, receiver) = channel();
+        MockBackend::new(path.to_str().unwrap()).watch(&[PLAY_PAUSE_PIN], sender).unwrap();
+        let events = receiver.iter().collect::<Vec<_>>();
+        std::fs::remove_file(&path).unwrap();
+
+        // Pins which are not watched are left out
+        assert_eq!(events.len(), 2);
+        let (mut handler, actions) = handler(config(Some(POWER_OFF_PIN)));
+        let start = Instant::now();
+        let timed_events = events
+            .iter()
+            .enumerate()
+            .map(|(index, event)| (index as u64 * 200, event.pin, event.active))
+            .collect::<Vec<_>>();
+        feed(&mut handler, start, &timed_events);
+        update_at(&mut handler, start, 300);
+
+        assert_eq!(take(&actions), vec!["TogglePlay"]);
+    }
+}
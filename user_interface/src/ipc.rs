@@ -0,0 +1,191 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::Mutex as AsyncMutex;
+use tracing::{debug, error, info, warn};
+
+use database::model::library_entry::RepeatMode;
+use player::Player;
+
+use crate::state::{Action, Dispatcher, State};
+
+/// Commands of the socket protocol. Every request is a single line of json, e.g. {"command":"seek","position":50}
+#[derive(Debug, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+enum Request {
+    State,
+    Play { library_entry_id: i32 },
+    Pause,
+    Resume,
+    Next,
+    Prev,
+    // Percent of the current track 0-100
+    Seek { position: f64 },
+    // 0-1
+    Volume { volume: f64 },
+    // Applies the audio effects stored by the admin interface
+    ReloadAudioConfig,
+}
+
+#[derive(Serialize)]
+struct Response {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    state: Option<PlayerStatus>,
+}
+
+#[derive(Serialize)]
+struct PlayerStatus {
+    playback_state: String,
+    paused: bool,
+    library_entry_id: Option<i32>,
+    parent_id: Option<i32>,
+    name: Option<String>,
+    // Percent of the current track 0-100
+    progress: f64,
+    // Live streams have no progress and can not be seeked
+    live: bool,
+    volume: f64,
+    shuffle: bool,
+    repeat_mode: RepeatMode,
+}
+
+/// Listens on the unix socket configured by PLAYER_SOCKET, so the admin interface can control the playback
+pub fn start(state: Arc<Mutex<State>>, dispatcher: Arc<Mutex<Dispatcher>>, player: Arc<AsyncMutex<Player>>) {
+    let path = PathBuf::from(std::env::var("PLAYER_SOCKET").unwrap_or("/tmp/tgb/player.sock".to_string()));
+    if let Some(folder) = path.parent() {
+        if let Err(error) = std::fs::create_dir_all(folder) {
+            error!("Could not create socket folder {:?}: {}", folder, error);
+            return;
+        }
+    }
+    // A socket left over by a previous run blocks binding
+    if path.exists() {
+        if let Err(error) = std::fs::remove_file(&path) {
+            error!("Could not remove stale socket {:?}: {}", path, error);
+            return;
+        }
+    }
+
+    let listener = match UnixListener::bind(&path) {
+        Ok(listener) => listener,
+        Err(error) => {
+            error!("Could not listen on socket {:?}: {}", path, error);
+            return;
+        }
+    };
+    info!("Listening for player commands on {:?}", path);
+
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    let state = state.clone();
+                    let dispatcher = dispatcher.clone();
+                    let player = player.clone();
+                    tokio::spawn(async move {
+                        if let Err(error) = handle_client(stream, state, dispatcher, player).await {
+                            debug!("Player socket client disconnected: {}", error);
+                        }
+                    });
+                }
+                Err(error) => warn!("Could not accept socket client: {}", error),
+            }
+        }
+    });
+}
+
+async fn handle_client(
+    stream: UnixStream,
+    state: Arc<Mutex<State>>,
+    dispatcher: Arc<Mutex<Dispatcher>>,
+    player: Arc<AsyncMutex<Player>>,
+) -> Result<(), std::io::Error> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => handle_request(request, &state, &dispatcher, &player).await,
+            Err(error) => Response { ok: false, error: Some(format!("invalid request: {}", error)), state: None },
+        };
+
+        let mut response = serde_json::to_string(&response).map_err(std::io::Error::other)?;
+        response.push('\n');
+        writer.write_all(response.as_bytes()).await?;
+    }
+
+    Ok(())
+}
+
+async fn handle_request(
+    request: Request,
+    state: &Arc<Mutex<State>>,
+    dispatcher: &Arc<Mutex<Dispatcher>>,
+    player: &Arc<AsyncMutex<Player>>,
+) -> Response {
+    debug!("Received player command {:?}", request);
+    let paused = state.lock().unwrap().paused;
+    let action = match request {
+        Request::State => {
+            let status = get_status(state, player).await;
+            return Response { ok: true, error: None, state: Some(status) };
+        }
+        Request::Play { library_entry_id } => {
+            let connection = state.lock().unwrap().connection.clone();
+            match Action::play_library_entry(&connection, library_entry_id).await {
+                Ok(action) => Some(action),
+                Err(error) => return Response { ok: false, error: Some(error), state: None },
+            }
+        }
+        Request::Pause => (!paused).then_some(Action::TogglePlay),
+        Request::Resume => paused.then_some(Action::TogglePlay),
+        Request::Next => Some(Action::NextTrack),
+        Request::Prev => Some(Action::PrevTrack),
+        Request::Seek { position } => Some(Action::Seek(position.clamp(0.0, 100.0))),
+        Request::Volume { volume } => Some(Action::SetVolume(volume.clamp(0.0, 1.0))),
+        Request::ReloadAudioConfig => {
+            return match player.lock().await.reload_audio_config().await {
+                Ok(_) => Response { ok: true, error: None, state: None },
+                Err(error) => Response { ok: false, error: Some(error), state: None },
+            };
+        }
+    };
+
+    if let Some(action) = action {
+        let dispatcher = dispatcher.lock().unwrap();
+        dispatcher.dispatch_action(Action::TrackActivity);
+        dispatcher.dispatch_action(action);
+    }
+    Response { ok: true, error: None, state: None }
+}
+
+async fn get_status(state: &Arc<Mutex<State>>, player: &Arc<AsyncMutex<Player>>) -> PlayerStatus {
+    let (playback_state, mode) = {
+        let player = player.lock().await;
+        (player.get_state(), player.get_playback_mode())
+    };
+    let state = state.lock().unwrap();
+    let track = state.playing_library_entry.as_ref();
+
+    PlayerStatus {
+        playback_state: format!("{:?}", playback_state).to_lowercase(),
+        paused: state.paused,
+        library_entry_id: track.map(|track| track.id),
+        parent_id: state.playing_parent_id,
+        name: track.map(|track| track.name.clone()),
+        progress: state.progress,
+        live: state.live_position.is_some(),
+        volume: state.volume.min(state.volume_limit),
+        shuffle: mode.shuffle,
+        repeat_mode: mode.repeat,
+    }
+}
@@ -5,20 +5,26 @@ use gtk4::gio::resources_register_include;
 use gtk4::prelude::{ApplicationExt, ApplicationExtManual};
 use gtk4::{glib, Application, CssProvider, IconTheme};
 use std::sync::{Arc, Mutex};
-use tracing::info;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{info, warn};
 use tracing::level_filters::LevelFilter;
 use tracing_subscriber::filter::Targets;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::Layer;
 
-use database::{connect, model::library_entry::Model as LibraryEntry};
-use player::{Player, Progress};
+use database::connect;
+use player::{Player, PlayerEvent};
 
 use crate::components::{Component, WindowComponent};
 use crate::state::{Action, Dispatcher, Event, EventHandler, State};
 
+mod card_reader;
 mod components;
+mod gpio_input;
+mod ipc;
+mod mpris;
+mod power;
 mod state;
 mod util;
 
@@ -46,28 +52,41 @@ async fn main() -> glib::ExitCode {
     let player = Player::new(connection.clone(), state.lock().unwrap().volume).await;
 
     {
-        let player = player.clone();
-        let dispatcher1 = dispatcher.clone();
-        let dispatcher2 = dispatcher.clone();
-        let dispatcher3 = dispatcher.clone();
+        let (mut events, volume_limit) = {
+            let player = player.lock().await;
+            (player.subscribe(), player.get_volume_limit())
+        };
+        dispatcher.lock().unwrap().dispatch_action(Action::SetVolumeLimit(volume_limit));
+        let dispatcher = dispatcher.clone();
         tokio::spawn(async move {
-            let mut player = player.lock().await;
-            let handle_progress_change = move |progress: Progress| {
-                dispatcher1.clone().lock().unwrap().dispatch_action(Action::SetProgress(progress.as_f64()));
-            };
-            let handle_track_change = move |library_entry: Option<LibraryEntry>| {
-                dispatcher2.lock().unwrap().dispatch_action(Action::SetPlayingTrack(library_entry));
-            };
-            let handle_track_end = move |_library_entry: LibraryEntry| {
-                dispatcher3.lock().unwrap().dispatch_action(Action::SetPlayedAt);
-            };
-
-            player.connect_progress_changed(handle_progress_change);
-            player.connect_track_changed(handle_track_change);
-            player.connect_track_ended(handle_track_end);
+            loop {
+                let action = match events.recv().await {
+                    Ok(PlayerEvent::StateChanged(playback_state)) => Action::SetPlaybackState(playback_state),
+                    Ok(PlayerEvent::TrackChanged(library_entry)) => Action::SetPlayingTrack(library_entry),
+                    Ok(PlayerEvent::TrackEnded(_library_entry)) => Action::SetPlayedAt,
+                    Ok(PlayerEvent::ProgressChanged(progress)) => Action::SetProgress(progress),
+                    Ok(PlayerEvent::StreamTitleChanged(library_entry_id, title)) => {
+                        Action::SetStreamTitle(library_entry_id, title)
+                    }
+                    Ok(PlayerEvent::SleepTimerChanged(sleep_timer)) => Action::SetSleepTimer(sleep_timer),
+                    Ok(PlayerEvent::VolumeLimitChanged(limit)) => Action::SetVolumeLimit(limit),
+                    Err(RecvError::Lagged(count)) => {
+                        warn!("Missed {} player events", count);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                dispatcher.lock().unwrap().dispatch_action(action);
+            }
         });
     }
 
+    card_reader::start(connection.clone(), dispatcher.clone());
+    gpio_input::start(connection.clone(), state.clone(), dispatcher.clone()).await;
+    ipc::start(state.clone(), dispatcher.clone(), player.clone());
+    mpris::start(state.clone(), dispatcher.clone(), player.clone());
+    power::start(state.clone(), dispatcher.clone());
+
     let handle = tokio::runtime::Handle::current();
     let thread = std::thread::spawn(move || {
         handle.block_on(async {
@@ -0,0 +1,303 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::Mutex as AsyncMutex;
+use tracing::{error, info, warn};
+use zbus::connection::Builder;
+use zbus::object_server::InterfaceRef;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+use zbus::Connection;
+
+use database::model::library_entry::Model as LibraryEntry;
+use database::LibraryEntryRepository;
+use player::{get_track_id, PlaybackMode, PlaybackState, Player, PlayerEvent, Progress};
+
+use crate::mpris::media_player::MediaPlayerInterface;
+use crate::mpris::playback::PlayerInterface;
+use crate::mpris::track_list::TrackListInterface;
+use crate::state::{Action, Dispatcher, State};
+
+mod media_player;
+mod playback;
+mod track_list;
+
+const BUS_NAME: &str = "org.mpris.MediaPlayer2.tinyghettobox";
+const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+const NO_TRACK: &str = "/org/mpris/MediaPlayer2/TrackList/NoTrack";
+// Jumps of the progress bigger than this are reported as seeks
+const SEEK_THRESHOLD: Duration = Duration::from_secs(2);
+
+/// Last known state of the player, so properties can be read without waiting for the player lock
+#[derive(Default)]
+struct Snapshot {
+    playback_state: PlaybackState,
+    track: Option<LibraryEntry>,
+    parent: Option<LibraryEntry>,
+    cover_url: Option<String>,
+    progress: Option<Progress>,
+    progress_updated_at: Option<Instant>,
+    queue: Vec<LibraryEntry>,
+}
+
+#[derive(Clone)]
+struct Context {
+    state: Arc<Mutex<State>>,
+    dispatcher: Arc<Mutex<Dispatcher>>,
+    player: Arc<AsyncMutex<Player>>,
+    snapshot: Arc<Mutex<Snapshot>>,
+}
+
+impl Context {
+    fn dispatch(&self, action: Action) {
+        let dispatcher = self.dispatcher.lock().unwrap();
+        dispatcher.dispatch_action(Action::TrackActivity);
+        dispatcher.dispatch_action(action);
+    }
+
+    fn is_paused(&self) -> bool {
+        self.state.lock().unwrap().paused
+    }
+
+    fn get_volume(&self) -> f64 {
+        let state = self.state.lock().unwrap();
+        state.volume.min(state.volume_limit)
+    }
+
+    fn get_playback_mode(&self) -> PlaybackMode {
+        self.state.lock().unwrap().playback_mode
+    }
+
+    fn get_metadata(&self) -> HashMap<String, OwnedValue> {
+        let snapshot = self.snapshot.lock().unwrap();
+        match snapshot.track.as_ref() {
+            Some(track) => get_metadata(
+                track,
+                snapshot.parent.as_ref(),
+                snapshot.progress.as_ref().and_then(|progress| progress.duration),
+                snapshot.cover_url.clone(),
+            ),
+            None => HashMap::from([("mpris:trackid".to_string(), to_owned_value(no_track()))]),
+        }
+    }
+}
+
+/// Publishes the player as MPRIS media player on the bus configured by MPRIS_BUS (session, system or off)
+pub fn start(state: Arc<Mutex<State>>, dispatcher: Arc<Mutex<Dispatcher>>, player: Arc<AsyncMutex<Player>>) {
+    let bus = std::env::var("MPRIS_BUS").unwrap_or("session".to_string());
+    if bus == "off" {
+        return;
+    }
+
+    let context = Context { state, dispatcher, player, snapshot: Arc::new(Mutex::new(Snapshot::default())) };
+    tokio::spawn(async move {
+        let connection = match connect(&bus, context.clone()).await {
+            Ok(connection) => connection,
+            Err(error) => {
+                warn!("Could not publish MPRIS interface on {} bus: {}", bus, error);
+                return;
+            }
+        };
+        info!("Published MPRIS interface as {} on {} bus", BUS_NAME, bus);
+
+        if let Err(error) = forward_changes(&connection, context).await {
+            error!("Could not emit MPRIS changes: {}", error);
+        }
+    });
+}
+
+async fn connect(bus: &str, context: Context) -> zbus::Result<Connection> {
+    let builder = match bus {
+        "system" => Builder::system()?,
+        _ => Builder::session()?,
+    };
+
+    builder
+        .name(BUS_NAME)?
+        .serve_at(OBJECT_PATH, MediaPlayerInterface)?
+        .serve_at(OBJECT_PATH, PlayerInterface::new(context.clone()))?
+        .serve_at(OBJECT_PATH, TrackListInterface::new(context))?
+        .build()
+        .await
+}
+
+/// Emits the changes of the player and the state as signals, so clients do not need to poll the properties
+async fn forward_changes(connection: &Connection, context: Context) -> zbus::Result<()> {
+    let player_ref = connection.object_server().interface::<_, PlayerInterface>(OBJECT_PATH).await?;
+    let track_list_ref = connection.object_server().interface::<_, TrackListInterface>(OBJECT_PATH).await?;
+    let mut events = context.player.lock().await.subscribe();
+    // Volume and playback mode are changed by actions without player events, so they are compared periodically
+    let mut interval = tokio::time::interval(Duration::from_secs(1));
+    let mut volume = context.get_volume();
+    let mut playback_mode = context.get_playback_mode();
+
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => on_player_event(event, &context, &player_ref, &track_list_ref).await?,
+                Err(RecvError::Lagged(count)) => warn!("MPRIS missed {} player events", count),
+                Err(RecvError::Closed) => break,
+            },
+            _ = interval.tick() => {
+                let emitter = player_ref.signal_emitter();
+                let new_volume = context.get_volume();
+                if new_volume != volume {
+                    volume = new_volume;
+                    player_ref.get().await.volume_changed(emitter).await?;
+                }
+                let new_mode = context.get_playback_mode();
+                if new_mode.shuffle != playback_mode.shuffle {
+                    player_ref.get().await.shuffle_changed(emitter).await?;
+                }
+                if new_mode.repeat != playback_mode.repeat {
+                    player_ref.get().await.loop_status_changed(emitter).await?;
+                }
+                playback_mode = new_mode;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn on_player_event(
+    event: PlayerEvent,
+    context: &Context,
+    player_ref: &InterfaceRef<PlayerInterface>,
+    track_list_ref: &InterfaceRef<TrackListInterface>,
+) -> zbus::Result<()> {
+    let emitter = player_ref.signal_emitter();
+    match event {
+        PlayerEvent::StateChanged(playback_state) => {
+            context.snapshot.lock().unwrap().playback_state = playback_state;
+            player_ref.get().await.playback_status_changed(emitter).await?;
+        }
+        PlayerEvent::TrackChanged(track) => {
+            let queue = context.player.lock().await.get_queue();
+            let connection = context.state.lock().unwrap().connection.clone();
+            let parent = match track.as_ref().and_then(|track| track.parent_id) {
+                Some(parent_id) => {
+                    LibraryEntryRepository::get_flat(&connection, parent_id).await.unwrap_or_else(|error| {
+                        warn!("Could not load folder of playing track: {}", error);
+                        None
+                    })
+                }
+                None => None,
+            };
+            let cover_url = track.as_ref().and_then(|track| write_cover(track, parent.as_ref()));
+
+            let queue_changed = {
+                let mut snapshot = context.snapshot.lock().unwrap();
+                let queue_changed = !is_same_queue(&snapshot.queue, &queue);
+                *snapshot = Snapshot {
+                    playback_state: snapshot.playback_state,
+                    track,
+                    parent,
+                    cover_url,
+                    queue,
+                    ..Default::default()
+                };
+                queue_changed
+            };
+            player_ref.get().await.metadata_changed(emitter).await?;
+            if queue_changed {
+                let (tracks, current) = track_list_ref.get().await.get_tracks_and_current();
+                TrackListInterface::track_list_replaced(track_list_ref.signal_emitter(), tracks, current).await?;
+            }
+        }
+        PlayerEvent::ProgressChanged(progress) => {
+            let (seeked, duration_changed) = {
+                let mut snapshot = context.snapshot.lock().unwrap();
+                let seeked = match (snapshot.progress.as_ref(), snapshot.progress_updated_at) {
+                    (Some(last), Some(updated_at)) => {
+                        let elapsed = match snapshot.playback_state.is_active() {
+                            true => updated_at.elapsed(),
+                            false => Duration::ZERO,
+                        };
+                        (last.position + elapsed).abs_diff(progress.position) > SEEK_THRESHOLD
+                    }
+                    _ => false,
+                };
+                let duration_changed = snapshot.progress.as_ref().map(|last| last.duration) != Some(progress.duration);
+                snapshot.progress = Some(progress.clone());
+                snapshot.progress_updated_at = Some(Instant::now());
+                (seeked, duration_changed)
+            };
+            if seeked {
+                PlayerInterface::seeked(emitter, progress.position.as_micros() as i64).await?;
+            }
+            if duration_changed {
+                let player_interface = player_ref.get().await;
+                player_interface.metadata_changed(emitter).await?;
+                player_interface.can_seek_changed(emitter).await?;
+            }
+        }
+        // The volume limit is covered by the periodic volume comparison
+        PlayerEvent::TrackEnded(_)
+        | PlayerEvent::StreamTitleChanged(..)
+        | PlayerEvent::SleepTimerChanged(_)
+        | PlayerEvent::VolumeLimitChanged(_) => {}
+    }
+
+    Ok(())
+}
+
+fn is_same_queue(current: &[LibraryEntry], new: &[LibraryEntry]) -> bool {
+    current.len() == new.len()
+        && current.iter().zip(new).all(|(current, new)| get_track_id(current) == get_track_id(new))
+}
+
+fn track_path(track: &LibraryEntry) -> OwnedObjectPath {
+    // Ids of Spotify tracks consist of letters and digits, so they are valid in paths
+    ObjectPath::try_from(format!("/org/tinyghettobox/track/{}", get_track_id(track)))
+        .expect("track path is valid")
+        .into()
+}
+
+fn no_track() -> OwnedObjectPath {
+    ObjectPath::from_static_str_unchecked(NO_TRACK).into()
+}
+
+fn get_metadata(
+    track: &LibraryEntry,
+    parent: Option<&LibraryEntry>,
+    length: Option<Duration>,
+    cover_url: Option<String>,
+) -> HashMap<String, OwnedValue> {
+    let mut metadata = HashMap::new();
+    metadata.insert("mpris:trackid".to_string(), to_owned_value(track_path(track)));
+    metadata.insert("xesam:title".to_string(), to_owned_value(track.name.as_str()));
+    if let Some(parent) = parent {
+        metadata.insert("xesam:album".to_string(), to_owned_value(parent.name.as_str()));
+    }
+    if let Some(length) = length {
+        metadata.insert("mpris:length".to_string(), to_owned_value(length.as_micros() as i64));
+    }
+    if let Some(cover_url) = cover_url {
+        metadata.insert("mpris:artUrl".to_string(), to_owned_value(cover_url.as_str()));
+    }
+    metadata
+}
+
+fn to_owned_value<'a>(value: impl Into<Value<'a>>) -> OwnedValue {
+    value.into().try_into().expect("metadata contains no file descriptors")
+}
+
+/// Writes the image of the track or its folder to the cache, as MPRIS clients expect an url to the cover
+fn write_cover(track: &LibraryEntry, parent: Option<&LibraryEntry>) -> Option<String> {
+    let entry = [Some(track), parent].into_iter().flatten().find(|entry| entry.image.is_some())?;
+    let folder = PathBuf::from(std::env::var("CACHE").unwrap_or("/tmp/tgb".to_string())).join("covers");
+    let path = folder.join(entry.id.to_string());
+
+    let image = entry.image.as_deref().unwrap_or_default();
+    let result = std::fs::create_dir_all(&folder).and_then(|_| std::fs::write(&path, image));
+    match result {
+        Ok(_) => Some(format!("file://{}", path.display())),
+        Err(error) => {
+            warn!("Could not write cover of {}: {}", entry.id, error);
+            None
+        }
+    }
+}
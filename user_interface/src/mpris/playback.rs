@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::{ObjectPath, OwnedValue};
+use zbus::{fdo, interface};
+
+use database::model::library_entry::RepeatMode;
+use player::PlaybackState;
+
+use crate::mpris::{track_path, Context};
+use crate::state::Action;
+
+/// Playback control of MPRIS. All commands are dispatched as actions, so the display stays in sync.
+pub(super) struct PlayerInterface {
+    context: Context,
+}
+
+impl PlayerInterface {
+    pub(super) fn new(context: Context) -> Self {
+        Self { context }
+    }
+
+    /// Seeks to the position within the current track. Positions after its end skip to the next track.
+    fn seek_to(&self, position: Duration) -> fdo::Result<()> {
+        let duration = self.context.snapshot.lock().unwrap().progress.as_ref().map(|progress| progress.duration);
+        match duration {
+            Some(Some(duration)) if position > duration => self.context.dispatch(Action::NextTrack),
+            Some(Some(duration)) if !duration.is_zero() => {
+                let percent = position.as_secs_f64() / duration.as_secs_f64() * 100.0;
+                self.context.dispatch(Action::Seek(percent));
+            }
+            Some(None) => return Err(fdo::Error::NotSupported("live streams can not be seeked".to_string())),
+            _ => return Err(fdo::Error::Failed("no track is playing".to_string())),
+        }
+        Ok(())
+    }
+}
+
+#[interface(name = "org.mpris.MediaPlayer2.Player")]
+impl PlayerInterface {
+    fn next(&self) {
+        self.context.dispatch(Action::NextTrack);
+    }
+
+    fn previous(&self) {
+        self.context.dispatch(Action::PrevTrack);
+    }
+
+    fn pause(&self) {
+        if !self.context.is_paused() {
+            self.context.dispatch(Action::TogglePlay);
+        }
+    }
+
+    fn play_pause(&self) {
+        self.context.dispatch(Action::TogglePlay);
+    }
+
+    // Stopping is not supported by the player, pausing keeps the position at least
+    fn stop(&self) {
+        self.pause();
+    }
+
+    fn play(&self) {
+        if self.context.is_paused() {
+            self.context.dispatch(Action::TogglePlay);
+        }
+    }
+
+    /// Moves the position by the offset in microseconds
+    fn seek(&self, offset: i64) -> fdo::Result<()> {
+        let position = self.context.snapshot.lock().unwrap().progress.as_ref().map(|progress| progress.position);
+        let position = position.unwrap_or_default().as_micros() as i64 + offset;
+        self.seek_to(Duration::from_micros(position.max(0) as u64))
+    }
+
+    fn set_position(&self, track_id: ObjectPath<'_>, position: i64) -> fdo::Result<()> {
+        let track_id_matches =
+            self.context.snapshot.lock().unwrap().track.as_ref().map(track_path) == Some(track_id.into());
+        // Requests for other tracks or negative positions are to be ignored according to the specification
+        if !track_id_matches || position < 0 {
+            return Ok(());
+        }
+        self.seek_to(Duration::from_micros(position as u64))
+    }
+
+    fn open_uri(&self, _uri: String) -> fdo::Result<()> {
+        Err(fdo::Error::NotSupported("playing uris is not supported".to_string()))
+    }
+
+    #[zbus(signal)]
+    pub(super) async fn seeked(emitter: &SignalEmitter<'_>, position: i64) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn playback_status(&self) -> String {
+        match self.context.snapshot.lock().unwrap().playback_state {
+            PlaybackState::Stopped | PlaybackState::Ended => "Stopped",
+            PlaybackState::Paused => "Paused",
+            PlaybackState::Loading | PlaybackState::Playing | PlaybackState::Buffering => "Playing",
+        }
+        .to_string()
+    }
+
+    #[zbus(property)]
+    fn loop_status(&self) -> String {
+        match self.context.get_playback_mode().repeat {
+            RepeatMode::Off => "None",
+            RepeatMode::One => "Track",
+            RepeatMode::All => "Playlist",
+        }
+        .to_string()
+    }
+
+    #[zbus(property)]
+    fn set_loop_status(&mut self, loop_status: String) -> zbus::Result<()> {
+        let repeat = match loop_status.as_str() {
+            "None" => RepeatMode::Off,
+            "Track" => RepeatMode::One,
+            "Playlist" => RepeatMode::All,
+            _ => return Err(fdo::Error::InvalidArgs(format!("unknown loop status {}", loop_status)).into()),
+        };
+        let mut playback_mode = self.context.get_playback_mode();
+        playback_mode.repeat = repeat;
+        self.context.dispatch(Action::SetPlaybackMode(playback_mode));
+        Ok(())
+    }
+
+    #[zbus(property)]
+    fn rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn minimum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn maximum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn shuffle(&self) -> bool {
+        self.context.get_playback_mode().shuffle
+    }
+
+    #[zbus(property)]
+    fn set_shuffle(&mut self, shuffle: bool) {
+        let mut playback_mode = self.context.get_playback_mode();
+        playback_mode.shuffle = shuffle;
+        self.context.dispatch(Action::SetPlaybackMode(playback_mode));
+    }
+
+    #[zbus(property)]
+    fn metadata(&self) -> HashMap<String, OwnedValue> {
+        self.context.get_metadata()
+    }
+
+    #[zbus(property)]
+    fn volume(&self) -> f64 {
+        self.context.get_volume()
+    }
+
+    #[zbus(property)]
+    fn set_volume(&mut self, volume: f64) {
+        self.context.dispatch(Action::SetVolume(volume.clamp(0.0, 1.0)));
+    }
+
+    // Changes constantly, so clients are expected to poll it instead of receiving signals
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn position(&self) -> i64 {
+        let snapshot = self.context.snapshot.lock().unwrap();
+        snapshot.progress.as_ref().map(|progress| progress.position.as_micros() as i64).unwrap_or(0)
+    }
+
+    #[zbus(property)]
+    fn can_go_next(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_go_previous(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_play(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_pause(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_seek(&self) -> bool {
+        !self.context.snapshot.lock().unwrap().progress.as_ref().is_some_and(|progress| progress.is_live())
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn can_control(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue};
+use zbus::{fdo, interface};
+
+use crate::mpris::{get_metadata, no_track, track_path, Context};
+use crate::state::Action;
+
+/// Exposes the queue of the player. Tracks can only be jumped to, editing the queue is done in the library.
+pub(super) struct TrackListInterface {
+    context: Context,
+}
+
+impl TrackListInterface {
+    pub(super) fn new(context: Context) -> Self {
+        Self { context }
+    }
+
+    pub(super) fn get_tracks_and_current(&self) -> (Vec<OwnedObjectPath>, OwnedObjectPath) {
+        let snapshot = self.context.snapshot.lock().unwrap();
+        let tracks = snapshot.queue.iter().map(track_path).collect();
+        let current = snapshot.track.as_ref().map(track_path).unwrap_or_else(no_track);
+        (tracks, current)
+    }
+}
+
+#[interface(name = "org.mpris.MediaPlayer2.TrackList")]
+impl TrackListInterface {
+    fn get_tracks_metadata(&self, track_ids: Vec<OwnedObjectPath>) -> Vec<HashMap<String, OwnedValue>> {
+        let snapshot = self.context.snapshot.lock().unwrap();
+        track_ids
+            .iter()
+            .filter_map(|track_id| snapshot.queue.iter().find(|track| track_path(track) == *track_id))
+            .map(|track| get_metadata(track, snapshot.parent.as_ref(), None, None))
+            .collect()
+    }
+
+    fn add_track(&self, _uri: String, _after_track: ObjectPath<'_>, _set_as_current: bool) -> fdo::Result<()> {
+        Err(fdo::Error::NotSupported("the track list can not be edited".to_string()))
+    }
+
+    fn remove_track(&self, _track_id: ObjectPath<'_>) -> fdo::Result<()> {
+        Err(fdo::Error::NotSupported("the track list can not be edited".to_string()))
+    }
+
+    fn go_to(&self, track_id: ObjectPath<'_>) -> fdo::Result<()> {
+        let track_id = OwnedObjectPath::from(track_id);
+        // The tracks of a Spotify album share its library entry, so they are only told apart by their position
+        let position =
+            self.context.snapshot.lock().unwrap().queue.iter().position(|track| track_path(track) == track_id);
+        match position {
+            Some(position) => {
+                self.context.dispatch(Action::PlayQueued(position));
+                Ok(())
+            }
+            None => Err(fdo::Error::InvalidArgs("track is not in the track list".to_string())),
+        }
+    }
+
+    #[zbus(signal)]
+    pub(super) async fn track_list_replaced(
+        emitter: &SignalEmitter<'_>,
+        tracks: Vec<OwnedObjectPath>,
+        current_track: OwnedObjectPath,
+    ) -> zbus::Result<()>;
+
+    #[zbus(property(emits_changed_signal = "invalidates"))]
+    fn tracks(&self) -> Vec<OwnedObjectPath> {
+        self.get_tracks_and_current().0
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn can_edit_tracks(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,140 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use tracing::{info, warn};
+
+use database::model::system_config::Model as SystemConfig;
+use database::{DatabaseConnection, SystemConfigRepository};
+
+use crate::state::{Action, Dispatcher, State};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+// The admin interface changes the system config, so it is read again periodically
+const RELOAD_INTERVAL: Duration = Duration::from_secs(10);
+// Seconds the display is dimmed before it switches off, so it can be kept on by touching it
+const DIM_TIME: i64 = 15;
+// Share of the configured brightness while the display is dimmed
+const DIM_FACTOR: f64 = 0.3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct PowerConfig {
+    // Seconds without activity, none if disabled
+    display_off: Option<i64>,
+    idle_shutdown: Option<i64>,
+    // Percent 0-100
+    brightness: i32,
+}
+
+impl PowerConfig {
+    fn new(config: &SystemConfig) -> Self {
+        let seconds = |minutes: i32| (minutes > 0).then_some(minutes as i64 * 60);
+        Self {
+            display_off: seconds(config.display_off_timer),
+            idle_shutdown: seconds(config.idle_shutdown_timer),
+            brightness: config.display_brightness.clamp(0, 100),
+        }
+    }
+
+    fn get_brightness(&self, display: Display) -> i32 {
+        match display {
+            Display::Dimmed => (self.brightness as f64 * DIM_FACTOR).round() as i32,
+            _ => self.brightness,
+        }
+    }
+}
+
+impl Default for PowerConfig {
+    fn default() -> Self {
+        Self { display_off: Some(2 * 60), idle_shutdown: Some(5 * 60), brightness: 100 }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Display {
+    Bright,
+    Dimmed,
+    Off,
+}
+
+/// Dims and switches off the display and shuts the box down after the idle times of the system config.
+/// Playback counts as activity for the shutdown, while the display goes off during playback as well.
+pub fn start(state: Arc<Mutex<State>>, dispatcher: Arc<Mutex<Dispatcher>>) {
+    let connection = state.lock().unwrap().connection.clone();
+
+    tokio::spawn(async move {
+        let mut config = load_config(&connection).await.unwrap_or_default();
+        let mut loaded_at = Instant::now();
+        let mut display = Display::Bright;
+        let mut last_playback = Utc::now().timestamp();
+        let mut shutting_down = false;
+        info!("Using power settings {:?}", config);
+        dispatcher.lock().unwrap().dispatch_action(Action::SetBrightness(config.brightness));
+
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            if loaded_at.elapsed() >= RELOAD_INTERVAL {
+                loaded_at = Instant::now();
+                if let Some(new_config) = load_config(&connection).await.filter(|new_config| *new_config != config) {
+                    info!("Power settings changed to {:?}", new_config);
+                    if new_config.brightness != config.brightness && display != Display::Off {
+                        let brightness = new_config.get_brightness(display);
+                        dispatcher.lock().unwrap().dispatch_action(Action::SetBrightness(brightness));
+                    }
+                    config = new_config;
+                }
+            }
+
+            let (last_activity, paused, monitor_active) = {
+                let state = state.lock().unwrap();
+                (state.last_activity, state.paused, state.monitor_active)
+            };
+            let now = Utc::now().timestamp();
+            if !paused {
+                last_playback = now;
+            }
+
+            // A display which is off stays off until it is touched
+            if monitor_active {
+                let idle = now - last_activity;
+                let target = match config.display_off {
+                    Some(display_off) if idle >= display_off => Display::Off,
+                    Some(display_off) if idle >= display_off - DIM_TIME => Display::Dimmed,
+                    _ => Display::Bright,
+                };
+                if target != display {
+                    let action = match target {
+                        Display::Off => Action::ToggleMonitor(false),
+                        _ => Action::SetBrightness(config.get_brightness(target)),
+                    };
+                    display = target;
+                    dispatcher.lock().unwrap().dispatch_action(action);
+                }
+            }
+
+            if let Some(idle_shutdown) = config.idle_shutdown {
+                if !shutting_down && now - last_activity.max(last_playback) >= idle_shutdown {
+                    info!("Shutting down after {} seconds without activity", idle_shutdown);
+                    shutting_down = true;
+                    dispatcher.lock().unwrap().dispatch_action(Action::Shutdown);
+                }
+            }
+        }
+    });
+}
+
+async fn load_config(connection: &DatabaseConnection) -> Option<PowerConfig> {
+    match SystemConfigRepository::get(connection).await {
+        Ok(Some(config)) => Some(PowerConfig::new(&config)),
+        Ok(None) => {
+            warn!("No system config found for power settings");
+            None
+        }
+        Err(error) => {
+            warn!("Could not load power settings: {}", error);
+            None
+        }
+    }
+}
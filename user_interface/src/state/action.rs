@@ -1,16 +1,21 @@
 use chrono::Utc;
 use std::process::Command;
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::Mutex as AsyncMutex;
 use tracing::{debug, error, info, warn};
 
-use database::model::library_entry::Variant;
-use database::{model::library_entry::Model as LibraryEntry, LibraryEntryRepository, SystemConfigRepository};
-use player::{Player, Progress};
+use database::model::library_entry::{RepeatMode, Variant};
+use database::{
+    model::library_entry::Model as LibraryEntry, DatabaseConnection, LibraryEntryRepository, SystemConfigRepository,
+};
+use player::{PlaybackMode, PlaybackState, Player, Progress, Queue, SleepTimer};
 
 use crate::state::{Dispatcher, State};
 
+// Minutes of the sleep timer, if none are configured
+const DEFAULT_SLEEP_TIMER: u64 = 30;
+
 #[derive(Debug)]
 pub enum Action {
     Started,
@@ -19,12 +24,26 @@ pub enum Action {
     TogglePlay,
     NextTrack,
     PrevTrack,
+    PlayQueued(usize), // Position in the order of the queue
+    ToggleShuffle,
+    ToggleRepeat,
+    SetPlaybackMode(PlaybackMode),
     SetPlayedAt,
     Seek(f64),
-    SetProgress(f64), // 0-1
+    SetProgress(Progress),
     SetPlayingTrack(Option<LibraryEntry>),
+    SetStreamTitle(i32, String), // Library entry id and song title
+    SetPlaybackState(PlaybackState),
     SetVolume(f64),
+    SetVolumeLimit(f64),
+    ChangeVolume(f64), // Difference to the current volume
+    SetSleepTimer(Option<SleepTimer>),
+    ExtendSleepTimer,
+    SleepAfterTrack,
+    SleepAtEndOfFolder,
+    CancelSleepTimer,
     ToggleMonitor(bool),
+    SetBrightness(i32), // Percent 0-100
     Shutdown,
     TrackActivity,
 }
@@ -36,7 +55,10 @@ pub enum Event {
     ProgressChanged,
     TrackPlayed,
     TrackChanged,
+    StreamTitleChanged,
     VolumeChanged,
+    PlaybackModeChanged,
+    SleepTimerChanged,
     MonitorToggled,
     Error(String),
     Dummy,
@@ -48,16 +70,26 @@ pub trait EventHandler {
 }
 
 impl Action {
-    pub async fn process<P, T, E>(
+    /// Returns the action playing the library entry. Folders are played completely, tracks within their folder.
+    pub async fn play_library_entry(connection: &DatabaseConnection, library_entry_id: i32) -> Result<Action, String> {
+        let entry = LibraryEntryRepository::get_flat(connection, library_entry_id)
+            .await
+            .map_err(|error| format!("could not load library entry {}: {}", library_entry_id, error))?
+            .ok_or(format!("library entry {} does not exist", library_entry_id))?;
+
+        match (entry.variant, entry.parent_id) {
+            (Variant::Folder | Variant::Podcast, _) => Ok(Action::Play(entry.id, None)),
+            (_, Some(parent_id)) => Ok(Action::Play(parent_id, Some(entry.id))),
+            (_, None) => Err(format!("library entry {} has no folder", library_entry_id)),
+        }
+    }
+
+    pub async fn process(
         action: Action,
         state: Arc<Mutex<State>>,
         dispatcher: Arc<Mutex<Dispatcher>>,
-        player: Arc<AsyncMutex<Player<P, T, E>>>,
-    ) where
-        P: Fn(Progress) + 'static + Sync + Send,
-        T: Fn(Option<LibraryEntry>) + 'static + Sync + Send,
-        E: Fn(LibraryEntry) + 'static + Sync + Send,
-    {
+        player: Arc<AsyncMutex<Player>>,
+    ) {
         match action {
             Action::Started => {
                 state.lock().unwrap().started = true;
@@ -85,7 +117,10 @@ impl Action {
                             Some(variants) => {
                                 if variants.len() == 0 {
                                     state.active_view = "empty_info".to_string();
-                                } else if variants.contains(&Variant::Folder) || variants.contains(&Variant::Stream) {
+                                } else if variants.contains(&Variant::Folder)
+                                    || variants.contains(&Variant::Podcast)
+                                    || variants.contains(&Variant::Stream)
+                                {
                                     state.active_view = "tile_list".to_string();
                                 } else {
                                     state.active_view = "detail_list".to_string();
@@ -103,18 +138,28 @@ impl Action {
             }
             Action::Play(parent_id, start_id) => {
                 let connection = state.lock().unwrap().connection.clone();
+                let playback_mode = match LibraryEntryRepository::get_flat(&connection, parent_id).await {
+                    Ok(parent) => parent.as_ref().map(PlaybackMode::from).unwrap_or_default(),
+                    Err(error) => {
+                        warn!("Could not load playback mode of '{}': {}", parent_id, error);
+                        PlaybackMode::default()
+                    }
+                };
+                {
+                    let mut state = state.lock().unwrap();
+                    state.playing_parent_id = Some(parent_id);
+                    state.playback_mode = playback_mode;
+                }
+                dispatcher.lock().unwrap().dispatch_event(Event::PlaybackModeChanged);
+
                 let event = match LibraryEntryRepository::get_tracks_in_parent(&connection, parent_id).await {
                     Ok(library_entries) => {
-                        let queue = if let Some(start_id) = start_id {
-                            library_entries.into_iter().skip_while(|entry| entry.id != start_id).collect()
-                        } else {
-                            library_entries.into_iter().collect()
-                        };
+                        let queue = Queue::from_tracks(library_entries, start_id, playback_mode);
                         debug!("playing queue: {}", queue);
 
                         match player.lock().await.play_queue(queue).await {
                             Ok(Some(_)) => {
-                                // Handled by on_track_change triggering SetPlayingTrack
+                                // Handled by the TrackChanged player event triggering SetPlayingTrack
                                 None
                             }
                             Ok(None) => Some(Event::Error("Did not play anything".to_string())),
@@ -156,31 +201,39 @@ impl Action {
                 state.playing_library_entry = library_entry.clone();
                 state.paused = library_entry.is_none();
                 state.progress = 0.0;
+                state.live_position = None;
 
                 dispatcher.lock().unwrap().dispatch_event(Event::TrackChanged);
             }
+            Action::SetStreamTitle(library_entry_id, title) => {
+                state.lock().unwrap().stream_title = Some((library_entry_id, title));
+                dispatcher.lock().unwrap().dispatch_event(Event::StreamTitleChanged);
+            }
+            Action::SetPlaybackState(playback_state) => {
+                {
+                    let mut state = state.lock().unwrap();
+                    state.paused = !playback_state.is_playing();
+                    state.buffering = matches!(playback_state, PlaybackState::Buffering);
+                }
+                dispatcher.lock().unwrap().dispatch_event(Event::PlayStateChanged);
+            }
             Action::TogglePlay => {
                 let result = if state.lock().unwrap().paused {
                     player.lock().await.resume().await
                 } else {
                     player.lock().await.pause().await
                 };
-                let event = match result {
-                    Ok(_) => {
-                        let mut state = state.lock().unwrap();
-                        state.paused = !state.paused;
-                        Event::PlayStateChanged
-                    }
-                    Err(error) => Event::Error(error),
-                };
-                dispatcher.lock().unwrap().dispatch_event(event);
+                // On success the StateChanged player event triggers SetPlaybackState
+                if let Err(error) = result {
+                    dispatcher.lock().unwrap().dispatch_event(Event::Error(error));
+                }
             }
-            Action::NextTrack | Action::PrevTrack => {
+            Action::NextTrack | Action::PrevTrack | Action::PlayQueued(_) => {
                 let mut player = player.lock().await;
-                let result = if matches!(action, Action::NextTrack) {
-                    player.play_next_track().await
-                } else {
-                    player.play_prev_track().await
+                let result = match action {
+                    Action::NextTrack => player.play_next_track().await,
+                    Action::PlayQueued(position) => player.play_queued_track(position).await,
+                    _ => player.play_prev_track().await,
                 };
                 let event = match result {
                     Ok(new_track) => {
@@ -190,12 +243,14 @@ impl Action {
                                 state.playing_library_entry = Some(track.clone());
                                 state.paused = false;
                                 state.progress = 0.0;
+                                state.live_position = None;
                                 Event::TrackChanged
                             }
                             None => {
                                 state.playing_library_entry = None;
                                 state.paused = true;
                                 state.progress = 0.0;
+                                state.live_position = None;
                                 Event::PlayStateChanged
                             }
                         }
@@ -204,8 +259,45 @@ impl Action {
                 };
                 dispatcher.lock().unwrap().dispatch_event(event);
             }
+            Action::ToggleShuffle | Action::ToggleRepeat | Action::SetPlaybackMode(_) => {
+                let mut playback_mode = state.lock().unwrap().playback_mode;
+                match action {
+                    Action::ToggleShuffle => playback_mode.shuffle = !playback_mode.shuffle,
+                    Action::ToggleRepeat => {
+                        playback_mode.repeat = match playback_mode.repeat {
+                            RepeatMode::Off => RepeatMode::All,
+                            RepeatMode::All => RepeatMode::One,
+                            RepeatMode::One => RepeatMode::Off,
+                        }
+                    }
+                    Action::SetPlaybackMode(mode) => playback_mode = mode,
+                    _ => unreachable!(),
+                }
+                player.lock().await.set_playback_mode(playback_mode);
+
+                let (connection, parent_id) = {
+                    let mut state = state.lock().unwrap();
+                    state.playback_mode = playback_mode;
+                    (state.connection.clone(), state.playing_parent_id)
+                };
+                // Remember the mode for the folder, so it is used the next time the folder is played
+                if let Some(parent_id) = parent_id {
+                    let PlaybackMode { shuffle, repeat } = playback_mode;
+                    if let Err(error) =
+                        LibraryEntryRepository::set_playback_mode(&connection, parent_id, shuffle, repeat).await
+                    {
+                        error!("Could not save playback mode of '{}': {}", parent_id, error);
+                    }
+                }
+
+                dispatcher.lock().unwrap().dispatch_event(Event::PlaybackModeChanged);
+            }
             Action::SetProgress(progress) => {
-                state.lock().unwrap().progress = progress;
+                {
+                    let mut state = state.lock().unwrap();
+                    state.progress = progress.as_f64();
+                    state.live_position = progress.is_live().then_some(progress.position);
+                }
                 dispatcher.lock().unwrap().dispatch_event(Event::ProgressChanged);
             }
             Action::Seek(percent) => {
@@ -227,7 +319,7 @@ impl Action {
                     tokio::time::sleep(Duration::from_secs(2)).await;
                 }
                 let event = match player.lock().await.set_volume(volume).await {
-                    Ok(_) => {
+                    Ok(volume) => {
                         let connection = state.lock().unwrap().connection.clone();
                         match SystemConfigRepository::set_volume(&connection, (volume * 100.0) as u8).await {
                             Ok(_) => {
@@ -242,6 +334,64 @@ impl Action {
 
                 dispatcher.lock().unwrap().dispatch_event(event);
             }
+            Action::SetVolumeLimit(limit) => {
+                state.lock().unwrap().volume_limit = limit;
+                dispatcher.lock().unwrap().dispatch_event(Event::VolumeChanged);
+            }
+            Action::ChangeVolume(difference) => {
+                // The state is updated right away, so following changes add up before the volume is set
+                let volume = {
+                    let mut state = state.lock().unwrap();
+                    state.volume = (state.volume.min(state.volume_limit) + difference).clamp(0.0, state.volume_limit);
+                    state.volume
+                };
+                dispatcher.lock().unwrap().dispatch_action(Action::SetVolume(volume));
+            }
+            Action::SetSleepTimer(sleep_timer) => {
+                state.lock().unwrap().sleep_timer = sleep_timer;
+                dispatcher.lock().unwrap().dispatch_event(Event::SleepTimerChanged);
+            }
+            Action::ExtendSleepTimer
+            | Action::SleepAfterTrack
+            | Action::SleepAtEndOfFolder
+            | Action::CancelSleepTimer => {
+                let (connection, current) = {
+                    let state = state.lock().unwrap();
+                    (state.connection.clone(), state.sleep_timer)
+                };
+                let sleep_timer = match action {
+                    Action::ExtendSleepTimer => {
+                        let minutes = match SystemConfigRepository::get(&connection).await {
+                            Ok(Some(config)) if config.sleep_timer > 0 => config.sleep_timer as u64,
+                            Ok(_) => DEFAULT_SLEEP_TIMER,
+                            Err(error) => {
+                                warn!("Could not load sleep timer duration: {}", error);
+                                DEFAULT_SLEEP_TIMER
+                            }
+                        };
+                        // A running timer is extended, otherwise it starts now
+                        let start = match current {
+                            Some(SleepTimer::Until(end)) => end.max(Instant::now()),
+                            _ => Instant::now(),
+                        };
+                        Some(SleepTimer::Until(start + Duration::from_secs(minutes * 60)))
+                    }
+                    Action::SleepAfterTrack => Some(SleepTimer::Tracks(1)),
+                    Action::SleepAtEndOfFolder => {
+                        Some(SleepTimer::Tracks(player.lock().await.count_remaining_tracks()))
+                    }
+                    Action::CancelSleepTimer => None,
+                    _ => unreachable!(),
+                };
+
+                // On success the SleepTimerChanged player event triggers SetSleepTimer
+                if let Err(error) = player.lock().await.set_sleep_timer(sleep_timer).await {
+                    dispatcher
+                        .lock()
+                        .unwrap()
+                        .dispatch_event(Event::Error(format!("Could not set sleep timer: {}", error)));
+                }
+            }
             Action::ToggleMonitor(active) => {
                 let mut state = state.lock().expect("could not lock");
 
@@ -257,6 +407,14 @@ impl Action {
                 state.monitor_active = active;
                 dispatcher.lock().unwrap().dispatch_event(Event::MonitorToggled);
             }
+            Action::SetBrightness(brightness) => {
+                if cfg!(target_arch = "arm") {
+                    debug!("Setting display brightness to {}%", brightness);
+                    if let Err(error) = set_brightness(brightness) {
+                        error!("Could not set display brightness: {}", error);
+                    }
+                }
+            }
             Action::Shutdown => {
                 if cfg!(target_arch = "arm") {
                     info!("Shutting down");
@@ -271,6 +429,17 @@ impl Action {
     }
 }
 
+/// Writes the brightness to the backlight configured by BACKLIGHT, scaled to its maximum brightness
+fn set_brightness(brightness: i32) -> Result<(), std::io::Error> {
+    let backlight = std::env::var("BACKLIGHT").unwrap_or("/sys/class/backlight/rpi_backlight".to_string());
+    let max_brightness = std::fs::read_to_string(format!("{}/max_brightness", backlight))?
+        .trim()
+        .parse::<i32>()
+        .map_err(std::io::Error::other)?;
+    let value = max_brightness * brightness.clamp(0, 100) / 100;
+    std::fs::write(format!("{}/brightness", backlight), value.to_string())
+}
+
 impl Event {
     pub fn broadcast(event: Event, listener: Arc<Mutex<Box<dyn EventHandler>>>) {
         debug!("Handling event {:?}", event);
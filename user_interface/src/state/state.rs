@@ -1,6 +1,8 @@
 use chrono::Utc;
 use database::model::library_entry::Model as LibraryEntry;
 use database::{DatabaseConnection, LibraryEntryRepository, SystemConfigRepository};
+use player::{PlaybackMode, SleepTimer};
+use std::time::Duration;
 
 pub struct State {
     pub started: bool,
@@ -8,9 +10,21 @@ pub struct State {
     pub library_entry: LibraryEntry,
     pub active_view: String,
     pub playing_library_entry: Option<LibraryEntry>,
+    // Song an internet radio announced last, with the id of its library entry
+    pub stream_title: Option<(i32, String)>,
+    // Folder the playing queue was created from
+    pub playing_parent_id: Option<i32>,
+    pub playback_mode: PlaybackMode,
     pub paused: bool,
+    // Remote track waits for its connection to come back
+    pub buffering: bool,
     pub progress: f64,
+    // Time a live stream is playing, which has no progress
+    pub live_position: Option<Duration>,
     pub volume: f64,
+    // Highest volume the volume policy of the player allows right now
+    pub volume_limit: f64,
+    pub sleep_timer: Option<SleepTimer>,
     pub monitor_active: bool,
     pub last_activity: i64,
 }
@@ -34,9 +48,16 @@ impl State {
             library_entry,
             active_view,
             volume,
+            volume_limit: 1.0,
             playing_library_entry: None,
+            stream_title: None,
+            playing_parent_id: None,
+            playback_mode: PlaybackMode::default(),
             paused: true,
+            buffering: false,
             progress: 0.0,
+            live_position: None,
+            sleep_timer: None,
             started: false,
             monitor_active: true,
             last_activity: Utc::now().timestamp(),